@@ -20,6 +20,8 @@ fn generate_man_page(out_dir: &str) -> io::Result<ExitStatus> {
     command
         .args(["-b", "manpage"])
         .args(["-a", concat!("revnumber=", env!("CARGO_PKG_VERSION"))]);
+    #[cfg(feature = "clipboard")]
+    command.args(["-a", "clipboard"]);
     #[cfg(feature = "decode-from-bmp")]
     command.args(["-a", "decode-from-bmp"]);
     #[cfg(feature = "decode-from-dds")]
@@ -52,6 +54,10 @@ fn generate_man_page(out_dir: &str) -> io::Result<ExitStatus> {
     command.args(["-a", "optimize-output-png"]);
     #[cfg(feature = "output-as-ansi")]
     command.args(["-a", "output-as-ansi"]);
+    #[cfg(feature = "output-as-avif")]
+    command.args(["-a", "output-as-avif"]);
+    #[cfg(feature = "output-with-title")]
+    command.args(["-a", "output-with-title"]);
     command
         .args(["-D", out_dir])
         .arg(man_dir.join("*.1.adoc"))
@@ -5,154 +5,873 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use std::{
-    fs::{self, File},
-    io::{self, BufReader, Cursor, Read, Write},
-    num::NonZeroU32,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Cursor, IsTerminal, Read, Write},
+    num::{NonZeroU32, NonZeroU64},
+    path::{Path, PathBuf},
 };
 
-use anyhow::Context;
-use clap::Parser;
-use image::{imageops, ImageFormat};
-use qrcode::{bits::Bits, QrCode};
-use rqrr::PreparedImage;
+use anyhow::{anyhow, Context};
+use clap::{error::ErrorKind, parser::ValueSource, FromArgMatches, ValueEnum};
+use image::{codecs::png::PngEncoder, imageops, DynamicImage, ImageFormat};
+use qrcode::{types::QrError, Version};
+use csscolorparser::Color;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
 use crate::{
-    cli::{Command, Opt, OutputFormat},
+    benchmark,
+    cli::{
+        self, BitDepth, CapacityFormat, Command, Escape, EstimateFormat, InputCharset,
+        InputEncoding, InputFormat, MarginUnit, Mirror, ModuleSize, Opt, OutputFormat, Preprocess,
+        SvgBackground, SvgOptimize, SymbolVersion, Threshold,
+    },
+    color,
+    config::Config,
     decode, encode,
+    encode::{Ecc, Mode, Segment, SvgSize, Variant},
+    exit_code::ExitStatus,
     input::Input,
-    metadata::Extractor,
+    matrix::{Matrix, Modules},
+    metadata::{Codewords, Extractor},
+    structured_append, verify, watchdog,
 };
 
 const MAX_DATA_SIZE: u64 = 7089;
 
+/// Prints the metadata or a warning to stderr, or to stdout if the result is
+/// output to stderr.
+macro_rules! diagnostic {
+    ($to_stdout:expr, $($arg:tt)*) => {
+        if $to_stdout {
+            println!($($arg)*);
+        } else {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// The capacity of a symbol, which is output as JSON.
+#[derive(Serialize)]
+struct SymbolCapacity {
+    variant: Variant,
+    version: i16,
+    mode: Mode,
+    level: Ecc,
+    capacity: usize,
+}
+
+/// The estimated version, which is output as JSON.
+#[derive(Serialize)]
+struct Estimate {
+    fits: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variant: Option<Variant>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<i16>,
+    level: Ecc,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codewords: Option<Codewords>,
+}
+
+/// Returns the path which has the mask pattern appended to the file stem.
+fn mask_file_name(path: &Path, mask: usize) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!("-mask{mask}"));
+    if let Some(ext) = path.extension() {
+        name.push(".");
+        name.push(ext);
+    }
+    path.with_file_name(name)
+}
+
+/// Returns the file name derived from the hash of the input data.
+fn file_name(data: &[u8], format: &OutputFormat) -> PathBuf {
+    let hash = Sha256::digest(data);
+    let hash = u64::from_be_bytes(hash[..8].try_into().expect("hash should be 32 bytes"));
+    PathBuf::from(format!("{hash:016x}")).with_extension(format.extension())
+}
+
+/// Writes the output to the open file descriptor.
+///
+/// The file descriptor is opened through `/dev/fd`, so this only works on Unix.
+fn write_to_fd(fd: u32, output: &[u8]) -> io::Result<()> {
+    let path = PathBuf::from(format!("/dev/fd/{fd}"));
+    OpenOptions::new().write(true).open(path)?.write_all(output)
+}
+
+/// Reads the input data from the string, the files or standard input.
+///
+/// At most one byte more than the maximum data size is read, so that too long
+/// data is rejected when constructing a QR code.
+fn read_input(input: Option<String>, read_from: &[PathBuf]) -> anyhow::Result<Vec<u8>> {
+    let input = if let Some(string) = input {
+        Input::String(Cursor::new(string))
+    } else if !read_from.is_empty() {
+        let files = read_from
+            .iter()
+            .map(|path| {
+                File::open(path).with_context(|| format!("could not open {}", path.display()))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Input::Files(files)
+    } else {
+        Input::Stdin(io::stdin())
+    };
+    let reader = BufReader::new(input);
+    let mut buf = Vec::new();
+    reader
+        .take(MAX_DATA_SIZE + 1)
+        .read_to_end(&mut buf)
+        .context("could not read data")?;
+    Ok(buf)
+}
+
+/// Reads the version number of the symbol from the first line of standard
+/// input, and the input data from the rest.
+fn read_version_from_stdin() -> anyhow::Result<(i16, Vec<u8>)> {
+    let mut reader = BufReader::new(io::stdin());
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .context("could not read the symbol version from standard input")?;
+    let version = cli::parse_symbol_version_number(line.trim()).unwrap_or_else(|err| {
+        clap::Error::raw(
+            ErrorKind::ValueValidation,
+            format!(
+                "invalid symbol version {:?} in standard input: {err}\n",
+                line.trim()
+            ),
+        )
+        .exit()
+    });
+    let mut buf = Vec::new();
+    reader
+        .take(MAX_DATA_SIZE + 1)
+        .read_to_end(&mut buf)
+        .context("could not read data")?;
+    Ok((version, buf))
+}
+
+/// Prints the names and the descriptions of the formats.
+fn print_formats<T: ValueEnum>() -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    for format in T::value_variants()
+        .iter()
+        .filter_map(ValueEnum::to_possible_value)
+        .filter(|format| !format.is_hide_set())
+    {
+        let help = format
+            .get_help()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        writeln!(stdout, "{} {help}", format.get_name())
+            .context("could not write the formats to stdout")?;
+    }
+    Ok(())
+}
+
 /// Runs the program and returns the result.
 #[allow(clippy::too_many_lines)]
 pub fn run() -> anyhow::Result<()> {
-    let opt = Opt::parse().validate()?;
+    let matches = Opt::command_with_palette()?.get_matches();
+    let mut opt = Opt::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if let (Some(Command::Encode(ref mut arg)), Some(("encode", matches))) =
+        (&mut opt.command, matches.subcommand())
+    {
+        if let Some(ref path) = arg.output {
+            if let Some(format) = OutputFormat::from_path(path) {
+                if matches.value_source("output_format") != Some(ValueSource::CommandLine) {
+                    arg.output_format = format;
+                } else if format != arg.output_format && !arg.quiet {
+                    diagnostic!(
+                        arg.output_on_stderr,
+                        "Warning: the output format does not match the extension of {}",
+                        path.display()
+                    );
+                }
+            }
+        }
+        let path = if arg.no_config {
+            None
+        } else {
+            arg.config
+                .clone()
+                .or_else(|| Config::default_path().filter(|p| p.is_file()))
+        };
+        if let Some(path) = path {
+            let config = Config::from_file(path)?;
+            arg.apply_config(config.encode, matches);
+        }
+        arg.apply_env(matches)?;
+        if let Some(opacity) = arg.foreground_opacity {
+            arg.foreground = color::with_opacity(&arg.foreground, opacity);
+        }
+        if let Some(opacity) = arg.background_opacity {
+            arg.background = color::with_opacity(&arg.background, opacity);
+        }
+        let ratio = color::contrast_ratio(&arg.foreground, &arg.background);
+        let is_transparent = arg.transparent || color::is_transparent(&arg.background);
+        if ratio < color::MIN_CONTRAST_RATIO
+            && !is_transparent
+            && !arg.output_format.is_monochrome()
+        {
+            if arg.strict_contrast {
+                clap::Error::raw(
+                    ErrorKind::ValueValidation,
+                    format!(
+                        "the foreground color has low contrast with the background ({ratio:.2}:1, less than {}:1)\n",
+                        color::MIN_CONTRAST_RATIO
+                    ),
+                )
+                .exit();
+            } else if !arg.quiet {
+                diagnostic!(
+                    arg.output_on_stderr,
+                    "Warning: the foreground color has low contrast with the background, which may make the QR code hard to scan"
+                );
+            }
+        }
+        if let Some(ref color) = arg.finder_color {
+            if color::contrast_ratio(color, &arg.background) < color::MIN_CONTRAST_RATIO
+                && !arg.quiet
+            {
+                diagnostic!(
+                    arg.output_on_stderr,
+                    "Warning: the finder color has low contrast with the background, which may make the QR code hard to scan"
+                );
+            }
+        }
+        if is_transparent {
+            if arg.output_format.supports_transparency() {
+                arg.background = Color::new(0.0, 0.0, 0.0, 0.0);
+            } else if !arg.quiet {
+                diagnostic!(
+                    arg.output_on_stderr,
+                    "Warning: the output format does not support transparency"
+                );
+            }
+        }
+    }
+    let opt = opt.validate()?;
 
     if let Some(shell) = opt.generate_completion {
         Opt::print_completion(shell);
         return Ok(());
     }
 
+    if opt.print_exit_codes {
+        let mut stdout = io::stdout().lock();
+        for status in ExitStatus::ALL {
+            writeln!(stdout, "{} {}", status.code(), status.description())
+                .context("could not write the exit statuses to stdout")?;
+        }
+        return Ok(());
+    }
+
     if let Some(command) = opt.command {
         match command {
             Command::Encode(arg) => {
-                let input = if let Some(string) = arg.input {
-                    Input::String(Cursor::new(string))
-                } else if let Some(ref path) = arg.read_from {
-                    let f = File::open(path)
-                        .with_context(|| format!("could not open {}", path.display()))?;
-                    Input::File(f)
-                } else {
-                    Input::Stdin(io::stdin())
-                };
-                let reader = BufReader::new(input);
-                let mut buf = Vec::new();
-                reader
-                    .take(MAX_DATA_SIZE + 1)
-                    .read_to_end(&mut buf)
-                    .context("could not read data")?;
-
-                let level = arg.error_correction_level.into();
-                let code = if let Some(version) = arg.symbol_version {
-                    let v = encode::set_version(version, &arg.variant)
-                        .context("could not set the version")?;
-                    let mut bits = Bits::new(v);
-                    if let Some(mode) = arg.mode {
-                        encode::push_data_for_selected_mode(&mut bits, buf, &mode)
-                    } else {
-                        bits.push_optimal_data(&buf)
+                let (symbol_version, stdin_data) = match arg.symbol_version {
+                    Some(SymbolVersion::Number(version)) => (Some(version), None),
+                    Some(SymbolVersion::Stdin) => {
+                        let (version, data) = read_version_from_stdin()?;
+                        (Some(version), Some(data))
                     }
-                    .and_then(|()| bits.push_terminator(level))
-                    .and_then(|()| QrCode::with_bits(bits, level))
-                } else {
-                    QrCode::with_error_correction_level(&buf, level)
+                    None => (None, None),
+                };
+                let (variant, mode) = (Variant::from(arg.variant), arg.mode.map(Mode::from));
+                if arg.list_colors {
+                    let mut stdout = io::stdout().lock();
+                    for (name, color) in color::named_colors() {
+                        writeln!(stdout, "{name} {}", color.to_css_hex())
+                            .context("could not write the named colors to stdout")?;
+                    }
+                    return Ok(());
                 }
-                .context("could not construct a QR code")?;
 
-                if arg.verbose {
-                    let metadata = code.metadata();
-                    eprintln!("Version: {}", metadata.symbol_version());
-                    eprintln!("Level: {:?}", metadata.error_correction_level());
+                if arg.list_formats {
+                    return print_formats::<OutputFormat>();
                 }
 
-                let margin =
-                    arg.margin
-                        .unwrap_or_else(|| if code.version().is_micro() { 2 } else { 4 });
-                let module_size = arg.size.map(NonZeroU32::get);
+                if arg.capacity {
+                    let (version, mode) = symbol_version
+                        .zip(mode)
+                        .expect("symbol version and mode should be specified with capacity");
+                    let level = arg
+                        .error_correction_level
+                        .to_ecc()
+                        .expect("error correction level should not be automatic");
+                    let capacity = encode::set_version(version, &variant)
+                        .and_then(|v| encode::capacity(v, &mode, level.into()))
+                        .context("could not compute the capacity")?;
+                    match arg.format {
+                        CapacityFormat::Text => println!("{capacity}"),
+                        CapacityFormat::Json => {
+                            let capacity = SymbolCapacity {
+                                variant,
+                                version,
+                                mode,
+                                level,
+                                capacity,
+                            };
+                            println!(
+                                "{}",
+                                serde_json::to_string(&capacity)
+                                    .context("could not serialize the capacity")?
+                            );
+                        }
+                    }
+                    return Ok(());
+                }
+
+                let module_shapes = (arg.module_shape, arg.finder_shape);
+                let has_circle_modules = arg.has_circle_modules();
+                let (code, output_file, masks, expected) = if let Some(path) = arg.from_matrix {
+                    let matrix = fs::read_to_string(&path)
+                        .with_context(|| {
+                            format!("could not read the matrix from {}", path.display())
+                        })?
+                        .parse::<Matrix>()
+                        .with_context(|| {
+                            format!("could not parse the matrix in {}", path.display())
+                        })?;
+                    (matrix, arg.output, None, None)
+                } else {
+                    let mut buf = if let Some(data) = stdin_data {
+                        data
+                    } else if arg.segment.is_empty() {
+                        read_input(arg.input, &arg.read_from)?
+                    } else {
+                        arg.segment
+                            .iter()
+                            .map(Segment::data)
+                            .collect::<Vec<_>>()
+                            .concat()
+                    };
+                    if let Some(encoding) = arg.input_encoding {
+                        buf = match encoding {
+                            InputEncoding::Hex => encode::from_hex(&buf)
+                                .ok_or_else(|| "invalid hexadecimal data".to_owned()),
+                            InputEncoding::Base64 => encode::from_base64(&buf)
+                                .map_err(|err| format!("invalid base64 data: {err}")),
+                        }
+                        .unwrap_or_else(|err| {
+                            clap::Error::raw(ErrorKind::ValueValidation, format!("{err}\n")).exit()
+                        });
+                    }
+                    if arg.prefix.is_some() || arg.suffix.is_some() {
+                        buf = [
+                            arg.prefix.as_deref().unwrap_or_default().as_bytes(),
+                            &buf,
+                            arg.suffix.as_deref().unwrap_or_default().as_bytes(),
+                        ]
+                        .concat();
+                    }
+                    let output_file = arg.output.or_else(|| {
+                        arg.output_dir
+                            .as_ref()
+                            .map(|dir| dir.join(file_name(&buf, &arg.output_format)))
+                    });
+                    if arg.input_charset == Some(InputCharset::Utf8) {
+                        buf = encode::to_shift_jis(&buf)
+                            .context("could not convert the data to Shift JIS")?;
+                    }
+
+                    let version = symbol_version
+                        .map(|version| encode::set_version(version, &variant))
+                        .transpose()
+                        .context("could not set the version")?;
+                    let min_level = arg.min_ecc.map_or(Ecc::L, Ecc::from).into();
+                    let to_qr_code = || match (arg.error_correction_level.to_ecc(), version) {
+                        _ if !arg.segment.is_empty() => encode::to_qr_code_with_segments(
+                            &arg.segment,
+                            symbol_version.map_or_else(
+                                || arg.min_version.unwrap_or(1)..=arg.max_version.unwrap_or(40),
+                                |version| version..=version,
+                            ),
+                            arg.error_correction_level.to_ecc().map(Into::into),
+                            min_level,
+                            !arg.no_eci,
+                        ),
+                        (Some(level), _) if arg.optimize_version => {
+                            encode::to_qr_code_with_min_version(
+                                &buf,
+                                &variant,
+                                mode.as_ref(),
+                                level.into(),
+                            )
+                        }
+                        (Some(level), _)
+                            if arg.min_version.is_some() || arg.max_version.is_some() =>
+                        {
+                            encode::to_qr_code_in_versions(
+                                &buf,
+                                arg.min_version.unwrap_or(1)..=arg.max_version.unwrap_or(40),
+                                &variant,
+                                mode.as_ref(),
+                                level.into(),
+                            )
+                        }
+                        (Some(level), None)
+                            if variant == Variant::Auto || mode.is_some() =>
+                        {
+                            encode::to_qr_code_with_min_version(
+                                &buf,
+                                &variant,
+                                mode.as_ref(),
+                                level.into(),
+                            )
+                        }
+                        (Some(level), version) => {
+                            encode::to_qr_code(&buf, version, mode.as_ref(), level.into())
+                        }
+                        (None, Some(version)) => encode::to_qr_code_with_max_level(
+                            &buf,
+                            version,
+                            mode.as_ref(),
+                            min_level,
+                        ),
+                        (None, None) => unreachable!(),
+                    };
+                    let code = to_qr_code()
+                        .map_err(anyhow::Error::from)
+                        .map_err(|err| {
+                            match (mode.as_ref(), encode::find_invalid_kanji(&buf)) {
+                                (Some(&Mode::Kanji), Some(offset)) => err.context(format!(
+                                    "invalid kanji character at byte offset {offset}"
+                                )),
+                                _ => err,
+                            }
+                        })
+                        .context("could not construct a QR code")?;
+                    if let Some(count) = arg.benchmark {
+                        let durations = benchmark::measure(count, to_qr_code)
+                            .context("could not construct a QR code")?;
+                        diagnostic!(arg.output_on_stderr, "Construct: {durations}");
+                    }
+
+                    if arg.verbose || arg.dry_run || arg.metadata_json {
+                        let codewords = if arg.segment.is_empty() {
+                            encode::codewords(&buf, &code, mode.as_ref())
+                        } else {
+                            encode::segments_codewords(&arg.segment, &code, !arg.no_eci)
+                        };
+                        let metadata = codewords.map_or_else(
+                            |_| code.metadata(),
+                            |(used, total)| code.metadata().with_codewords(used, total),
+                        );
+                        if arg.metadata_json {
+                            diagnostic!(arg.output_on_stderr, "{}", metadata.to_json());
+                        } else {
+                            if variant == Variant::Auto {
+                                let variant = if code.version().is_micro() {
+                                    Variant::Micro
+                                } else {
+                                    Variant::Normal
+                                };
+                                diagnostic!(
+                                    arg.output_on_stderr,
+                                    "Variant: {}",
+                                    format!("{variant:?}").to_lowercase()
+                                );
+                            }
+                            diagnostic!(
+                                arg.output_on_stderr,
+                                "Version: {}",
+                                metadata.symbol_version()
+                            );
+                            diagnostic!(
+                                arg.output_on_stderr,
+                                "Level: {:?}",
+                                metadata.error_correction_level()
+                            );
+                            diagnostic!(arg.output_on_stderr, "Mask: {}", metadata.mask_pattern());
+                            diagnostic!(arg.output_on_stderr, "Width: {}", metadata.width());
+                            if let Some(Codewords { used, total }) = metadata.codewords() {
+                                diagnostic!(
+                                    arg.output_on_stderr,
+                                    "Capacity: {used}/{total} codewords ({}%)",
+                                    used * 100 / total
+                                );
+                            }
+                        }
+                    }
+                    if arg.dry_run {
+                        return Ok(());
+                    }
+                    let masks = if arg.dump_all_masks {
+                        let masks =
+                            encode::to_matrices_with_all_masks(&buf, &code, mode.as_ref())
+                                .context("could not construct a QR code")?;
+                        if arg.verbose {
+                            for (i, mask) in masks.iter().enumerate() {
+                                diagnostic!(
+                                    arg.output_on_stderr,
+                                    "Mask {i}: penalty {}",
+                                    encode::penalty_score(mask)
+                                );
+                            }
+                        }
+                        Some(masks)
+                    } else {
+                        None
+                    };
+                    (
+                        Matrix::from(&code),
+                        output_file,
+                        masks,
+                        arg.verify.then_some(buf),
+                    )
+                };
+
+                let (module_size, module_percent) = match arg.size {
+                    Some(ModuleSize::Pixels(size)) => (Some(size.get()), None),
+                    Some(ModuleSize::Percent(percent)) => (None, Some(percent.get())),
+                    None => (None, None),
+                };
+                let quiet_zone = if code.is_micro() { 2 } else { 4 };
+                let margin = match (arg.margin, arg.margin_unit) {
+                    (Some(margin), MarginUnit::Pixels) => margin.div_ceil(
+                        module_size.unwrap_or_else(|| arg.output_format.default_module_size()),
+                    ),
+                    (margin, _) => margin.unwrap_or(quiet_zone),
+                };
+                let pad = arg.pad.zip(arg.physical_size).zip(arg.dpi).map(
+                    |((alignment, length), dpi)| (alignment, encode::to_pixels(length, dpi.get())),
+                );
+                let module_size = match (arg.physical_size, arg.dpi) {
+                    (Some(length), Some(dpi)) => {
+                        let modules = u32::try_from(code.width()).unwrap_or(u32::MAX) + 2 * margin;
+                        let size = match pad {
+                            Some((_, pixels)) => NonZeroU32::new(pixels / modules),
+                            None => encode::fit_module_size(length, dpi.get(), modules),
+                        }
+                        .ok_or_else(|| {
+                            anyhow!("the physical size is too small for the resolution")
+                        })?;
+                        Some(size.get())
+                    }
+                    _ => module_size,
+                };
+                if margin < quiet_zone && !arg.allow_small_margin && !arg.quiet {
+                    diagnostic!(
+                        arg.output_on_stderr,
+                        "Warning: the margin is smaller than the recommended quiet zone of {quiet_zone} modules, which may make the QR code hard to scan"
+                    );
+                }
                 let is_invert = matches!(
                     arg.output_format,
                     OutputFormat::AsciiInvert | OutputFormat::UnicodeInvert
                 );
-                let output = match arg.output_format {
-                    OutputFormat::Png => {
-                        let image = encode::to_image(
-                            &code,
+                let to_rgba_image = |code: &Matrix| {
+                    #[cfg(feature = "output-with-title")]
+                    let mut image = encode::to_image_with_title(
+                        code,
+                        margin,
+                        &(arg.foreground.clone(), arg.background.clone()),
+                        module_size,
+                        arg.title.as_deref().unwrap_or_default(),
+                    );
+                    #[cfg(not(feature = "output-with-title"))]
+                    let mut image = encode::to_image(
+                        code,
+                        margin,
+                        &(arg.foreground.clone(), arg.background.clone()),
+                        module_size,
+                    );
+                    if let Some(ref color) = arg.quiet_zone_color {
+                        encode::paint_quiet_zone(&mut image, code, margin, module_size, color);
+                    }
+                    if let Some(ref color) = arg.finder_color {
+                        encode::paint_finder_patterns(&mut image, code, margin, module_size, color);
+                    }
+                    if has_circle_modules {
+                        encode::paint_module_shapes(
+                            &mut image,
+                            code,
                             margin,
-                            &(arg.foreground, arg.background),
                             module_size,
+                            &arg.background,
+                            module_shapes,
                         );
-                        let mut buf = Vec::new();
-                        image
-                            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
-                            .context("could not write the image to the buffer")?;
+                    }
+                    if arg.debug_overlay {
+                        encode::paint_debug_overlay(&mut image, code, margin, module_size);
+                    }
+                    image
+                };
+                let tile_image = |image| match arg.repeat {
+                    Some(grid) => encode::tile_image(&image, grid, arg.gap, &arg.background)
+                        .context("the tiled image is too large"),
+                    None => Ok(image),
+                };
+                let render = |code: &Matrix| -> anyhow::Result<Vec<u8>> {
+                    let output = match arg.output_format {
+                        OutputFormat::Png => {
+                            let image = if arg.bit_depth == BitDepth::Sixteen {
+                                let image = encode::to_luma16_image(
+                                    code,
+                                    margin,
+                                    &(arg.foreground.clone(), arg.background.clone()),
+                                    module_size,
+                                );
+                                DynamicImage::ImageLuma16(image)
+                            } else {
+                                DynamicImage::ImageRgba8(to_rgba_image(code))
+                            };
+                            let image = tile_image(image)?;
+                            let image = match pad {
+                                Some((alignment, pixels)) => {
+                                    encode::pad_image(image, pixels, &arg.background, alignment)
+                                }
+                                None => image,
+                            };
+                            let compression = arg.png_compression.unwrap_or_default();
+                            #[cfg_attr(not(feature = "optimize-output-png"), allow(unused_mut))]
+                            let mut buf = if arg.bilevel {
+                                encode::to_bilevel_png(
+                                    &image.to_luma8(),
+                                    compression.into(),
+                                    arg.png_filter.map(Into::into),
+                                )
+                                .context("could not write the image to the buffer")?
+                            } else {
+                                let mut buf = Vec::new();
+                                let encoder = PngEncoder::new_with_quality(
+                                    &mut buf,
+                                    compression.into(),
+                                    arg.png_filter.unwrap_or_default().into(),
+                                );
+                                image
+                                    .write_with_encoder(encoder)
+                                    .context("could not write the image to the buffer")?;
+                                buf
+                            };
 
-                        #[cfg(feature = "optimize-output-png")]
-                        if let Some(level) = arg.optimize_png {
-                            let mut optimize_opt = oxipng::Options::from_preset(level.into());
-                            if let Some(iterations) = arg.zopfli {
-                                optimize_opt.deflate = oxipng::Deflaters::Zopfli { iterations };
+                            #[cfg(feature = "optimize-output-png")]
+                            if let Some(level) = arg.optimize_png {
+                                let mut optimize_opt = oxipng::Options::from_preset(level.into());
+                                // Keep the 16-bit depth even if it can be reduced losslessly.
+                                optimize_opt.bit_depth_reduction =
+                                    arg.bit_depth != BitDepth::Sixteen;
+                                if let Some(iterations) = arg.zopfli {
+                                    optimize_opt.deflate = oxipng::Deflaters::Zopfli { iterations };
+                                }
+                                buf = oxipng::optimize_from_memory(&buf, &optimize_opt)
+                                    .context("could not optimize the image")?;
+                            }
+                            let buf = match arg.dpi {
+                                Some(dpi) => encode::set_png_dpi(&buf, dpi.get()),
+                                None => buf,
+                            };
+                            if arg.nearest_neighbor_only {
+                                let image =
+                                    image::load_from_memory_with_format(&buf, ImageFormat::Png)
+                                        .context("could not read the encoded image")?;
+                                if !encode::has_hard_edges(&image.to_rgba16(), module_size) {
+                                    return Err(anyhow!(
+                                        "the modules of the encoded image are not hard-edged"
+                                    ));
+                                }
                             }
-                            buf = oxipng::optimize_from_memory(&buf, &optimize_opt)
-                                .context("could not optimize the image")?;
+                            buf
+                        }
+                        #[cfg(feature = "output-as-avif")]
+                        OutputFormat::Avif => {
+                            use image::codecs::avif::AvifEncoder;
+
+                            let mut buf = Vec::new();
+                            // The encoder does not support lossless encoding, so use the
+                            // highest quality to keep the edges of modules as sharp as
+                            // possible.
+                            let encoder = AvifEncoder::new_with_speed_quality(&mut buf, 8, 100);
+                            tile_image(to_rgba_image(code).into())?
+                                .write_with_encoder(encoder)
+                                .context("could not write the image to the buffer")?;
+                            buf
                         }
-                        buf
+                        OutputFormat::Svg => {
+                            let background = match arg.svg_background {
+                                SvgBackground::Color => arg.background.clone(),
+                                SvgBackground::None => Color::new(0.0, 0.0, 0.0, 0.0),
+                            };
+                            let colors = (arg.foreground.clone(), background);
+                            let mut svg =
+                                encode::to_svg_image(code, margin, &colors, module_size);
+                            let svg_optimize = match arg.svg_optimize {
+                                SvgOptimize::Auto => code.width() >= encode::OPTIMIZE_SVG_MIN_WIDTH,
+                                SvgOptimize::Always => true,
+                                SvgOptimize::Never => false,
+                            };
+                            if svg_optimize {
+                                encode::optimize_svg(&mut svg, code, margin, module_size);
+                            }
+                            if has_circle_modules {
+                                encode::paint_svg_module_shapes(
+                                    &mut svg,
+                                    code,
+                                    margin,
+                                    module_size,
+                                    module_shapes,
+                                );
+                            }
+                            if let Some(ref color) = arg.quiet_zone_color {
+                                encode::paint_svg_quiet_zone(
+                                    &mut svg,
+                                    code,
+                                    margin,
+                                    module_size,
+                                    color,
+                                );
+                            }
+                            if let Some(ref color) = arg.finder_color {
+                                encode::paint_svg_finder_patterns(
+                                    &mut svg,
+                                    code,
+                                    margin,
+                                    module_size,
+                                    color,
+                                    module_shapes.1,
+                                );
+                            }
+                            #[cfg(feature = "output-with-title")]
+                            let svg = encode::to_svg_with_title(
+                                &svg,
+                                &colors,
+                                module_size,
+                                arg.title.as_deref().unwrap_or_default(),
+                            );
+                            let mut svg = match arg.repeat {
+                                Some(grid) => {
+                                    encode::tile_svg(&svg, grid, arg.gap, &colors.1)
+                                        .context("the tiled image is too large")?
+                                }
+                                None => svg,
+                            };
+                            svg.crisp_edges |= arg.crisp_edges;
+                            let size = if arg.svg_viewbox {
+                                SvgSize::Responsive
+                            } else if let Some(percent) = module_percent {
+                                SvgSize::Percentage {
+                                    module_size: arg.output_format.default_module_size(),
+                                    percent,
+                                }
+                            } else {
+                                SvgSize::Pixels
+                            };
+                            svg.to_document(size, arg.svg_symbol.as_deref()).into()
+                        }
+                        OutputFormat::Pic => encode::to_pic(code, margin, module_size).into(),
+                        OutputFormat::Pbm => encode::to_pbm(code, margin, module_size),
+                        OutputFormat::CArray => {
+                            encode::to_c_array(code, margin, module_size).into()
+                        }
+                        OutputFormat::RustArray => {
+                            encode::to_rust_array(code, margin, module_size).into()
+                        }
+                        #[cfg(feature = "output-as-ansi")]
+                        OutputFormat::Ansi => encode::to_ansi(
+                            code,
+                            margin,
+                            &(arg.foreground.clone(), arg.background.clone()),
+                            module_size,
+                        )
+                        .into(),
+                        #[cfg(feature = "output-as-ansi")]
+                        OutputFormat::Ansi256 => encode::to_ansi_256(
+                            code,
+                            margin,
+                            &(arg.foreground.clone(), arg.background.clone()),
+                            module_size,
+                        )
+                        .into(),
+                        #[cfg(feature = "output-as-ansi")]
+                        OutputFormat::AnsiTrueColor => encode::to_ansi_true_color(
+                            code,
+                            margin,
+                            &(arg.foreground.clone(), arg.background.clone()),
+                            module_size,
+                        )
+                        .into(),
+                        OutputFormat::Ascii | OutputFormat::AsciiInvert => {
+                            encode::to_ascii(code, margin, module_size, is_invert).into()
+                        }
+                        OutputFormat::Unicode | OutputFormat::UnicodeInvert => {
+                            encode::to_unicode(code, margin, module_size, is_invert).into()
+                        }
+                    };
+                    Ok(output)
+                };
+                if let Some(count) = arg.benchmark {
+                    let durations = benchmark::measure(count, || render(&code))?;
+                    diagnostic!(arg.output_on_stderr, "Render: {durations}");
+                    return Ok(());
+                }
+                if let Some(masks) = masks {
+                    let file = output_file.expect("output file should be specified");
+                    if let Some(dir) = arg.output_dir {
+                        fs::create_dir_all(&dir)
+                            .with_context(|| format!("could not create {}", dir.display()))?;
                     }
-                    OutputFormat::Svg => encode::to_svg(
-                        &code,
-                        margin,
-                        &(arg.foreground, arg.background),
-                        module_size,
-                    )
-                    .into(),
-                    OutputFormat::Pic => encode::to_pic(&code, margin, module_size).into(),
-                    #[cfg(feature = "output-as-ansi")]
-                    OutputFormat::Ansi => encode::to_ansi(
-                        &code,
-                        margin,
-                        &(arg.foreground, arg.background),
-                        module_size,
-                    )
-                    .into(),
-                    #[cfg(feature = "output-as-ansi")]
-                    OutputFormat::Ansi256 => encode::to_ansi_256(
-                        &code,
-                        margin,
-                        &(arg.foreground, arg.background),
-                        module_size,
-                    )
-                    .into(),
-                    #[cfg(feature = "output-as-ansi")]
-                    OutputFormat::AnsiTrueColor => encode::to_ansi_true_color(
-                        &code,
-                        margin,
-                        &(arg.foreground, arg.background),
-                        module_size,
-                    )
-                    .into(),
-                    OutputFormat::Ascii | OutputFormat::AsciiInvert => {
-                        encode::to_ascii(&code, margin, module_size, is_invert).into()
+                    for (i, mask) in masks.iter().enumerate() {
+                        let file = mask_file_name(&file, i);
+                        fs::write(&file, render(mask)?).with_context(|| {
+                            format!("could not write the image to {}", file.display())
+                        })?;
+                    }
+                    return Ok(());
+                }
+                let output = render(&code)?;
+
+                if let Some(expected) = expected {
+                    let image = match arg.output_format {
+                        #[cfg(feature = "decode-from-svg")]
+                        OutputFormat::Svg => decode::from_svg(&output, 1),
+                        _ => image::load_from_memory_with_format(&output, ImageFormat::Png)
+                            .map_err(anyhow::Error::from),
                     }
-                    OutputFormat::Unicode | OutputFormat::UnicodeInvert => {
-                        encode::to_unicode(&code, margin, module_size, is_invert).into()
+                    .context("could not read the rendered image")?;
+                    let contents = decode::decode(image.into_luma8())
+                        .context("could not decode the rendered image")?;
+                    if contents.is_empty() {
+                        return Err(anyhow!("no QR code was detected in the rendered image"));
                     }
-                };
+                    for content in contents {
+                        verify::verify(&expected, &content.1)
+                            .context("the rendered image does not reproduce the input data")?;
+                    }
+                }
+
+                #[cfg(feature = "clipboard")]
+                if arg.clipboard {
+                    return if arg.output_format == OutputFormat::Png {
+                        let image = image::load_from_memory_with_format(&output, ImageFormat::Png)
+                            .context("could not read the image")?;
+                        crate::clipboard::set_image(&image.into_rgba8())
+                    } else {
+                        let text = String::from_utf8(output)
+                            .context("could not convert the output to a string")?;
+                        crate::clipboard::set_text(&text)
+                    };
+                }
 
-                if let Some(file) = arg.output {
+                if let Some(fd) = arg.output_fd {
+                    write_to_fd(fd, &output).with_context(|| {
+                        format!("could not write the image to the file descriptor {fd}")
+                    })?;
+                } else if let Some(file) = output_file {
+                    if let Some(dir) = arg.output_dir {
+                        fs::create_dir_all(&dir)
+                            .with_context(|| format!("could not create {}", dir.display()))?;
+                    }
                     fs::write(&file, output).with_context(|| {
                         format!("could not write the image to {}", file.display())
                     })?;
+                } else if arg.output_on_stderr {
+                    io::stderr()
+                        .write_all(&output)
+                        .context("could not write the image to standard error")?;
                 } else {
                     io::stdout()
                         .write_all(&output)
@@ -160,75 +879,372 @@ pub fn run() -> anyhow::Result<()> {
                 }
             }
             Command::Decode(arg) => {
-                let input = match arg.input {
-                    Some(ref path) if path.as_os_str() != "-" => fs::read(path)
-                        .with_context(|| format!("could not read data from {}", path.display()))?,
-                    _ => {
-                        let mut buf = Vec::new();
-                        io::stdin()
-                            .read_to_end(&mut buf)
-                            .context("could not read data from standard input")?;
-                        buf
-                    }
-                };
-                let input_format = arg.input_format;
-                #[cfg(feature = "decode-from-svg")]
-                let input_format = input_format
-                    .or_else(|| is_svg::is_svg(&input).then_some(crate::cli::InputFormat::Svg));
-                #[allow(clippy::option_if_let_else)]
-                let image = match input_format {
+                if arg.list_formats {
+                    return print_formats::<InputFormat>();
+                }
+
+                if let Some(timeout) = arg.timeout {
+                    watchdog::spawn(timeout);
+                }
+                let max_pixels = arg.max_pixels.map(NonZeroU64::get);
+                let expected = match (arg.expect, arg.expect_file) {
+                    (Some(string), _) => Some(string.into_bytes()),
+                    (_, Some(path)) => Some(fs::read(&path).with_context(|| {
+                        format!("could not read the expected data from {}", path.display())
+                    })?),
+                    _ => None,
+                };
+                #[cfg(feature = "clipboard")]
+                let clipboard_image = arg
+                    .clipboard
+                    .then(crate::clipboard::get_image)
+                    .transpose()?;
+                #[cfg(not(feature = "clipboard"))]
+                let clipboard_image: Option<DynamicImage> = None;
+                let data_uri = arg.data_uri.as_deref().map(|uri| {
+                    decode::from_data_uri(uri).unwrap_or_else(|err| {
+                        clap::Error::raw(ErrorKind::ValueValidation, format!("{err:#}\n")).exit()
+                    })
+                });
+                let load_images = |input: Vec<u8>, path: Option<&PathBuf>| -> anyhow::Result<_> {
+                    let input_format = arg.input_format;
                     #[cfg(feature = "decode-from-svg")]
-                    Some(crate::cli::InputFormat::Svg) => decode::from_svg(&input),
-                    format => {
-                        let format = if let Some(f) = format {
-                            f.try_into()
-                        } else {
-                            image::guess_format(&input).or_else(|err| {
-                                arg.input.map_or_else(|| Err(err), ImageFormat::from_path)
-                            })
+                    let input_format =
+                        input_format.or_else(|| is_svg::is_svg(&input).then_some(InputFormat::Svg));
+                    #[allow(clippy::option_if_let_else)]
+                    match input_format {
+                        #[cfg(feature = "decode-from-svg")]
+                        Some(InputFormat::Svg) => {
+                            decode::from_svg(&input, arg.svg_scale).map(|image| vec![image])
+                        }
+                        format => {
+                            let format = if let Some(f) = format {
+                                f.try_into()
+                            } else {
+                                image::guess_format(&input).or_else(|err| {
+                                    path.map_or_else(|| Err(err), ImageFormat::from_path)
+                                })
+                            }
+                            .context("could not determine the image format")?;
+                            if let Some(max) = max_pixels {
+                                let dimensions = decode::dimensions(&input, format)
+                                    .context("could not read the image")?;
+                                decode::check_dimensions(dimensions, max).with_context(|| {
+                                    format!("the image has more than {max} pixels")
+                                })?;
+                            }
+                            #[cfg(any(feature = "decode-from-gif", feature = "decode-from-webp"))]
+                            let frames = decode::frames(&input, format);
+                            #[cfg(not(any(
+                                feature = "decode-from-gif",
+                                feature = "decode-from-webp"
+                            )))]
+                            let frames: image::ImageResult<Option<_>> = Ok(None);
+                            frames
+                                .and_then(|frames| {
+                                    frames.map_or_else(
+                                        || {
+                                            if arg.ignore_orientation {
+                                                image::load_from_memory_with_format(&input, format)
+                                            } else {
+                                                decode::load_with_orientation(&input, format)
+                                            }
+                                            .map(|image| vec![image])
+                                        },
+                                        Ok,
+                                    )
+                                })
+                                .map_err(anyhow::Error::from)
                         }
-                        .context("could not determine the image format")?;
-                        image::load_from_memory_with_format(&input, format)
-                            .map_err(anyhow::Error::from)
                     }
-                }
-                .context("could not read the image")?;
-                let mut image = image.into_luma8();
-
-                let get_contents = |image| {
-                    let mut image = PreparedImage::prepare(image);
-                    let grids = image.detect_grids();
-                    decode::grids_as_bytes(grids).context("could not decode the grid")
+                    .context("could not read the image")
                 };
-
-                // NOTE: rqrr doesn't appear to work if the background is darker than
-                // the foreground. So we try with an inverted image if decoding fails
-                // or no content is returned.
-                let contents = match get_contents(image.clone()) {
-                    Err(e) => {
-                        imageops::invert(&mut image);
-                        get_contents(image).map_err(|_| e)?
+                let read_images = |path: Option<&PathBuf>| -> anyhow::Result<_> {
+                    let input = match path {
+                        Some(path) if path.as_os_str() != "-" => {
+                            fs::read(path).with_context(|| {
+                                format!("could not read data from {}", path.display())
+                            })?
+                        }
+                        _ => {
+                            let mut buf = Vec::new();
+                            io::stdin()
+                                .read_to_end(&mut buf)
+                                .context("could not read data from standard input")?;
+                            buf
+                        }
+                    };
+                    load_images(input, path)
+                };
+                let preprocess = |image: DynamicImage| {
+                    let image = match arg.max_size {
+                        Some(size) => decode::downscale(image, size, arg.scale_filter.into()),
+                        None => image,
+                    };
+                    let image = match arg.min_size {
+                        Some(size) => decode::upscale(image, size, arg.resize_filter.into()),
+                        None => image,
+                    };
+                    let mut image = match arg.preprocess {
+                        Preprocess::Grayscale => image.into_luma8(),
+                        Preprocess::Luminance => decode::to_luminance(&image.into_rgb8()),
+                        Preprocess::Saturation => decode::to_saturation(&image.into_rgb8()),
+                    };
+                    if arg.mirror == Some(Mirror::Always) {
+                        imageops::flip_horizontal_in_place(&mut image);
                     }
-                    Ok(contents) if contents.is_empty() => {
-                        imageops::invert(&mut image);
-                        get_contents(image).unwrap_or(contents)
+                    if let Some(threshold) = arg.threshold {
+                        let threshold = match threshold {
+                            Threshold::Fixed(threshold) => threshold,
+                            Threshold::Otsu => decode::otsu_threshold(&image),
+                        };
+                        decode::binarize(&mut image, threshold);
                     }
-                    Ok(contents) => contents,
+                    image
                 };
+                let try_mirrored = arg.mirror == Some(Mirror::Auto);
+                let escape = match arg.escape {
+                    Escape::Auto => io::stdout().is_terminal(),
+                    Escape::Always => true,
+                    Escape::Never => false,
+                };
+                let to_output = |content: &[u8]| {
+                    if escape {
+                        decode::escape(content)
+                    } else {
+                        content.to_vec()
+                    }
+                };
+                let process = |images: Vec<DynamicImage>| -> anyhow::Result<()> {
+                    if let Some(max) = max_pixels {
+                        for image in &images {
+                            decode::check_dimensions((image.width(), image.height()), max)
+                                .with_context(|| format!("the image has more than {max} pixels"))?;
+                        }
+                    }
+                    if let Some(count) = arg.benchmark {
+                        let durations = benchmark::measure(count, || {
+                            decode::decode_frames(
+                                images.iter().cloned().map(preprocess),
+                                try_mirrored,
+                                arg.first,
+                            )
+                        })
+                        .context("could not decode the grid")?;
+                        eprintln!("Decode: {durations}");
+                        return Ok(());
+                    }
+                    let images = images.into_iter().map(preprocess);
+                    if arg.dump {
+                        let matrices = decode::matrices(images)
+                            .iter()
+                            .map(Matrix::to_string)
+                            .collect::<Vec<_>>();
+                        io::stdout()
+                            .write_all(matrices.join("\n").as_bytes())
+                            .context("could not write data to standard output")?;
+                        return Ok(());
+                    }
+                    if arg.join {
+                        let symbols = images
+                            .map(structured_append::decode)
+                            .collect::<Result<Vec<_>, _>>()
+                            .context("could not decode the grid")?
+                            .concat();
+                        if arg.verbose {
+                            for (header, content) in symbols.iter().flatten() {
+                                let metadata = content.0.metadata();
+                                eprintln!("Position: {}/{}", header.index + 1, header.total);
+                                eprintln!("Version: {}", metadata.symbol_version());
+                                eprintln!("Level: {:?}", metadata.error_correction_level());
+                                eprintln!("Mask: {}", metadata.mask_pattern());
+                                eprintln!("Width: {}", metadata.width());
+                            }
+                        }
+                        let content = structured_append::join(symbols)
+                            .context("could not reassemble the structured append symbols")?;
 
-                for content in contents {
-                    if arg.verbose || arg.metadata {
-                        let metadata = content.0.metadata();
-                        eprintln!("Version: {}", metadata.symbol_version());
-                        eprintln!("Level: {:?}", metadata.error_correction_level());
-                        if arg.metadata {
-                            continue;
+                        let mut stdout = io::stdout().lock();
+                        stdout
+                            .write_all(&to_output(&content))
+                            .context("could not write data to standard output")?;
+                        if arg.print0 {
+                            stdout
+                                .write_all(b"\0")
+                                .context("could not write data to standard output")?;
+                        }
+                        if let Some(ref expected) = expected {
+                            verify::verify(expected, &content)
+                                .context("the decoded data does not match the expected data")?;
+                        }
+                        return Ok(());
+                    }
+                    let contents = if arg.error_details {
+                        let contents =
+                            decode::decode_frames_with_errors(images, try_mirrored, arg.first)
+                                .context("could not decode the grid")?;
+                        for (_, metadata) in &contents {
+                            let errors = metadata.block_errors().unwrap_or_default();
+                            let blocks = errors
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, &count)| count > 0)
+                                .map(|(i, count)| format!("block {i}: {count}"))
+                                .collect::<Vec<_>>();
+                            if blocks.is_empty() {
+                                eprintln!("Errors: 0 codewords corrected");
+                            } else {
+                                eprintln!(
+                                    "Errors: {} codewords corrected ({})",
+                                    errors.iter().sum::<usize>(),
+                                    blocks.join(", ")
+                                );
+                            }
                         }
+                        contents.into_iter().map(|(content, _)| content).collect()
+                    } else {
+                        decode::decode_frames(images, try_mirrored, arg.first)
+                            .context("could not decode the grid")?
+                    };
+                    // A misordered tile breaks the QR code, so report it rather than
+                    // printing nothing.
+                    if arg.mosaic.is_some() && contents.is_empty() {
+                        return Err(anyhow!(
+                            "could not find a QR code in the stitched image, the images may be misordered"
+                        ));
                     }
 
-                    io::stdout()
-                        .write_all(&content.1)
-                        .context("could not write data to standard output")?;
+                    for content in &contents {
+                        if arg.verbose || arg.metadata {
+                            let metadata = content.0.metadata();
+                            eprintln!("Version: {}", metadata.symbol_version());
+                            eprintln!("Level: {:?}", metadata.error_correction_level());
+                            eprintln!("Mask: {}", metadata.mask_pattern());
+                            eprintln!("Width: {}", metadata.width());
+                            if arg.metadata {
+                                continue;
+                            }
+                        }
+
+                        let mut stdout = io::stdout().lock();
+                        stdout
+                            .write_all(&to_output(&content.1))
+                            .context("could not write data to standard output")?;
+                        if arg.print0 {
+                            stdout
+                                .write_all(b"\0")
+                                .context("could not write data to standard output")?;
+                        }
+                    }
+                    if let Some(ref expected) = expected {
+                        verify::verify_all(expected, contents.iter().map(|c| c.1.as_slice()))
+                            .context("the decoded data does not match the expected data")?;
+                    }
+                    Ok(())
+                };
+                if let Some((columns, _)) = arg.mosaic {
+                    let tiles = arg
+                        .input
+                        .iter()
+                        .map(|path| {
+                            read_images(Some(path))?
+                                .into_iter()
+                                .next()
+                                .with_context(|| format!("{} has no images", path.display()))
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    let image =
+                        decode::stitch(&tiles, columns).context("could not stitch the images")?;
+                    return process(vec![image]);
+                }
+                if arg.input.len() > 1 && !arg.join {
+                    let (mut failed, mut last_err) = (0, None);
+                    for path in &arg.input {
+                        if let Err(err) = read_images(Some(path)).and_then(process) {
+                            let err = err.context(format!("could not decode {}", path.display()));
+                            // A file which cannot be decoded must not pass the check.
+                            if arg.strict || expected.is_some() {
+                                return Err(err);
+                            }
+                            if !arg.quiet {
+                                eprintln!("Warning: {err:#}");
+                            }
+                            failed += 1;
+                            last_err = Some(err);
+                        }
+                    }
+                    // The remaining files are decoded, but the failure is still reported
+                    // by the exit status.
+                    return last_err.map_or(Ok(()), |err| {
+                        Err(err).context(format!(
+                            "could not decode {failed} of {} files",
+                            arg.input.len()
+                        ))
+                    });
+                }
+                let images = if let Some(image) = clipboard_image {
+                    vec![image]
+                } else if let Some(input) = data_uri {
+                    load_images(input, None)?
+                } else if arg.input.is_empty() {
+                    read_images(None)?
+                } else {
+                    arg.input
+                        .iter()
+                        .map(|path| read_images(Some(path)))
+                        .collect::<anyhow::Result<Vec<_>>>()?
+                        .concat()
+                };
+                process(images)?;
+            }
+            Command::Estimate(arg) => {
+                let buf = read_input(arg.input, &arg.read_from)?;
+                let level = Ecc::from(arg.error_correction_level);
+                let estimate = match encode::min_version(
+                    &buf,
+                    &arg.variant.into(),
+                    arg.mode.map(Mode::from).as_ref(),
+                    level.into(),
+                ) {
+                    Ok((Version::Normal(number), codewords)) => {
+                        Some((Variant::Normal, number, codewords))
+                    }
+                    Ok((Version::Micro(number), codewords)) => {
+                        Some((Variant::Micro, number, codewords))
+                    }
+                    Err(QrError::DataTooLong | QrError::InvalidVersion) => None,
+                    Err(err) => return Err(err).context("could not estimate the version"),
+                };
+                match (arg.format, estimate) {
+                    (EstimateFormat::Text, Some((variant, number, (used, total)))) => {
+                        println!("Version: {number}");
+                        println!("Type: {}", format!("{variant:?}").to_lowercase());
+                        println!("Codewords: {used}/{total}");
+                    }
+                    (EstimateFormat::Text, None) => println!("Version: none"),
+                    (EstimateFormat::Json, estimate) => {
+                        let estimate = estimate.map_or(
+                            Estimate {
+                                fits: false,
+                                variant: None,
+                                version: None,
+                                level,
+                                codewords: None,
+                            },
+                            |(variant, number, (used, total))| Estimate {
+                                fits: true,
+                                variant: Some(variant),
+                                version: Some(number),
+                                level,
+                                codewords: Some(Codewords { used, total }),
+                            },
+                        );
+                        println!(
+                            "{}",
+                            serde_json::to_string(&estimate)
+                                .context("could not serialize the estimate")?
+                        );
+                    }
                 }
             }
         }
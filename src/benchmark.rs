@@ -0,0 +1,100 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::{
+    fmt, hint,
+    num::NonZeroU32,
+    time::{Duration, Instant},
+};
+
+/// The sorted durations of the runs.
+#[derive(Debug)]
+pub struct Durations(Vec<Duration>);
+
+impl Durations {
+    /// Returns the shortest duration.
+    fn min(&self) -> Duration {
+        self.0[0]
+    }
+
+    /// Returns the median duration.
+    fn median(&self) -> Duration {
+        let mid = self.0.len() / 2;
+        if self.0.len() % 2 == 0 {
+            (self.0[mid - 1] + self.0[mid]) / 2
+        } else {
+            self.0[mid]
+        }
+    }
+
+    /// Returns the longest duration.
+    fn max(&self) -> Duration {
+        self.0[self.0.len() - 1]
+    }
+}
+
+impl fmt::Display for Durations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {:?}, median {:?}, max {:?}",
+            self.min(),
+            self.median(),
+            self.max()
+        )
+    }
+}
+
+/// Runs `f` `count` times and returns the durations.
+pub fn measure<T, E>(
+    count: NonZeroU32,
+    mut f: impl FnMut() -> Result<T, E>,
+) -> Result<Durations, E> {
+    let mut durations = (0..count.get())
+        .map(|_| {
+            let start = Instant::now();
+            hint::black_box(f()?);
+            Ok(start.elapsed())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    durations.sort_unstable();
+    Ok(Durations(durations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        let durations = Durations(
+            vec![1, 2, 4]
+                .into_iter()
+                .map(Duration::from_millis)
+                .collect(),
+        );
+        assert_eq!(durations.min(), Duration::from_millis(1));
+        assert_eq!(durations.median(), Duration::from_millis(2));
+        assert_eq!(durations.max(), Duration::from_millis(4));
+        assert_eq!(durations.to_string(), "min 1ms, median 2ms, max 4ms");
+
+        let durations = Durations(vec![1, 2].into_iter().map(Duration::from_millis).collect());
+        assert_eq!(durations.median(), Duration::from_micros(1500));
+    }
+
+    #[test]
+    fn measure_error() {
+        assert_eq!(
+            measure(NonZeroU32::MIN, || Err::<(), _>("error")).unwrap_err(),
+            "error"
+        );
+        assert_eq!(
+            measure(NonZeroU32::new(3).unwrap(), || Ok::<_, ()>(()))
+                .unwrap()
+                .0
+                .len(),
+            3
+        );
+    }
+}
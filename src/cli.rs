@@ -4,16 +4,38 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use std::{
+    env,
     io::{self, Write},
-    num::NonZeroU32,
-    path::PathBuf,
+    num::{NonZeroU32, NonZeroU64, ParseIntError},
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
-use anyhow::anyhow;
-use clap::{value_parser, Args, CommandFactory, Parser, Subcommand, ValueEnum, ValueHint};
+use anyhow::{anyhow, Context};
+use clap::{
+    parser::ValueSource, value_parser, ArgGroup, ArgMatches, Args, CommandFactory, Parser,
+    Subcommand, ValueEnum, ValueHint,
+};
 use clap_complete::Generator;
 use csscolorparser::Color;
-use image::{ImageError, ImageFormat};
+use image::{
+    codecs::png::{CompressionType, FilterType as PngFilterType},
+    imageops::FilterType,
+    ImageError, ImageFormat,
+};
+
+use crate::{
+    color,
+    config::EncodeConfig,
+    encode::{self, Alignment, Charset, ModuleShape, Segment},
+    palette::Palette,
+};
+
+/// The environment variable which specifies the default foreground color.
+const FOREGROUND_ENV: &str = "QRTOOL_FOREGROUND";
+
+/// The environment variable which specifies the default background color.
+const BACKGROUND_ENV: &str = "QRTOOL_BACKGROUND";
 
 const LONG_VERSION: &str = concat!(
     env!("CARGO_PKG_VERSION"),
@@ -43,6 +65,8 @@ const DECODE_AFTER_LONG_HELP: &str = concat!(
     "See `qrtool-decode(1)` for more details."
 );
 
+const ESTIMATE_AFTER_LONG_HELP: &str = "See `qrtool-estimate(1)` for more details.";
+
 #[derive(Debug, Parser)]
 #[allow(clippy::struct_excessive_bools)]
 #[command(
@@ -62,11 +86,16 @@ pub struct Opt {
     #[arg(long, value_enum, value_name("SHELL"))]
     pub generate_completion: Option<Shell>,
 
+    /// Print the exit statuses and their meanings.
+    #[arg(long, hide(true), conflicts_with("generate_completion"))]
+    pub print_exit_codes: bool,
+
     #[command(subcommand)]
     pub command: Option<Command>,
 }
 
 #[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Command {
     /// Encode input data in a QR code.
     #[command(
@@ -83,15 +112,66 @@ pub enum Command {
         visible_alias("d")
     )]
     Decode(Decode),
+
+    /// Estimate the minimum version required to store input data.
+    #[command(after_long_help(ESTIMATE_AFTER_LONG_HELP))]
+    Estimate(Estimate),
 }
 
 #[derive(Args, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+#[command(group(ArgGroup::new("version_selection").multiple(true)))]
 pub struct Encode {
     /// Output the result to a file.
     #[arg(short, long, value_name("FILE"))]
     pub output: Option<PathBuf>,
 
+    /// Output the result to a file in the directory.
+    ///
+    /// The file name is derived from a hash of the input data and the
+    /// extension of the output format, so the same input data is always
+    /// output to the same file. The directory is created if it does not exist.
+    #[arg(
+        long,
+        value_name("DIRECTORY"),
+        conflicts_with("output"),
+        value_hint(ValueHint::DirPath)
+    )]
+    pub output_dir: Option<PathBuf>,
+
+    /// Output the result to a file descriptor.
+    ///
+    /// This is useful for writing multiple outputs from a pipeline, such as
+    /// `3>output.png`. This option is only supported on Unix.
+    #[arg(
+        long,
+        value_name("FD"),
+        conflicts_with_all(["output", "output_dir"])
+    )]
+    pub output_fd: Option<u32>,
+
+    /// Output the result to stderr instead of stdout.
+    ///
+    /// The metadata and warnings are output to stdout instead, so that they
+    /// can be read separately from the result.
+    #[arg(long, conflicts_with_all(["output", "output_dir", "output_fd"]))]
+    pub output_on_stderr: bool,
+
+    /// Copy the result to the system clipboard.
+    ///
+    /// A PNG image is copied as an image, and the other formats are copied as
+    /// text. Nothing is output to stdout.
+    #[cfg(feature = "clipboard")]
+    #[arg(
+        long,
+        conflicts_with_all(["output", "output_dir", "output_fd", "output_on_stderr"])
+    )]
+    pub clipboard: bool,
+
     /// Read input data from a file.
+    ///
+    /// This option can be specified multiple times, in which case the contents
+    /// of the files are concatenated in the order specified.
     #[arg(
         short,
         long,
@@ -99,14 +179,80 @@ pub struct Encode {
         value_hint(ValueHint::FilePath),
         conflicts_with("input")
     )]
-    pub read_from: Option<PathBuf>,
+    pub read_from: Vec<PathBuf>,
+
+    /// Read a matrix of modules from a file and render it as is.
+    ///
+    /// Each line of the file is a row of modules, in which "1" is a dark module
+    /// and "0" is a light module. Such a matrix can be printed by 'qrtool
+    /// decode --dump'. The input data is not read, and the options for encoding
+    /// the data cannot be used.
+    #[arg(
+        long,
+        value_name("FILE"),
+        value_hint(ValueHint::FilePath),
+        conflicts_with_all([
+            "input",
+            "read_from",
+            "error_correction_level",
+            "version_selection",
+            "mode",
+            "variant",
+            "verbose",
+            "dry_run",
+            "capacity",
+            "output_dir"
+        ])
+    )]
+    pub from_matrix: Option<PathBuf>,
 
     /// The module size in pixels.
     ///
     /// If this option is not specified, the module size is 8 when the output
-    /// format is PNG or SVG, and 1 otherwise.
-    #[arg(short, long, value_name("NUMBER"))]
-    pub size: Option<NonZeroU32>,
+    /// format is PNG, SVG or AVIF, and 1 otherwise. For SVG, the module size
+    /// can also be specified as a percentage of the viewport width, such as
+    /// "5%".
+    #[arg(short, long, value_parser(parse_module_size), value_name("NUMBER"))]
+    pub size: Option<ModuleSize>,
+
+    /// The resolution of a PNG image in dots per inch.
+    ///
+    /// This is written to the "pHYs" chunk of the PNG image, so that the image
+    /// is printed at the intended physical size.
+    #[arg(long, value_name("DPI"))]
+    pub dpi: Option<NonZeroU32>,
+
+    /// Scale the QR code to the physical size at the resolution of '--dpi'.
+    ///
+    /// <LENGTH> is the length of each side of the QR code including the margin,
+    /// with the unit "mm", "cm" or "in", such as "25mm". The module size is
+    /// computed from <LENGTH> and '--dpi', and is rounded to the nearest
+    /// integer.
+    #[arg(
+        long,
+        value_parser(parse_length),
+        value_name("LENGTH"),
+        requires("dpi"),
+        conflicts_with_all(["size", "margin_unit"])
+    )]
+    pub physical_size: Option<f64>,
+
+    /// Pad the image to exactly the physical size of '--physical-size'.
+    ///
+    /// The module size is rounded down instead of to the nearest integer, and
+    /// the image is padded with the background color, so that the image has
+    /// exactly the number of pixels of <LENGTH> at the resolution. <ALIGNMENT>
+    /// is the position of the QR code in the padded image. This cannot be used
+    /// with the title or '--repeat'.
+    #[arg(
+        long,
+        value_enum,
+        value_name("ALIGNMENT"),
+        ignore_case(true),
+        requires("physical_size"),
+        conflicts_with("repeat")
+    )]
+    pub pad: Option<Alignment>,
 
     /// Error correction level.
     #[arg(
@@ -118,31 +264,103 @@ pub struct Encode {
         value_name("LEVEL"),
         ignore_case(true)
     )]
-    pub error_correction_level: Ecc,
+    pub error_correction_level: ErrorCorrectionLevel,
+
+    /// The minimum error correction level chosen by the automatic error
+    /// correction level.
+    ///
+    /// The levels lower than <LEVEL> are not tried, so the data which cannot
+    /// be stored with <LEVEL> in the given version is rejected.
+    #[arg(long, value_enum, value_name("LEVEL"), ignore_case(true))]
+    pub min_ecc: Option<Ecc>,
 
     /// The version of the symbol.
     ///
     /// If this option is not specified, the minimum version required to store
     /// the data will be automatically chosen. For normal QR code, <NUMBER>
     /// should be between 1 and 40. For Micro QR code, <NUMBER> should be
-    /// between 1 and 4.
+    /// between 1 and 4. If "-" is specified, <NUMBER> is read from the first
+    /// line of standard input, and the rest of standard input is the input
+    /// data.
     #[arg(
-        value_parser(value_parser!(i16).range(1..=40)),
+        value_parser(parse_symbol_version),
         short('v'),
         long,
         visible_alias("symversion"),
-        value_name("NUMBER")
+        value_name("NUMBER"),
+        group("version_selection"),
+        conflicts_with_all(["optimize_version", "min_version", "max_version"])
+    )]
+    pub symbol_version: Option<SymbolVersion>,
+
+    /// Choose the minimum version required to store the data.
+    ///
+    /// Unlike when '--symbol-version' is not specified, this respects '--mode'
+    /// and '--variant'.
+    #[arg(long, group("version_selection"))]
+    pub optimize_version: bool,
+
+    /// The minimum version of the symbol.
+    ///
+    /// The minimum version required to store the data which is greater than or
+    /// equal to <NUMBER> will be chosen. Like '--optimize-version', this
+    /// respects '--mode' and '--variant'.
+    #[arg(
+        value_parser(value_parser!(i16).range(1..=40)),
+        long,
+        value_name("NUMBER"),
+        group("version_selection")
+    )]
+    pub min_version: Option<i16>,
+
+    /// The maximum version of the symbol.
+    ///
+    /// The minimum version required to store the data which is less than or
+    /// equal to <NUMBER> will be chosen. Like '--optimize-version', this
+    /// respects '--mode' and '--variant'.
+    #[arg(
+        value_parser(value_parser!(i16).range(1..=40)),
+        long,
+        value_name("NUMBER"),
+        group("version_selection")
     )]
-    pub symbol_version: Option<i16>,
+    pub max_version: Option<i16>,
 
     /// The width of margin.
     ///
     /// If this option is not specified, the margin will be 4 for normal QR code
-    /// and 2 for Micro QR code.
+    /// and 2 for Micro QR code. If 0 is specified, the output has no margin for
+    /// all output formats.
     #[arg(short, long, value_name("NUMBER"))]
     pub margin: Option<u32>,
 
+    /// The unit of the width of margin.
+    ///
+    /// If "pixels" is specified, the margin is converted to modules based on
+    /// the module size, and rounded up to a whole number of modules. The default
+    /// margin is always in modules.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        value_name("UNIT"),
+        ignore_case(true)
+    )]
+    pub margin_unit: MarginUnit,
+
+    /// Do not warn when the margin is smaller than the quiet zone.
+    ///
+    /// The QR code specification recommends a margin of at least 4 modules
+    /// for normal QR code and 2 modules for Micro QR code. Otherwise, the QR
+    /// code may be hard to scan on a busy background.
+    #[arg(long)]
+    pub allow_small_margin: bool,
+
     /// The format of the output.
+    ///
+    /// If this option is not specified and the result is output to a file, the
+    /// format is determined based on the extension of the file. If the format
+    /// cannot be determined, PNG is used.
     #[arg(
         short('t'),
         long("type"),
@@ -153,6 +371,86 @@ pub struct Encode {
     )]
     pub output_format: OutputFormat,
 
+    /// Make a SVG image scale to its container.
+    ///
+    /// The root element has the width of 100% and the "viewBox" attribute
+    /// instead of the fixed width and height in pixels.
+    #[arg(long)]
+    pub svg_viewbox: bool,
+
+    /// Merge the dark modules of a SVG image into horizontal runs.
+    ///
+    /// Adjacent dark modules in each row are drawn as a single rectangle, which
+    /// greatly reduces the file size of a large QR code. By default, this is
+    /// done only for version 10 or larger. If <WHEN> is not specified, it is
+    /// assumed that "always" is specified.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        num_args(0..=1),
+        require_equals(true),
+        value_name("WHEN"),
+        ignore_case(true),
+        default_missing_value("always")
+    )]
+    pub svg_optimize: SvgOptimize,
+
+    /// Whether to draw the background of a SVG image.
+    ///
+    /// If this is "none", the background rectangle is not drawn at all, so the
+    /// light modules are transparent. This is useful for layering the SVG image
+    /// over a colored page.
+    #[arg(long, value_enum, default_value_t, value_name("BACKGROUND"))]
+    pub svg_background: SvgBackground,
+
+    /// Wrap the QR code in a SVG "symbol" element with <ID>.
+    ///
+    /// The symbol is not rendered by itself, but can be referenced many times
+    /// with '<use href="#<ID>"/>'. This reduces the markup of a document which
+    /// shows the same QR code repeatedly.
+    #[arg(long, value_parser(parse_svg_id), value_name("ID"))]
+    pub svg_symbol: Option<String>,
+
+    /// Disable the anti-aliasing of a SVG image.
+    ///
+    /// The root element has the "shape-rendering" attribute set to
+    /// "crispEdges", so that the edges of modules are not blurred. The root
+    /// element already has it unless '--title' or '--repeat' is specified.
+    #[arg(long)]
+    pub crisp_edges: bool,
+
+    /// Set the compression level for a PNG image.
+    ///
+    /// If this option is not specified, it is assumed that "default" is
+    /// specified.
+    #[arg(long, value_enum, value_name("LEVEL"), ignore_case(true))]
+    pub png_compression: Option<PngCompression>,
+
+    /// Set the filter type for a PNG image.
+    ///
+    /// The filter only affects the file size, and the pixels are not changed.
+    /// If this option is not specified, it is assumed that "adaptive" is
+    /// specified, except for a 1-bit image which uses the default filter of the
+    /// encoder. Note that '--optimize-png' may choose other filters.
+    #[arg(long, value_enum, value_name("FILTER"), ignore_case(true))]
+    pub png_filter: Option<PngFilter>,
+
+    /// Set the bit depth of a PNG image.
+    ///
+    /// If "16" is specified, the image is output as a 16-bit grayscale image.
+    /// In this case, the colors must be fully opaque, and they are converted to
+    /// grayscale.
+    #[arg(long, value_enum, default_value_t, value_name("DEPTH"))]
+    pub bit_depth: BitDepth,
+
+    /// Output a 1-bit grayscale PNG image.
+    ///
+    /// This produces a much smaller image than the default RGBA image. The
+    /// colors must be black or white, and they must be fully opaque.
+    #[arg(long, conflicts_with("bit_depth"))]
+    pub bilevel: bool,
+
     /// Set the optimization level for a PNG image.
     ///
     /// Lower levels are faster, higher levels provide better compression. If
@@ -186,22 +484,108 @@ pub struct Encode {
 
     /// The mode of the output.
     ///
-    /// If this option is not specified, use the optimal encoding.
+    /// If this option is not specified, use the optimal encoding. If the
+    /// version is not selected, the minimum version required to store the data
+    /// in this mode is chosen in the same way as '--optimize-version'.
+    #[arg(long, value_enum, value_name("MODE"), ignore_case(true))]
+    pub mode: Option<Mode>,
+
+    /// The character set of the input data.
+    ///
+    /// If "utf-8" is specified, the input data is converted to Shift JIS before
+    /// encoding. This option requires '--mode kanji'.
     #[arg(
         long,
         value_enum,
-        requires("symbol_version"),
-        value_name("MODE"),
+        requires("mode"),
+        value_name("CHARSET"),
         ignore_case(true)
     )]
-    pub mode: Option<Mode>,
+    pub input_charset: Option<InputCharset>,
+
+    /// The encoding of the input data.
+    ///
+    /// The input data is decoded into raw bytes before encoding. This is useful
+    /// for encoding binary data which cannot be passed safely on the command
+    /// line.
+    #[arg(
+        long,
+        value_enum,
+        value_name("ENCODING"),
+        ignore_case(true),
+        conflicts_with("from_matrix")
+    )]
+    pub input_encoding: Option<InputEncoding>,
+
+    /// Prepend the text to the input data.
+    ///
+    /// The input data is wrapped after being read from any source and decoded
+    /// by '--input-encoding', so this is useful for generating URLs from
+    /// identifiers. The bytes of <TEXT> are concatenated as is, so in byte mode
+    /// the result is the concatenated bytes, and the result must still be valid
+    /// in the mode specified by '--mode'.
+    #[arg(
+        long,
+        value_name("TEXT"),
+        conflicts_with_all(["from_matrix", "segment", "capacity"])
+    )]
+    pub prefix: Option<String>,
+
+    /// Append the text to the input data.
+    ///
+    /// This is the same as '--prefix', except that <TEXT> is appended.
+    #[arg(
+        long,
+        value_name("TEXT"),
+        conflicts_with_all(["from_matrix", "segment", "capacity"])
+    )]
+    pub suffix: Option<String>,
+
+    /// Encode a segment of text in the character set instead of the input data.
+    ///
+    /// <TEXT> is encoded in <CHARSET> as a byte mode segment with its ECI
+    /// designator. This option can be specified multiple times, in which case
+    /// the segments are encoded in the order specified. This allows mixing
+    /// character sets such as "Hello:ascii" and "漢字:shift-jis". <CHARSET> is
+    /// one of "ascii", "iso-8859-1", "shift-jis", "utf-8", "big5", "gb18030"
+    /// and "euc-kr". Only a normal QR code is supported.
+    #[arg(
+        long,
+        value_parser(parse_segment),
+        value_name("TEXT:CHARSET"),
+        conflicts_with_all([
+            "input",
+            "read_from",
+            "from_matrix",
+            "mode",
+            "input_encoding",
+            "capacity",
+            "dump_all_masks"
+        ])
+    )]
+    pub segment: Vec<Segment>,
+
+    /// Do not write any ECI designator.
+    ///
+    /// The segments specified by '--segment' are written as plain byte mode
+    /// segments, so the reader has to guess their character sets. Most readers
+    /// assume ISO/IEC 8859-1 or UTF-8, therefore this is only recommended for
+    /// old readers which cannot read a QR code containing ECI designators. The
+    /// input data is never written with an ECI designator regardless of this
+    /// option.
+    #[arg(long)]
+    pub no_eci: bool,
 
     /// The type of QR code.
+    ///
+    /// If "auto" is specified, the versions of Micro QR code are tried before
+    /// the versions of normal QR code. This does not require the version
+    /// selection, and conflicts with '--symbol-version'.
     #[arg(
         long,
         value_enum,
         default_value_t,
-        requires("symbol_version"),
+        requires_ifs([("normal", "version_selection"), ("micro", "version_selection")]),
         value_name("TYPE"),
         ignore_case(true)
     )]
@@ -214,7 +598,18 @@ pub struct Encode {
     /// lossy conversion may be performed depending on the color space supported
     /// by the method to specify a color, the color depth supported by the
     /// output format, etc.
-    #[arg(long, default_value("black"), value_name("COLOR"))]
+    ///
+    /// In addition to CSS colors, a CMYK color such as "cmyk(0% 50% 50% 0%)"
+    /// can be specified.
+    ///
+    /// If this option is not specified, the value of the environment variable
+    /// `QRTOOL_FOREGROUND` is used if it is set.
+    #[arg(
+        long,
+        default_value("black"),
+        value_name("COLOR"),
+        value_parser(color::parse)
+    )]
     pub foreground: Color,
 
     /// Background color.
@@ -224,15 +619,259 @@ pub struct Encode {
     /// lossy conversion may be performed depending on the color space supported
     /// by the method to specify a color, the color depth supported by the
     /// output format, etc.
-    #[arg(long, default_value("white"), value_name("COLOR"))]
+    ///
+    /// In addition to CSS colors, a CMYK color such as "cmyk(0% 50% 50% 0%)"
+    /// can be specified. "transparent" makes the background fully transparent
+    /// in the same way as '--transparent'.
+    ///
+    /// If this option is not specified, the value of the environment variable
+    /// `QRTOOL_BACKGROUND` is used if it is set.
+    #[arg(
+        long,
+        default_value("white"),
+        value_name("COLOR"),
+        value_parser(color::parse)
+    )]
     pub background: Color,
 
+    /// The opacity of the foreground color.
+    ///
+    /// <OPACITY> is a number between 0.0 and 1.0, which is multiplied into the
+    /// alpha component of '--foreground'.
+    #[arg(long, value_parser(parse_opacity), value_name("OPACITY"))]
+    pub foreground_opacity: Option<f32>,
+
+    /// The opacity of the background color.
+    ///
+    /// <OPACITY> is a number between 0.0 and 1.0, which is multiplied into the
+    /// alpha component of '--background'.
+    #[arg(long, value_parser(parse_opacity), value_name("OPACITY"))]
+    pub background_opacity: Option<f32>,
+
+    /// Exit with an error if the colors have low contrast.
+    ///
+    /// By default, a warning is printed if the contrast ratio between the
+    /// foreground and background colors is less than 3:1, which is the
+    /// contrast ratio required for graphical objects by WCAG 2.
+    #[arg(long)]
+    pub strict_contrast: bool,
+
+    /// Color of the finder patterns.
+    ///
+    /// <COLOR> takes the same value as '--foreground'. The three finder
+    /// patterns (one for Micro QR code) are painted in this color instead of
+    /// the foreground color. This option requires the output format to be
+    /// PNG, SVG or AVIF.
+    #[arg(long, value_name("COLOR"), value_parser(color::parse))]
+    pub finder_color: Option<Color>,
+
+    /// Color of the quiet zone.
+    ///
+    /// <COLOR> takes the same value as '--foreground'. The margin around the
+    /// QR code is painted in this color instead of the background color, while
+    /// the light modules keep the background color. This is useful for a QR
+    /// code in a colored frame. Note that decoders may fail to detect the QR
+    /// code if this color is dark. This option requires the output format to be
+    /// PNG, SVG or AVIF.
+    #[arg(long, value_name("COLOR"), value_parser(color::parse))]
+    pub quiet_zone_color: Option<Color>,
+
+    /// Shape of the dark modules.
+    ///
+    /// "circle" draws each dark module as a circle inscribed in the module, so
+    /// that adjacent circles touch but never overlap. The finder patterns are
+    /// drawn in the shape specified by '--finder-shape'. This option requires
+    /// the output format to be PNG, SVG or AVIF.
+    #[arg(long, value_enum, default_value_t, value_name("SHAPE"))]
+    pub module_shape: ModuleShape,
+
+    /// Shape of the dark modules of the finder patterns.
+    ///
+    /// The finder patterns are kept square by default regardless of
+    /// '--module-shape', since decoders may fail to detect the QR code if they
+    /// are drawn as circles. This option requires the output format to be PNG,
+    /// SVG or AVIF.
+    #[arg(long, value_enum, default_value_t, value_name("SHAPE"))]
+    pub finder_shape: ModuleShape,
+
+    /// Read named colors from a palette file.
+    ///
+    /// The names defined in the palette can be used as <COLOR> of
+    /// '--foreground', '--background', '--finder-color' and
+    /// '--quiet-zone-color'. The names are resolved before the built-in color
+    /// names.
+    #[arg(long, value_name("FILE"), value_hint(ValueHint::FilePath))]
+    pub palette: Option<PathBuf>,
+
+    /// Render the title beneath the QR code.
+    ///
+    /// This is only available when the output format is PNG, SVG or AVIF. For
+    /// PNG and AVIF, the title is drawn with a built-in font which supports
+    /// only the printable ASCII characters, and its size scales with '--size'.
+    /// If <TEXT> is empty, this option is ignored.
+    #[cfg(feature = "output-with-title")]
+    #[arg(long, value_name("TEXT"))]
+    pub title: Option<String>,
+
+    /// Make the background transparent.
+    ///
+    /// This takes precedence over '--background'. This is only available when
+    /// the output format is PNG, SVG or AVIF, and is ignored with a warning
+    /// otherwise.
+    #[arg(long)]
+    pub transparent: bool,
+
+    /// Tile the QR code in a grid of <COLUMNS>x<ROWS>.
+    ///
+    /// This produces a single image with copies of the QR code, which is useful
+    /// for printing many identical QR codes on a page. <COLUMNS> and <ROWS> must
+    /// be at most 100. This is only available when the output format is PNG,
+    /// SVG or AVIF.
+    #[arg(long, value_parser(parse_repeat), value_name("COLUMNSxROWS"))]
+    pub repeat: Option<(u32, u32)>,
+
+    /// The gap between the tiled QR codes in pixels.
+    ///
+    /// The gap is filled with the background color.
+    #[arg(long, default_value_t, value_name("PIXELS"), requires("repeat"))]
+    pub gap: u32,
+
     /// Also print the metadata.
     ///
     /// It is output to stderr.
     #[arg(long)]
     pub verbose: bool,
 
+    /// Do not print the metadata and warnings.
+    ///
+    /// Error messages are still output to stderr.
+    #[arg(short, long, conflicts_with("verbose"))]
+    pub quiet: bool,
+
+    /// Print the metadata as a JSON object.
+    ///
+    /// Like '--verbose', the metadata is output to stderr, but as a JSON object
+    /// on a single line instead of plain text. This is useful for consuming the
+    /// metadata in scripts.
+    #[arg(long, conflicts_with_all(["quiet", "from_matrix"]))]
+    pub metadata_json: bool,
+
+    /// Construct a QR code without producing any output.
+    ///
+    /// The metadata is printed to stderr, and nothing is rendered or written.
+    /// This is useful for checking whether the data can be encoded with the
+    /// given options.
+    #[arg(
+        long,
+        conflicts_with_all(["quiet", "output", "output_dir", "capacity"])
+    )]
+    pub dry_run: bool,
+
+    /// Construct and render a QR code the number of times and print the
+    /// durations.
+    ///
+    /// The minimum, median and maximum durations are output to stderr, and
+    /// nothing is written.
+    #[arg(long, hide(true), value_name("NUMBER"), conflicts_with("dry_run"))]
+    pub benchmark: Option<NonZeroU32>,
+
+    /// Render the QR code with each of the eight mask patterns.
+    ///
+    /// Each image is written to a separate file which has the mask pattern
+    /// appended to the name of the output file, such as "out-mask0.png" to
+    /// "out-mask7.png". With --verbose, the penalty score of each mask pattern
+    /// is also printed. Only a normal QR code is supported.
+    #[arg(
+        long,
+        conflicts_with_all(["from_matrix", "output_fd", "output_on_stderr", "dry_run", "benchmark"])
+    )]
+    pub dump_all_masks: bool,
+
+    /// Decode the rendered image and check that it reproduces the input data.
+    ///
+    /// If the image cannot be decoded or the decoded data does not match the
+    /// input data, an error is returned. This is useful for checking that
+    /// unusual options, such as colors with low contrast, still produce a
+    /// valid QR code.
+    #[arg(
+        long,
+        conflicts_with_all(["from_matrix", "dry_run", "benchmark", "dump_all_masks"])
+    )]
+    pub verify: bool,
+
+    /// Tint the function patterns and the information regions.
+    ///
+    /// The finder patterns, the timing patterns, the alignment patterns, the
+    /// format information and the version information are tinted in distinct
+    /// colors on top of the modules. This is an aid for learning the structure
+    /// of a QR code, and the output is not meant to be scanned.
+    #[arg(long, hide(true), conflicts_with_all(["verify", "bilevel"]))]
+    pub debug_overlay: bool,
+
+    /// Fail if any module of the image is not a solid square of pixels.
+    ///
+    /// Modules are always scaled by replicating pixels without interpolation,
+    /// and this option asserts it for print pipelines which rely on hard edges.
+    /// The encoded PNG image is decoded again and checked, so this also covers
+    /// '--bilevel' and the optimization. This is only available when the output
+    /// format is PNG.
+    #[arg(long, conflicts_with_all(["pad", "repeat"]))]
+    pub nearest_neighbor_only: bool,
+
+    /// Read the default values of options from a file.
+    ///
+    /// If this option is not specified, the configuration is read from
+    /// "qrtool/config.toml" in the user's configuration directory if it
+    /// exists. Options specified on the command line take precedence over the
+    /// configuration.
+    #[arg(
+        long,
+        value_name("FILE"),
+        value_hint(ValueHint::FilePath),
+        conflicts_with("no_config")
+    )]
+    pub config: Option<PathBuf>,
+
+    /// Do not read the configuration file.
+    #[arg(long)]
+    pub no_config: bool,
+
+    /// List the named colors and exit.
+    ///
+    /// Each line contains the name of the color and its hexadecimal notation.
+    #[arg(long, exclusive(true))]
+    pub list_colors: bool,
+
+    /// List the output formats supported by this build and exit.
+    ///
+    /// Each line contains the name of the format and its description. The
+    /// formats which are disabled at compile time are not listed.
+    #[arg(long, exclusive(true))]
+    pub list_formats: bool,
+
+    /// Print the maximum number of characters which can be stored and exit.
+    ///
+    /// The capacity is computed for the version, the mode, the error correction
+    /// level and the type of QR code. The input data is not read. This option
+    /// requires '--symbol-version' and '--mode'.
+    #[arg(
+        long,
+        requires_all(["symbol_version", "mode"]),
+        conflicts_with_all(["input", "read_from", "output", "output_dir"])
+    )]
+    pub capacity: bool,
+
+    /// The format of the capacity.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        requires("capacity"),
+        value_name("FORMAT"),
+        ignore_case(true)
+    )]
+    pub format: CapacityFormat,
+
     /// Input data.
     ///
     /// If [STRING] is not specified, data will be read from standard input.
@@ -242,6 +881,7 @@ pub struct Encode {
 }
 
 #[derive(Args, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Decode {
     /// The format of the input.
     ///
@@ -256,66 +896,899 @@ pub struct Decode {
     )]
     pub input_format: Option<InputFormat>,
 
-    /// Also print the metadata.
+    /// The scale factor for rasterizing the SVG image.
     ///
-    /// It is output to stderr.
-    #[arg(long, conflicts_with("metadata"))]
-    pub verbose: bool,
+    /// The SVG image is rasterized at <FACTOR> times its intrinsic size before
+    /// scanning. Increasing this may help to detect a QR code whose modules are
+    /// drawn very small. The value must be between 1 and 16. This option is
+    /// ignored if the input is not an SVG image.
+    #[cfg(feature = "decode-from-svg")]
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser(value_parser!(u32).range(1..=16)),
+        value_name("FACTOR")
+    )]
+    pub svg_scale: u32,
+
+    /// Upscale the image so that its smaller dimension is at least <PIXELS>
+    /// before scanning.
+    ///
+    /// This may help to detect a QR code from a very small image. The aspect
+    /// ratio is preserved, and the image is not downscaled.
+    #[arg(long, value_parser(value_parser!(u32).range(1..=16384)), value_name("PIXELS"))]
+    pub min_size: Option<u32>,
+
+    /// The filter used to upscale the image.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        value_name("FILTER"),
+        ignore_case(true),
+        requires("min_size")
+    )]
+    pub resize_filter: ResizeFilter,
+
+    /// Downscale the image so that its larger dimension is at most <PIXELS>
+    /// before scanning.
+    ///
+    /// This bounds the memory usage and the time to scan a very large image.
+    /// The aspect ratio is preserved, and the image is not upscaled.
+    #[arg(long, value_parser(value_parser!(u32).range(1..=16384)), value_name("PIXELS"))]
+    pub max_size: Option<u32>,
+
+    /// Reject an image which has more than <NUMBER> pixels.
+    ///
+    /// The dimensions are checked before the image is decoded, so a huge image
+    /// does not exhaust the memory. This is useful for decoding untrusted
+    /// input.
+    #[arg(long, value_name("NUMBER"))]
+    pub max_pixels: Option<NonZeroU64>,
+
+    /// Abort if decoding does not finish within <SECONDS>.
+    ///
+    /// <SECONDS> may have a fractional part, such as "0.5". If the time limit
+    /// is exceeded, qrtool exits with the status 75.
+    #[arg(long, value_parser(parse_timeout), value_name("SECONDS"))]
+    pub timeout: Option<Duration>,
+
+    /// The filter used to downscale the image.
+    ///
+    /// Smoother filters such as "lanczos3" may blur the edges of modules.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ResizeFilter::Triangle,
+        value_name("FILTER"),
+        ignore_case(true),
+        requires("max_size")
+    )]
+    pub scale_filter: ResizeFilter,
+
+    /// How to convert the image to grayscale before scanning.
+    ///
+    /// A colored or photographed QR code may not be detected from the luma,
+    /// since different colors can have similar luma.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        value_name("METHOD"),
+        ignore_case(true)
+    )]
+    pub preprocess: Preprocess,
+
+    /// Binarize the image with a global threshold before scanning.
+    ///
+    /// Pixels whose luma is less than <THRESHOLD> become black, and the others
+    /// become white. <THRESHOLD> is a number between 0 and 255, or "otsu" to
+    /// determine it automatically using Otsu's method. This may help to detect
+    /// a QR code from a noisy image.
+    #[arg(long, value_parser(parse_threshold), value_name("THRESHOLD"))]
+    pub threshold: Option<Threshold>,
+
+    /// Flip the image horizontally before scanning.
+    ///
+    /// This decodes a mirrored QR code, such as one printed on a transparent
+    /// film and viewed from behind. If <MODE> is not specified, it is assumed
+    /// that "always" is specified.
+    #[arg(
+        long,
+        value_enum,
+        num_args(0..=1),
+        value_name("MODE"),
+        ignore_case(true),
+        default_missing_value("always")
+    )]
+    pub mirror: Option<Mirror>,
+
+    /// Do not correct the orientation of the image.
+    ///
+    /// By default, the image is rotated and flipped according to the
+    /// orientation in its EXIF metadata, such as a JPEG image taken by a
+    /// phone.
+    #[arg(long)]
+    pub ignore_orientation: bool,
+
+    /// Also print the metadata.
+    ///
+    /// It is output to stderr.
+    #[arg(long, conflicts_with("metadata"))]
+    pub verbose: bool,
+
+    /// Print only the metadata.
+    ///
+    /// It is output to stderr.
+    #[arg(long)]
+    pub metadata: bool,
+
+    /// Also print the number of codewords corrected in each decoded QR code.
+    ///
+    /// The blocks which had errors are also printed. It is output to stderr.
+    /// This helps to decide whether a damaged QR code should be reprinted.
+    #[arg(long, conflicts_with_all(["dump", "join"]))]
+    pub error_details: bool,
+
+    /// Do not print the metadata and warnings.
+    ///
+    /// Error messages are still output to stderr.
+    #[arg(short, long, conflicts_with_all(["verbose", "metadata"]))]
+    pub quiet: bool,
+
+    /// Terminate each decoded content with a NUL character.
+    ///
+    /// This is useful when multiple QR codes are decoded and the contents may
+    /// contain newlines. The output can be consumed by tools such as
+    /// "xargs -0".
+    #[arg(long, conflicts_with("metadata"))]
+    pub print0: bool,
+
+    /// Escape the non-printable bytes in the decoded contents as "\xNN".
+    ///
+    /// The control characters other than tab and line feed, and the bytes
+    /// which are not valid UTF-8 are escaped, so a malicious or binary content
+    /// cannot mangle the terminal. The printable text is not changed. By
+    /// default, the contents are escaped only if standard output is a terminal.
+    /// If <WHEN> is not specified, it is assumed that "always" is specified.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        num_args(0..=1),
+        require_equals(true),
+        value_name("WHEN"),
+        ignore_case(true),
+        default_missing_value("always")
+    )]
+    pub escape: Escape,
+
+    /// Exit with an error unless the decoded data matches the string.
+    ///
+    /// The decoded data is still output. If multiple QR codes are decoded,
+    /// all of them must match. If the data does not match, the mismatch is
+    /// printed to stderr and the exit status is 3.
+    #[arg(long, value_name("STRING"), conflicts_with_all(["metadata", "dump"]))]
+    pub expect: Option<String>,
+
+    /// Exit with an error unless the decoded data matches the contents of the
+    /// file.
+    ///
+    /// This is the same as '--expect', except that the data is compared with
+    /// the bytes of the file, so arbitrary binary data can be compared.
+    #[arg(
+        long,
+        value_name("FILE"),
+        value_hint(ValueHint::FilePath),
+        conflicts_with_all(["expect", "metadata", "dump"])
+    )]
+    pub expect_file: Option<PathBuf>,
+
+    /// Read the image from the system clipboard.
+    ///
+    /// This is useful for decoding a screenshot without saving it to a file.
+    #[cfg(feature = "clipboard")]
+    #[arg(long, conflicts_with_all(["input", "input_format", "data_uri"]))]
+    pub clipboard: bool,
+
+    /// Read the image from the data URI.
+    ///
+    /// <URI> is a base64 data URI such as "data:image/png;base64,...". The
+    /// media type is ignored, and the format is determined in the same way as
+    /// the input image files. This is useful for decoding an image which is
+    /// already encoded as a string without saving it to a file.
+    #[arg(long, value_name("URI"), conflicts_with("input"))]
+    pub data_uri: Option<String>,
+
+    /// Reassemble the QR codes in Structured Append mode into one content.
+    ///
+    /// The QR codes are read from all input images, and their contents are
+    /// concatenated in the order of their positions in the sequence. It is an
+    /// error if any of the QR codes is missing, or if the parity does not match
+    /// the reassembled content.
+    #[arg(long, conflicts_with("metadata"))]
+    pub join: bool,
+
+    /// Stop at the first QR code which is decoded successfully.
+    ///
+    /// The remaining QR codes are not decoded, which makes decoding faster if
+    /// the image contains one known QR code. The frames of an animated image
+    /// are tried in order, and the QR codes in an image are tried roughly from
+    /// the top to the bottom. If multiple images are specified, this applies to
+    /// each image.
+    #[arg(long, conflicts_with_all(["join", "dump"]))]
+    pub first: bool,
+
+    /// Abort if any of the multiple images cannot be decoded.
+    ///
+    /// By default, an image which cannot be decoded is reported as a warning,
+    /// and the remaining images are still decoded. Even then, the exit status
+    /// is non-zero if any image cannot be decoded.
+    #[arg(long, conflicts_with("join"))]
+    pub strict: bool,
+
+    /// Stitch the images into one image in a grid of <COLUMNS>x<ROWS> before
+    /// decoding.
+    ///
+    /// This is useful for a large QR code which is captured in sections. The
+    /// images are placed in the order specified, from left to right and then
+    /// from top to bottom, so the number of images must be <COLUMNS> times
+    /// <ROWS>. <COLUMNS> and <ROWS> must be at most 100. All images must have
+    /// the same dimensions.
+    #[arg(
+        long,
+        value_parser(parse_repeat),
+        value_name("COLUMNSxROWS"),
+        conflicts_with_all(["join", "strict"])
+    )]
+    pub mosaic: Option<(u32, u32)>,
+
+    /// Print the matrices of modules of the detected QR codes instead of their
+    /// contents.
+    ///
+    /// Each line is a row of modules, in which "1" is a dark module and "0" is
+    /// a light module, and the matrices are separated by an empty line. The
+    /// QR codes are not decoded. The matrix can be rendered again by 'qrtool
+    /// encode --from-matrix'.
+    #[arg(long, conflicts_with_all(["verbose", "metadata", "print0", "join"]))]
+    pub dump: bool,
+
+    /// Decode the images the number of times and print the durations.
+    ///
+    /// The minimum, median and maximum durations are output to stderr, and
+    /// nothing is written.
+    #[arg(
+        long,
+        hide(true),
+        value_name("NUMBER"),
+        conflicts_with_all(["metadata", "join", "dump", "expect", "expect_file"])
+    )]
+    pub benchmark: Option<NonZeroU32>,
+
+    /// Input image files.
+    ///
+    /// If [IMAGE] is not specified, or if "-" is specified, the image will be
+    /// read from standard input. Supported raster image formats are based on
+    /// the formats supported by the image crate. The format of [IMAGE] is
+    /// determined based on the extension or the magic number if possible. If
+    /// the format cannot be determined, use '--type'. Note that the SVG image
+    /// is rasterized before scanning. If multiple images are specified, they
+    /// are decoded in order, unless '--join' is specified.
+    #[arg(value_name("IMAGE"), value_hint(ValueHint::FilePath))]
+    pub input: Vec<PathBuf>,
+
+    /// List the input formats supported by this build and exit.
+    ///
+    /// Each line contains the name of the format and its description. The
+    /// formats which are disabled at compile time are not listed.
+    #[arg(long, exclusive(true))]
+    pub list_formats: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct Estimate {
+    /// Read input data from a file.
+    ///
+    /// This option can be specified multiple times, in which case the contents
+    /// of the files are concatenated in the order specified.
+    #[arg(
+        short,
+        long,
+        value_name("FILE"),
+        value_hint(ValueHint::FilePath),
+        conflicts_with("input")
+    )]
+    pub read_from: Vec<PathBuf>,
+
+    /// Error correction level.
+    #[arg(
+        short('l'),
+        long,
+        value_enum,
+        default_value_t,
+        visible_alias("level"),
+        value_name("LEVEL"),
+        ignore_case(true)
+    )]
+    pub error_correction_level: Ecc,
+
+    /// The mode of the data.
+    ///
+    /// If this option is not specified, use the optimal encoding.
+    #[arg(long, value_enum, value_name("MODE"), ignore_case(true))]
+    pub mode: Option<Mode>,
+
+    /// The type of QR code.
+    ///
+    /// If "auto" is specified, the versions of Micro QR code are tried before
+    /// the versions of normal QR code.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        value_name("TYPE"),
+        ignore_case(true)
+    )]
+    pub variant: Variant,
+
+    /// The format of the estimate.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t,
+        value_name("FORMAT"),
+        ignore_case(true)
+    )]
+    pub format: EstimateFormat,
+
+    /// Input data.
+    ///
+    /// If [STRING] is not specified, data will be read from standard input.
+    /// [STRING] must be a valid UTF-8 string.
+    #[arg(value_name("STRING"))]
+    pub input: Option<String>,
+}
+
+impl Opt {
+    /// Validates arguments.
+    #[allow(clippy::too_many_lines)]
+    pub fn validate(self) -> anyhow::Result<Self> {
+        if let Some(Command::Encode(ref arg)) = self.command {
+            if arg.error_correction_level == ErrorCorrectionLevel::Auto && arg.capacity {
+                return Err(anyhow!(
+                    "automatic error correction level cannot be used with the capacity"
+                ));
+            }
+            if arg.error_correction_level == ErrorCorrectionLevel::Auto
+                && arg.symbol_version.is_none()
+            {
+                return Err(anyhow!(
+                    "automatic error correction level requires the symbol version"
+                ));
+            }
+            if arg.min_ecc.is_some() && arg.error_correction_level != ErrorCorrectionLevel::Auto {
+                return Err(anyhow!(
+                    "minimum error correction level requires the automatic error correction level"
+                ));
+            }
+            if let (Some(min), Some(max)) = (arg.min_version, arg.max_version) {
+                if min > max {
+                    return Err(anyhow!(
+                        "minimum version is greater than the maximum version"
+                    ));
+                }
+            }
+            if cfg!(not(unix)) && arg.output_fd.is_some() {
+                return Err(anyhow!(
+                    "file descriptors can only be used as the output on Unix"
+                ));
+            }
+            if arg.dump_all_masks {
+                if arg.output.is_none() && arg.output_dir.is_none() {
+                    return Err(anyhow!("all mask patterns can only be output to files"));
+                }
+                if arg.variant != Variant::Normal {
+                    return Err(anyhow!(
+                        "all mask patterns can only be output for a normal QR code"
+                    ));
+                }
+            }
+            if !arg.segment.is_empty() && arg.variant != Variant::Normal {
+                return Err(anyhow!("segments can only be encoded in a normal QR code"));
+            }
+            if arg.variant == Variant::Auto && arg.symbol_version.is_some() {
+                return Err(anyhow!(
+                    "automatic type of QR code cannot be used with the symbol version"
+                ));
+            }
+            if arg.symbol_version == Some(SymbolVersion::Stdin)
+                && (arg.input.is_some()
+                    || !arg.read_from.is_empty()
+                    || arg.from_matrix.is_some()
+                    || !arg.segment.is_empty()
+                    || arg.capacity)
+            {
+                return Err(anyhow!(
+                    "symbol version can only be read from standard input with the input data"
+                ));
+            }
+            if arg.input_charset.is_some() && (arg.mode != Some(Mode::Kanji)) {
+                return Err(anyhow!("input charset can only be specified in kanji mode"));
+            }
+            if arg.bilevel {
+                if arg.output_format != OutputFormat::Png {
+                    return Err(anyhow!("output format is not PNG"));
+                }
+                let is_bilevel = |color: &Color| {
+                    let color = color.to_rgba8();
+                    color == [u8::MIN, u8::MIN, u8::MIN, u8::MAX]
+                        || color == [u8::MAX, u8::MAX, u8::MAX, u8::MAX]
+                };
+                if !(is_bilevel(&arg.foreground)
+                    && is_bilevel(&arg.background)
+                    && arg.finder_color.as_ref().map_or(true, is_bilevel)
+                    && arg.quiet_zone_color.as_ref().map_or(true, is_bilevel))
+                {
+                    return Err(anyhow!(
+                        "only opaque black and white can be used with a 1-bit image"
+                    ));
+                }
+            }
+            if arg.bit_depth == BitDepth::Sixteen {
+                if arg.output_format != OutputFormat::Png {
+                    return Err(anyhow!("output format is not PNG"));
+                }
+                if arg.foreground.a < 1.0 || arg.background.a < 1.0 {
+                    return Err(anyhow!(
+                        "colors with alpha cannot be used with 16-bit grayscale"
+                    ));
+                }
+                #[cfg(feature = "output-with-title")]
+                let has_title = arg.title.as_ref().is_some_and(|title| !title.is_empty());
+                #[cfg(not(feature = "output-with-title"))]
+                let has_title = false;
+                if has_title || arg.finder_color.is_some() || arg.quiet_zone_color.is_some() {
+                    return Err(anyhow!(
+                        "16-bit grayscale cannot be used with the title, the finder color or the quiet zone color"
+                    ));
+                }
+            }
+            #[cfg(all(feature = "clipboard", feature = "output-as-avif"))]
+            if arg.clipboard && (arg.output_format == OutputFormat::Avif) {
+                return Err(anyhow!("AVIF image cannot be copied to the clipboard"));
+            }
+            #[cfg(feature = "clipboard")]
+            if arg.clipboard && (arg.output_format == OutputFormat::Pbm) {
+                return Err(anyhow!("PBM image cannot be copied to the clipboard"));
+            }
+            if let Some(ModuleSize::Percent(_)) = arg.size {
+                if arg.output_format != OutputFormat::Svg {
+                    return Err(anyhow!("percentage module size can only be used with SVG"));
+                }
+                if arg.svg_viewbox {
+                    return Err(anyhow!(
+                        "percentage module size cannot be used with the responsive SVG"
+                    ));
+                }
+            }
+            if (arg.svg_viewbox
+                || arg.svg_optimize == SvgOptimize::Always
+                || arg.crisp_edges
+                || arg.svg_background == SvgBackground::None
+                || arg.svg_symbol.is_some())
+                && (arg.output_format != OutputFormat::Svg)
+            {
+                return Err(anyhow!("output format is not SVG"));
+            }
+            #[cfg(feature = "decode-from-svg")]
+            if arg.verify && !matches!(arg.output_format, OutputFormat::Png | OutputFormat::Svg) {
+                return Err(anyhow!("output format is not PNG or SVG"));
+            }
+            #[cfg(not(feature = "decode-from-svg"))]
+            if arg.verify && (arg.output_format != OutputFormat::Png) {
+                return Err(anyhow!("output format is not PNG"));
+            }
+            if arg.debug_overlay
+                && (!arg.output_format.is_image()
+                    || arg.output_format == OutputFormat::Svg
+                    || arg.bit_depth == BitDepth::Sixteen)
+            {
+                return Err(anyhow!("debug overlay can only be used with an RGBA image"));
+            }
+            if arg.nearest_neighbor_only && (arg.output_format != OutputFormat::Png) {
+                return Err(anyhow!(
+                    "nearest neighbor assertion can only be used with a PNG image"
+                ));
+            }
+            #[cfg(feature = "output-with-title")]
+            if arg.nearest_neighbor_only && arg.title.as_ref().is_some_and(|t| !t.is_empty()) {
+                return Err(anyhow!(
+                    "nearest neighbor assertion cannot be used with the title"
+                ));
+            }
+            #[cfg(feature = "output-with-title")]
+            if arg.pad.is_some() && arg.title.as_ref().is_some_and(|t| !t.is_empty()) {
+                return Err(anyhow!("padding cannot be used with the title"));
+            }
+            if (arg.finder_color.is_some() || arg.quiet_zone_color.is_some())
+                && !arg.output_format.is_image()
+            {
+                return Err(anyhow!("output format is not PNG or SVG"));
+            }
+            if arg.has_circle_modules() {
+                if !arg.output_format.is_image() {
+                    return Err(anyhow!("output format is not PNG or SVG"));
+                }
+                if arg.bit_depth == BitDepth::Sixteen || arg.nearest_neighbor_only {
+                    return Err(anyhow!(
+                        "circle modules cannot be used with 16-bit grayscale or the nearest neighbor assertion"
+                    ));
+                }
+            }
+            if arg.dpi.is_some() && (arg.output_format != OutputFormat::Png) {
+                return Err(anyhow!("output format is not PNG"));
+            }
+            if (arg.png_compression.is_some() || arg.png_filter.is_some())
+                && (arg.output_format != OutputFormat::Png)
+            {
+                return Err(anyhow!("output format is not PNG"));
+            }
+            #[cfg(feature = "optimize-output-png")]
+            if arg.optimize_png.is_some() && (arg.output_format != OutputFormat::Png) {
+                return Err(anyhow!("output format is not PNG"));
+            }
+            if arg.repeat.is_some() && !arg.output_format.is_image() {
+                return Err(anyhow!("output format is not PNG or SVG"));
+            }
+            #[cfg(feature = "output-with-title")]
+            if arg.title.as_ref().is_some_and(|title| !title.is_empty())
+                && !arg.output_format.is_image()
+            {
+                return Err(anyhow!("output format is not PNG or SVG"));
+            }
+            let is_monochrome = arg.output_format.is_monochrome();
+            let is_default_colors = (arg.foreground
+                == Color::from_rgba8(u8::MIN, u8::MIN, u8::MIN, u8::MAX))
+                && (arg.background == Color::from_rgba8(u8::MAX, u8::MAX, u8::MAX, u8::MAX));
+            if is_monochrome && !is_default_colors {
+                return Err(anyhow!(
+                    "foreground and/or background colors cannot be changed"
+                ));
+            }
+        }
+        if let Some(Command::Decode(ref arg)) = self.command {
+            if let (Some(min), Some(max)) = (arg.min_size, arg.max_size) {
+                if min > max {
+                    return Err(anyhow!("minimum size is greater than the maximum size"));
+                }
+            }
+            if let Some((columns, rows)) = arg.mosaic {
+                if arg.input.len() as u64 != u64::from(columns) * u64::from(rows) {
+                    return Err(anyhow!(
+                        "{} images cannot be stitched in a grid of {columns}x{rows}",
+                        arg.input.len()
+                    ));
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns the command which resolves colors with the palette specified by
+    /// '--palette'.
+    ///
+    /// The command line is parsed once without parsing colors to read the
+    /// palette, since the colors may refer to the names in it.
+    pub fn command_with_palette() -> anyhow::Result<clap::Command> {
+        const COLOR_ARGS: [&str; 4] = [
+            "foreground",
+            "background",
+            "finder_color",
+            "quiet_zone_color",
+        ];
+
+        let command = COLOR_ARGS.iter().fold(Self::command(), |command, id| {
+            command.mut_subcommand("encode", |encode| {
+                encode.mut_arg(id, |arg| arg.value_parser(value_parser!(String)))
+            })
+        });
+        let Some(path) = command.try_get_matches().ok().and_then(|matches| {
+            matches
+                .subcommand_matches("encode")
+                .and_then(|matches| matches.get_one::<PathBuf>("palette").cloned())
+        }) else {
+            return Ok(Self::command());
+        };
+        let palette = Palette::from_file(path)?;
+        Ok(COLOR_ARGS.iter().fold(Self::command(), |command, id| {
+            let palette = palette.clone();
+            command.mut_subcommand("encode", |encode| {
+                encode.mut_arg(id, |arg| arg.value_parser(move |s: &str| palette.parse(s)))
+            })
+        }))
+    }
+
+    /// Generates shell completion and print it.
+    pub fn print_completion(gen: impl Generator) {
+        clap_complete::generate(
+            gen,
+            &mut Self::command(),
+            Self::command().get_name(),
+            &mut io::stdout(),
+        );
+    }
+}
+
+impl Encode {
+    /// Returns `true` if any of the modules are drawn as circles.
+    pub fn has_circle_modules(&self) -> bool {
+        self.module_shape == ModuleShape::Circle || self.finder_shape == ModuleShape::Circle
+    }
+
+    /// Applies the configuration to the options which are not specified on the
+    /// command line.
+    pub fn apply_config(&mut self, config: EncodeConfig, matches: &ArgMatches) {
+        let is_unspecified = |id| matches.value_source(id) != Some(ValueSource::CommandLine);
+        if is_unspecified("size") {
+            self.size = config.size.map(ModuleSize::Pixels).or(self.size);
+        }
+        if let Some(level) = config
+            .error_correction_level
+            .filter(|_| is_unspecified("error_correction_level"))
+        {
+            self.error_correction_level = level.into();
+        }
+        if is_unspecified("margin") {
+            self.margin = config.margin.or(self.margin);
+        }
+        // Colors cannot be changed for monochrome output formats, so the
+        // configured colors are only used for the other formats.
+        if self.output_format.is_monochrome() {
+            return;
+        }
+        if let Some(color) = config.foreground.filter(|_| is_unspecified("foreground")) {
+            self.foreground = color;
+        }
+        if let Some(color) = config.background.filter(|_| is_unspecified("background")) {
+            self.background = color;
+        }
+    }
+
+    /// Applies the colors specified by the environment variables to the
+    /// options which are not specified on the command line.
+    ///
+    /// The environment variables take precedence over the configuration file.
+    /// Empty values are ignored.
+    pub fn apply_env(&mut self, matches: &ArgMatches) -> anyhow::Result<()> {
+        if self.output_format.is_monochrome() {
+            return Ok(());
+        }
+        let is_unspecified = |id| matches.value_source(id) != Some(ValueSource::CommandLine);
+        for (id, name, color) in [
+            ("foreground", FOREGROUND_ENV, &mut self.foreground),
+            ("background", BACKGROUND_ENV, &mut self.background),
+        ] {
+            let Some(value) = env::var_os(name).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            if !is_unspecified(id) {
+                continue;
+            }
+            *color = value
+                .to_str()
+                .ok_or_else(|| anyhow!("{name} is not valid UTF-8"))
+                .and_then(|value| color::parse(value).map_err(anyhow::Error::from))
+                .with_context(|| format!("could not parse the color in {name}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum SvgBackground {
+    /// Draw the background rectangle filled with the background color.
+    #[default]
+    Color,
+
+    /// Do not draw the background rectangle.
+    None,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum SvgOptimize {
+    /// Merge the dark modules only if the QR code is version 10 or larger.
+    #[default]
+    Auto,
+
+    /// Always merge the dark modules.
+    Always,
+
+    /// Never merge the dark modules.
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum BitDepth {
+    /// 8-bit RGBA.
+    #[default]
+    #[value(name = "8")]
+    Eight,
+
+    /// 16-bit grayscale.
+    #[value(name = "16")]
+    Sixteen,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum MarginUnit {
+    /// The number of modules.
+    #[default]
+    Modules,
+
+    /// The number of pixels.
+    Pixels,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum CapacityFormat {
+    /// The number of characters.
+    #[default]
+    Text,
+
+    /// A JSON object which also contains the parameters.
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum EstimateFormat {
+    /// Human-readable text.
+    #[default]
+    Text,
+
+    /// A JSON object which also contains the parameters.
+    Json,
+}
+
+/// Error correction level.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Ecc {
+    /// Level L.
+    ///
+    /// 7% of codewords can be restored.
+    L,
+
+    /// Level M.
+    ///
+    /// 15% of codewords can be restored.
+    #[default]
+    M,
+
+    /// Level Q.
+    ///
+    /// 25% of codewords can be restored.
+    Q,
+
+    /// Level H.
+    ///
+    /// 30% of codewords can be restored.
+    H,
+}
+
+impl From<Ecc> for encode::Ecc {
+    fn from(level: Ecc) -> Self {
+        match level {
+            Ecc::L => Self::L,
+            Ecc::M => Self::M,
+            Ecc::Q => Self::Q,
+            Ecc::H => Self::H,
+        }
+    }
+}
+
+/// The mode of the data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Mode {
+    /// All digits.
+    Numeric,
+
+    /// Alphanumerics and few symbols.
+    Alphanumeric,
+
+    /// Arbitrary binary data.
+    Byte,
+
+    /// Shift JIS text.
+    Kanji,
+}
+
+impl From<Mode> for encode::Mode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Numeric => Self::Numeric,
+            Mode::Alphanumeric => Self::Alphanumeric,
+            Mode::Byte => Self::Byte,
+            Mode::Kanji => Self::Kanji,
+        }
+    }
+}
+
+/// The type of QR code.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Variant {
+    /// Normal QR code.
+    #[default]
+    Normal,
+
+    /// Micro QR code.
+    Micro,
+
+    /// Micro QR code if the data can be stored in it, otherwise normal QR
+    /// code.
+    Auto,
+}
+
+impl From<Variant> for encode::Variant {
+    fn from(variant: Variant) -> Self {
+        match variant {
+            Variant::Normal => Self::Normal,
+            Variant::Micro => Self::Micro,
+            Variant::Auto => Self::Auto,
+        }
+    }
+}
+
+/// Error correction level.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ErrorCorrectionLevel {
+    /// Level L.
+    ///
+    /// 7% of codewords can be restored.
+    L,
+
+    /// Level M.
+    ///
+    /// 15% of codewords can be restored.
+    #[default]
+    M,
+
+    /// Level Q.
+    ///
+    /// 25% of codewords can be restored.
+    Q,
 
-    /// Print only the metadata.
+    /// Level H.
     ///
-    /// It is output to stderr.
-    #[arg(long)]
-    pub metadata: bool,
+    /// 30% of codewords can be restored.
+    H,
 
-    /// Input image file.
+    /// The highest level which can store the data in the given version.
     ///
-    /// If [IMAGE] is not specified, or if "-" is specified, the image will be
-    /// read from standard input. Supported raster image formats are based on
-    /// the formats supported by the image crate. The format of [IMAGE] is
-    /// determined based on the extension or the magic number if possible. If
-    /// the format cannot be determined, use '--type'. Note that the SVG image
-    /// is rasterized before scanning.
-    #[arg(value_name("IMAGE"), value_hint(ValueHint::FilePath))]
-    pub input: Option<PathBuf>,
+    /// This requires '--symbol-version'.
+    Auto,
 }
 
-impl Opt {
-    /// Validates arguments.
-    pub fn validate(self) -> anyhow::Result<Self> {
-        if let Some(Command::Encode(ref arg)) = self.command {
-            #[cfg(feature = "optimize-output-png")]
-            if arg.optimize_png.is_some() && (arg.output_format != OutputFormat::Png) {
-                return Err(anyhow!("output format is not PNG"));
-            }
-            let is_monochrome = matches!(
-                arg.output_format,
-                OutputFormat::Pic
-                    | OutputFormat::Ascii
-                    | OutputFormat::AsciiInvert
-                    | OutputFormat::Unicode
-                    | OutputFormat::UnicodeInvert
-            );
-            let is_default_colors = (arg.foreground
-                == Color::from_rgba8(u8::MIN, u8::MIN, u8::MIN, u8::MAX))
-                && (arg.background == Color::from_rgba8(u8::MAX, u8::MAX, u8::MAX, u8::MAX));
-            if is_monochrome && !is_default_colors {
-                return Err(anyhow!(
-                    "foreground and/or background colors cannot be changed"
-                ));
-            }
+impl ErrorCorrectionLevel {
+    /// Returns the error correction level, or [`None`] if it is determined
+    /// automatically.
+    pub const fn to_ecc(self) -> Option<encode::Ecc> {
+        match self {
+            Self::L => Some(encode::Ecc::L),
+            Self::M => Some(encode::Ecc::M),
+            Self::Q => Some(encode::Ecc::Q),
+            Self::H => Some(encode::Ecc::H),
+            Self::Auto => None,
         }
-        Ok(self)
     }
+}
 
-    /// Generates shell completion and print it.
-    pub fn print_completion(gen: impl Generator) {
-        clap_complete::generate(
-            gen,
-            &mut Self::command(),
-            Self::command().get_name(),
-            &mut io::stdout(),
-        );
+impl From<Ecc> for ErrorCorrectionLevel {
+    fn from(level: Ecc) -> Self {
+        match level {
+            Ecc::L => Self::L,
+            Ecc::M => Self::M,
+            Ecc::Q => Self::Q,
+            Ecc::H => Self::H,
+        }
     }
 }
 
@@ -367,41 +1840,6 @@ impl Generator for Shell {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
-pub enum Ecc {
-    /// Level L.
-    ///
-    /// 7% of codewords can be restored.
-    L,
-
-    /// Level M.
-    ///
-    /// 15% of codewords can be restored.
-    #[default]
-    M,
-
-    /// Level Q.
-    ///
-    /// 25% of codewords can be restored.
-    Q,
-
-    /// Level H.
-    ///
-    /// 30% of codewords can be restored.
-    H,
-}
-
-impl From<Ecc> for qrcode::EcLevel {
-    fn from(level: Ecc) -> Self {
-        match level {
-            Ecc::L => Self::L,
-            Ecc::M => Self::M,
-            Ecc::Q => Self::Q,
-            Ecc::H => Self::H,
-        }
-    }
-}
-
 #[derive(Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
 pub enum OutputFormat {
     /// Portable Network Graphics.
@@ -413,9 +1851,33 @@ pub enum OutputFormat {
     /// Scalable Vector Graphics.
     Svg,
 
+    /// AV1 Image File Format.
+    ///
+    /// This outputs 32-bit RGBA AVIF image. The image is always encoded lossily
+    /// with the highest quality, so the edges of modules may be slightly
+    /// blurred. Use PNG if a lossless image is required.
+    #[cfg(feature = "output-as-avif")]
+    Avif,
+
     /// PIC markup language.
     Pic,
 
+    /// Portable Bitmap.
+    ///
+    /// This outputs binary (P4) PBM image.
+    Pbm,
+
+    /// C source code which defines the bitmap as an array.
+    ///
+    /// Each row is packed into bytes, in which the most significant bit is the
+    /// leftmost pixel and a set bit is a dark module.
+    CArray,
+
+    /// Rust source code which defines the bitmap as an array.
+    ///
+    /// The array is the same as "c-array".
+    RustArray,
+
     /// To the terminal using 4-bit ANSI escape sequences.
     #[cfg(feature = "output-as-ansi")]
     Ansi,
@@ -448,8 +1910,90 @@ pub enum OutputFormat {
     UnicodeInvert,
 }
 
+impl OutputFormat {
+    /// Guesses the output format from the extension of the path.
+    ///
+    /// Returns [`None`] if the extension is not associated with any image
+    /// format.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?;
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "svg" => Some(Self::Svg),
+            "pic" => Some(Self::Pic),
+            "pbm" => Some(Self::Pbm),
+            "h" => Some(Self::CArray),
+            "rs" => Some(Self::RustArray),
+            #[cfg(feature = "output-as-avif")]
+            "avif" => Some(Self::Avif),
+            _ => None,
+        }
+    }
+
+    /// Returns the extension of the file for the output format.
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+            Self::Pic => "pic",
+            Self::Pbm => "pbm",
+            Self::CArray => "h",
+            Self::RustArray => "rs",
+            #[cfg(feature = "output-as-avif")]
+            Self::Avif => "avif",
+            _ => "txt",
+        }
+    }
+
+    /// Returns `true` if the output format supports transparency.
+    pub const fn supports_transparency(&self) -> bool {
+        match self {
+            Self::Png | Self::Svg => true,
+            #[cfg(feature = "output-as-avif")]
+            Self::Avif => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the output format is an image which can contain the
+    /// title and the colored finder patterns.
+    pub const fn is_image(&self) -> bool {
+        match self {
+            Self::Png | Self::Svg => true,
+            #[cfg(feature = "output-as-avif")]
+            Self::Avif => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the module size in pixels when '--size' is not specified.
+    pub const fn default_module_size(&self) -> u32 {
+        match self {
+            Self::Png | Self::Svg | Self::Pic | Self::Pbm => 8,
+            #[cfg(feature = "output-as-avif")]
+            Self::Avif => 8,
+            _ => 1,
+        }
+    }
+
+    /// Returns `true` if the output format does not support colors.
+    pub const fn is_monochrome(&self) -> bool {
+        matches!(
+            self,
+            Self::Pic
+                | Self::Pbm
+                | Self::CArray
+                | Self::RustArray
+                | Self::Ascii
+                | Self::AsciiInvert
+                | Self::Unicode
+                | Self::UnicodeInvert
+        )
+    }
+}
+
 #[cfg(feature = "optimize-output-png")]
-#[derive(Clone, Debug, ValueEnum)]
+#[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum PngOptimizationLevel {
     /// Level 0.
     ///
@@ -491,29 +2035,309 @@ impl From<PngOptimizationLevel> for u8 {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, ValueEnum)]
-pub enum Mode {
-    /// All digits.
-    Numeric,
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum InputEncoding {
+    /// Hexadecimal.
+    ///
+    /// ASCII whitespace is ignored.
+    Hex,
 
-    /// Alphanumerics and few symbols.
-    Alphanumeric,
+    /// Base64 with the standard alphabet.
+    ///
+    /// The padding is optional, and ASCII whitespace is ignored.
+    Base64,
+}
 
-    /// Arbitrary binary data.
-    Byte,
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum InputCharset {
+    /// Shift JIS.
+    ///
+    /// The input data is encoded as is.
+    #[value(name = "shift-jis", alias("sjis"))]
+    ShiftJis,
 
-    /// Shift JIS text.
-    Kanji,
+    /// UTF-8.
+    ///
+    /// The input data is converted to Shift JIS.
+    #[value(name = "utf-8", alias("utf8"))]
+    Utf8,
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
-pub enum Variant {
-    /// Normal QR code.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum PngCompression {
+    /// The default compression level of the encoder.
     #[default]
-    Normal,
+    Default,
 
-    /// Micro QR code.
-    Micro,
+    /// Fast, minimal compression.
+    Fast,
+
+    /// High compression level.
+    Best,
+}
+
+impl From<PngCompression> for png::Compression {
+    fn from(compression: PngCompression) -> Self {
+        match compression {
+            PngCompression::Default => Self::Default,
+            PngCompression::Fast => Self::Fast,
+            PngCompression::Best => Self::Best,
+        }
+    }
+}
+
+impl From<PngCompression> for CompressionType {
+    fn from(compression: PngCompression) -> Self {
+        match compression {
+            PngCompression::Default => Self::default(),
+            PngCompression::Fast => Self::Fast,
+            PngCompression::Best => Self::Best,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum PngFilter {
+    /// No filter.
+    #[value(alias("no-filter"))]
+    None,
+
+    /// The difference from the previous pixel in the same row.
+    Sub,
+
+    /// The difference from the pixel in the previous row.
+    Up,
+
+    /// The difference from the average of the left and upper pixels.
+    Avg,
+
+    /// The Paeth predictor of the left, upper and upper left pixels.
+    Paeth,
+
+    /// Choose one of the above filters for each row.
+    #[default]
+    Adaptive,
+}
+
+impl From<PngFilter> for PngFilterType {
+    fn from(filter: PngFilter) -> Self {
+        match filter {
+            PngFilter::None => Self::NoFilter,
+            PngFilter::Sub => Self::Sub,
+            PngFilter::Up => Self::Up,
+            PngFilter::Avg => Self::Avg,
+            PngFilter::Paeth => Self::Paeth,
+            PngFilter::Adaptive => Self::Adaptive,
+        }
+    }
+}
+
+impl From<PngFilter> for (png::FilterType, png::AdaptiveFilterType) {
+    fn from(filter: PngFilter) -> Self {
+        use png::{AdaptiveFilterType, FilterType};
+
+        match filter {
+            PngFilter::None => (FilterType::NoFilter, AdaptiveFilterType::NonAdaptive),
+            PngFilter::Sub => (FilterType::Sub, AdaptiveFilterType::NonAdaptive),
+            PngFilter::Up => (FilterType::Up, AdaptiveFilterType::NonAdaptive),
+            PngFilter::Avg => (FilterType::Avg, AdaptiveFilterType::NonAdaptive),
+            PngFilter::Paeth => (FilterType::Paeth, AdaptiveFilterType::NonAdaptive),
+            PngFilter::Adaptive => (FilterType::default(), AdaptiveFilterType::Adaptive),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ModuleSize {
+    /// The module size in pixels.
+    Pixels(NonZeroU32),
+
+    /// The module size as a percentage of the viewport width.
+    Percent(NonZeroU32),
+}
+
+fn parse_module_size(s: &str) -> Result<ModuleSize, ParseIntError> {
+    s.strip_suffix('%').map_or_else(
+        || s.parse().map(ModuleSize::Pixels),
+        |percent| percent.parse().map(ModuleSize::Percent),
+    )
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SymbolVersion {
+    /// The version number.
+    Number(i16),
+
+    /// The version number which is read from standard input.
+    Stdin,
+}
+
+fn parse_symbol_version(s: &str) -> anyhow::Result<SymbolVersion> {
+    if s == "-" {
+        return Ok(SymbolVersion::Stdin);
+    }
+    parse_symbol_version_number(s).map(SymbolVersion::Number)
+}
+
+/// Parses the version number of the symbol.
+///
+/// # Errors
+///
+/// Returns [`Err`] if `s` is not a number between 1 and 40.
+pub fn parse_symbol_version_number(s: &str) -> anyhow::Result<i16> {
+    let version = s.parse()?;
+    if !(1..=40).contains(&version) {
+        return Err(anyhow!("{version} is not in 1..=40"));
+    }
+    Ok(version)
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Threshold {
+    /// The fixed threshold.
+    Fixed(u8),
+
+    /// The threshold determined by Otsu's method.
+    Otsu,
+}
+
+fn parse_threshold(s: &str) -> anyhow::Result<Threshold> {
+    if s.eq_ignore_ascii_case("otsu") {
+        return Ok(Threshold::Otsu);
+    }
+    s.parse()
+        .map(Threshold::Fixed)
+        .map_err(|_| anyhow!("threshold must be a number between 0 and 255, or \"otsu\""))
+}
+
+fn parse_opacity(s: &str) -> anyhow::Result<f32> {
+    s.parse()
+        .ok()
+        .filter(|opacity| (0.0..=1.0).contains(opacity))
+        .ok_or_else(|| anyhow!("opacity must be a number between 0.0 and 1.0"))
+}
+
+fn parse_segment(s: &str) -> anyhow::Result<Segment> {
+    let (text, name) = s
+        .rsplit_once(':')
+        .ok_or_else(|| anyhow!("segment must be <TEXT>:<CHARSET>"))?;
+    let charset =
+        Charset::from_str(name, true).map_err(|_| anyhow!("invalid character set \"{name}\""))?;
+    Segment::new(text, charset).map_err(|_| anyhow!("\"{text}\" cannot be encoded in {name}"))
+}
+
+fn parse_svg_id(s: &str) -> anyhow::Result<String> {
+    let mut chars = s.chars();
+    let is_valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if is_valid {
+        Ok(s.into())
+    } else {
+        Err(anyhow!(
+            "ID must start with a letter or an underscore, and contain only letters, digits, hyphens, underscores and periods"
+        ))
+    }
+}
+
+fn parse_length(s: &str) -> anyhow::Result<f64> {
+    [("mm", 1.0), ("cm", 10.0), ("in", 25.4)]
+        .into_iter()
+        .find_map(|(unit, scale)| {
+            let length = s.strip_suffix(unit)?.trim_end().parse::<f64>().ok()?;
+            Some(length * scale)
+        })
+        .filter(|length| length.is_finite() && *length > 0.0)
+        .ok_or_else(|| anyhow!("length must be a positive number with the unit mm, cm or in"))
+}
+
+fn parse_timeout(s: &str) -> anyhow::Result<Duration> {
+    s.parse()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .filter(|timeout| !timeout.is_zero())
+        .ok_or_else(|| anyhow!("timeout must be a positive number of seconds"))
+}
+
+fn parse_repeat(s: &str) -> anyhow::Result<(u32, u32)> {
+    const MAX_GRID: u32 = 100;
+
+    s.split_once(['x', 'X'])
+        .and_then(|(columns, rows)| Some((columns.parse().ok()?, rows.parse().ok()?)))
+        .filter(|grid| [grid.0, grid.1].iter().all(|n| (1..=MAX_GRID).contains(n)))
+        .ok_or_else(|| {
+            anyhow!("grid must be <COLUMNS>x<ROWS> with integers from 1 to {MAX_GRID}")
+        })
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Escape {
+    /// Escape the contents only if standard output is a terminal.
+    #[default]
+    Auto,
+
+    /// Always escape the contents.
+    Always,
+
+    /// Never escape the contents.
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Preprocess {
+    /// Use the luma of the gamma-encoded color.
+    #[default]
+    Grayscale,
+
+    /// Use the relative luminance of the linear color.
+    Luminance,
+
+    /// Use the inverted saturation, so that colored modules become dark.
+    Saturation,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Mirror {
+    /// Always flip the image.
+    Always,
+
+    /// Flip the image only if no QR code can be decoded from the original
+    /// image.
+    Auto,
+}
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum ResizeFilter {
+    /// Nearest neighbor.
+    ///
+    /// This preserves sharp edges of modules.
+    #[default]
+    Nearest,
+
+    /// Linear filter.
+    Triangle,
+
+    /// Cubic filter.
+    CatmullRom,
+
+    /// Gaussian filter.
+    Gaussian,
+
+    /// Lanczos with window 3.
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(filter: ResizeFilter) -> Self {
+        match filter {
+            ResizeFilter::Nearest => Self::Nearest,
+            ResizeFilter::Triangle => Self::Triangle,
+            ResizeFilter::CatmullRom => Self::CatmullRom,
+            ResizeFilter::Gaussian => Self::Gaussian,
+            ResizeFilter::Lanczos3 => Self::Lanczos3,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -644,21 +2468,111 @@ mod tests {
     }
 
     #[test]
-    fn default_ecc() {
-        assert_eq!(Ecc::default(), Ecc::M);
+    fn default_output_format() {
+        assert_eq!(OutputFormat::default(), OutputFormat::Png);
     }
 
     #[test]
-    fn from_ecc_to_ec_level() {
-        assert_eq!(qrcode::EcLevel::from(Ecc::L), qrcode::EcLevel::L);
-        assert_eq!(qrcode::EcLevel::from(Ecc::M), qrcode::EcLevel::M);
-        assert_eq!(qrcode::EcLevel::from(Ecc::Q), qrcode::EcLevel::Q);
-        assert_eq!(qrcode::EcLevel::from(Ecc::H), qrcode::EcLevel::H);
+    fn output_format_from_path() {
+        assert_eq!(OutputFormat::from_path("a.png"), Some(OutputFormat::Png));
+        assert_eq!(OutputFormat::from_path("a.PNG"), Some(OutputFormat::Png));
+        assert_eq!(OutputFormat::from_path("a.svg"), Some(OutputFormat::Svg));
+        assert_eq!(OutputFormat::from_path("a.pic"), Some(OutputFormat::Pic));
+        assert_eq!(OutputFormat::from_path("a.pbm"), Some(OutputFormat::Pbm));
+        assert_eq!(OutputFormat::from_path("a.h"), Some(OutputFormat::CArray));
+        assert_eq!(
+            OutputFormat::from_path("a.rs"),
+            Some(OutputFormat::RustArray)
+        );
+        #[cfg(feature = "output-as-avif")]
+        assert_eq!(OutputFormat::from_path("a.avif"), Some(OutputFormat::Avif));
+        assert_eq!(OutputFormat::from_path("a.txt"), None);
+        assert_eq!(OutputFormat::from_path("a"), None);
     }
 
     #[test]
-    fn default_output_format() {
-        assert_eq!(OutputFormat::default(), OutputFormat::Png);
+    fn parse_threshold_value() {
+        assert_eq!(parse_threshold("0").unwrap(), Threshold::Fixed(0));
+        assert_eq!(parse_threshold("128").unwrap(), Threshold::Fixed(128));
+        assert_eq!(parse_threshold("255").unwrap(), Threshold::Fixed(255));
+        assert_eq!(parse_threshold("otsu").unwrap(), Threshold::Otsu);
+        assert_eq!(parse_threshold("OTSU").unwrap(), Threshold::Otsu);
+        assert!(parse_threshold("256").is_err());
+        assert!(parse_threshold("-1").is_err());
+        assert!(parse_threshold("").is_err());
+    }
+
+    #[test]
+    fn parse_symbol_version_value() {
+        assert_eq!(parse_symbol_version("1").unwrap(), SymbolVersion::Number(1));
+        assert_eq!(
+            parse_symbol_version("40").unwrap(),
+            SymbolVersion::Number(40)
+        );
+        assert_eq!(parse_symbol_version("-").unwrap(), SymbolVersion::Stdin);
+        assert_eq!(
+            parse_symbol_version("41").unwrap_err().to_string(),
+            "41 is not in 1..=40"
+        );
+        assert!(parse_symbol_version("0").is_err());
+        assert!(parse_symbol_version("-1").is_err());
+        assert!(parse_symbol_version("").is_err());
+    }
+
+    #[test]
+    fn parse_module_size_value() {
+        assert_eq!(
+            parse_module_size("8").unwrap(),
+            ModuleSize::Pixels(NonZeroU32::new(8).unwrap())
+        );
+        assert_eq!(
+            parse_module_size("5%").unwrap(),
+            ModuleSize::Percent(NonZeroU32::new(5).unwrap())
+        );
+        assert!(parse_module_size("0").is_err());
+        assert!(parse_module_size("0%").is_err());
+        assert!(parse_module_size("2.5%").is_err());
+        assert!(parse_module_size("%").is_err());
+        assert!(parse_module_size("").is_err());
+    }
+
+    #[allow(clippy::float_cmp)]
+    #[test]
+    fn parse_opacity_value() {
+        assert_eq!(parse_opacity("0").unwrap(), 0.0);
+        assert_eq!(parse_opacity("0.5").unwrap(), 0.5);
+        assert_eq!(parse_opacity("1.0").unwrap(), 1.0);
+        assert!(parse_opacity("1.5").is_err());
+        assert!(parse_opacity("-0.1").is_err());
+        assert!(parse_opacity("NaN").is_err());
+        assert!(parse_opacity("").is_err());
+    }
+
+    #[test]
+    fn parse_repeat_value() {
+        assert_eq!(parse_repeat("3x4").unwrap(), (3, 4));
+        assert_eq!(parse_repeat("1X1").unwrap(), (1, 1));
+        assert_eq!(parse_repeat("100x100").unwrap(), (100, 100));
+        assert!(parse_repeat("0x4").is_err());
+        assert!(parse_repeat("101x1").is_err());
+        assert!(parse_repeat("100000x100000").is_err());
+        assert!(parse_repeat("3x").is_err());
+        assert!(parse_repeat("3").is_err());
+        assert!(parse_repeat("-3x4").is_err());
+        assert!(parse_repeat("").is_err());
+    }
+
+    #[allow(clippy::float_cmp)]
+    #[test]
+    fn parse_length_value() {
+        assert_eq!(parse_length("25mm").unwrap(), 25.0);
+        assert_eq!(parse_length("2.5cm").unwrap(), 25.0);
+        assert_eq!(parse_length("1 in").unwrap(), 25.4);
+        assert!(parse_length("0mm").is_err());
+        assert!(parse_length("-1cm").is_err());
+        assert!(parse_length("25").is_err());
+        assert!(parse_length("mm").is_err());
+        assert!(parse_length("").is_err());
     }
 
     #[cfg(feature = "optimize-output-png")]
@@ -673,11 +2587,6 @@ mod tests {
         assert_eq!(u8::from(PngOptimizationLevel::Level6), 6);
     }
 
-    #[test]
-    fn default_variant() {
-        assert_eq!(Variant::default(), Variant::Normal);
-    }
-
     #[test]
     fn try_from_input_format_to_image_format() {
         #[cfg(feature = "decode-from-bmp")]
@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use anyhow::Context;
+use arboard::{Clipboard, ImageData};
+use image::{DynamicImage, RgbaImage};
+
+/// Copies the image to the system clipboard.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the clipboard is not available.
+pub fn set_image(image: &RgbaImage) -> anyhow::Result<()> {
+    let image = ImageData {
+        width: usize::try_from(image.width()).context("the image is too large")?,
+        height: usize::try_from(image.height()).context("the image is too large")?,
+        bytes: image.as_raw().into(),
+    };
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_image(image))
+        .context("could not copy the image to the clipboard")
+}
+
+/// Copies the text to the system clipboard.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the clipboard is not available.
+pub fn set_text(text: &str) -> anyhow::Result<()> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .context("could not copy the text to the clipboard")
+}
+
+/// Reads the image from the system clipboard.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the clipboard is not available or does not contain an
+/// image.
+pub fn get_image() -> anyhow::Result<DynamicImage> {
+    let image = Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_image())
+        .map_err(|err| {
+            let context = if matches!(err, arboard::Error::ContentNotAvailable) {
+                "the clipboard does not contain an image"
+            } else {
+                "could not read an image from the clipboard"
+            };
+            anyhow::Error::from(err).context(context)
+        })?;
+    let (width, height) = (
+        u32::try_from(image.width).context("the image is too large")?,
+        u32::try_from(image.height).context("the image is too large")?,
+    );
+    RgbaImage::from_raw(width, height, image.bytes.into_owned())
+        .map(DynamicImage::ImageRgba8)
+        .context("the image in the clipboard is invalid")
+}
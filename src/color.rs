@@ -0,0 +1,227 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Functions for parsing colors.
+
+use std::{error::Error, fmt};
+
+use csscolorparser::Color;
+
+/// An error which can be returned when parsing a color.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseColorError {
+    /// The CMYK color is invalid.
+    InvalidCmyk,
+
+    /// The CSS color is invalid.
+    Css(csscolorparser::ParseColorError),
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCmyk => f.write_str("invalid cmyk format"),
+            Self::Css(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ParseColorError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidCmyk => None,
+            Self::Css(err) => Some(err),
+        }
+    }
+}
+
+impl From<csscolorparser::ParseColorError> for ParseColorError {
+    fn from(err: csscolorparser::ParseColorError) -> Self {
+        Self::Css(err)
+    }
+}
+
+/// Parses a color.
+///
+/// In addition to CSS colors, this accepts `cmyk()` (e.g. `cmyk(0% 50% 50%
+/// 0%)`), which takes cyan, magenta, yellow and black components, and
+/// optionally alpha after a slash. The CMYK color is converted to RGB. The
+/// `transparent` keyword is parsed as fully transparent black.
+///
+/// # Errors
+///
+/// Returns [`Err`] if `s` is not a valid color.
+pub fn parse(s: &str) -> Result<Color, ParseColorError> {
+    let trimmed = s.trim();
+    match trimmed
+        .get(..5)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("cmyk("))
+        .and_then(|_| trimmed[5..].strip_suffix(')'))
+    {
+        Some(args) => parse_cmyk(args).ok_or(ParseColorError::InvalidCmyk),
+        None => Ok(csscolorparser::parse(s)?),
+    }
+}
+
+/// Returns the named colors sorted by name.
+#[must_use]
+pub fn named_colors() -> Vec<(&'static str, Color)> {
+    let mut colors = csscolorparser::NAMED_COLORS
+        .entries()
+        .map(|(name, &[r, g, b])| (*name, Color::from_rgba8(r, g, b, u8::MAX)))
+        .collect::<Vec<_>>();
+    colors.sort_unstable_by_key(|(name, _)| *name);
+    colors
+}
+
+/// The minimum contrast ratio between two colors of a QR code which is
+/// considered to be scannable.
+///
+/// This is the contrast ratio required for graphical objects by WCAG 2.
+pub const MIN_CONTRAST_RATIO: f32 = 3.0;
+
+/// Returns the contrast ratio between two colors.
+///
+/// The contrast ratio is defined by WCAG 2, and ranges from 1 to 21. The alpha
+/// components are ignored.
+#[must_use]
+pub fn contrast_ratio(a: &Color, b: &Color) -> f32 {
+    let luminance = |color: &Color| {
+        let [r, g, b, _] = color.to_linear_rgba();
+        0.2126_f32.mul_add(r, 0.7152_f32.mul_add(g, 0.0722 * b))
+    };
+    let (a, b) = (luminance(a), luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+/// Returns `true` if the color is fully transparent.
+#[must_use]
+pub fn is_transparent(color: &Color) -> bool {
+    color.to_rgba8()[3] == u8::MIN
+}
+
+/// Returns the color with its alpha component multiplied by `opacity`.
+#[must_use]
+pub fn with_opacity(color: &Color, opacity: f32) -> Color {
+    Color::new(color.r, color.g, color.b, color.a * opacity)
+}
+
+fn parse_cmyk(args: &str) -> Option<Color> {
+    let (components, alpha) = match args.split_once('/') {
+        Some((components, alpha)) => (components, Some(alpha)),
+        None => (args, None),
+    };
+    let components = components
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|c| !c.is_empty())
+        .map(parse_component)
+        .collect::<Option<Vec<_>>>()?;
+    let [cyan, magenta, yellow, black] = components[..] else {
+        return None;
+    };
+    let alpha = alpha.map_or(Some(1.0), |alpha| parse_component(alpha.trim()))?;
+    Some(Color::new(
+        (1.0 - cyan) * (1.0 - black),
+        (1.0 - magenta) * (1.0 - black),
+        (1.0 - yellow) * (1.0 - black),
+        alpha,
+    ))
+}
+
+fn parse_component(s: &str) -> Option<f32> {
+    let value = match s.strip_suffix('%') {
+        Some(percentage) => percentage.parse::<f32>().ok()? / 100.0,
+        None => s.parse().ok()?,
+    };
+    (0.0..=1.0).contains(&value).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cmyk_color() {
+        assert_eq!(
+            parse("cmyk(0% 50% 50% 0%)").unwrap().to_rgba8(),
+            [255, 128, 128, 255]
+        );
+        assert_eq!(
+            parse("cmyk(0, 0, 0, 1)").unwrap().to_rgba8(),
+            [0, 0, 0, 255]
+        );
+        assert_eq!(
+            parse("CMYK(100% 0% 100% 0% / 50%)").unwrap().to_rgba8(),
+            [0, 255, 0, 128]
+        );
+    }
+
+    #[test]
+    fn apply_opacity() {
+        let color = parse("#ff0000").unwrap();
+        assert_eq!(with_opacity(&color, 0.5).to_rgba8(), [255, 0, 0, 128]);
+        let color = parse("#ff000080").unwrap();
+        assert_eq!(with_opacity(&color, 0.5).to_rgba8(), [255, 0, 0, 64]);
+        assert_eq!(with_opacity(&color, 1.0), color);
+    }
+
+    #[test]
+    fn parse_css_color() {
+        assert_eq!(parse("brown").unwrap().to_rgba8(), [165, 42, 42, 255]);
+        assert_eq!(parse("#ff0000").unwrap().to_rgba8(), [255, 0, 0, 255]);
+        assert_eq!(
+            parse("oklab(62.8% 0.2249 0.1258)").unwrap().to_rgba8(),
+            [255, 0, 0, 255]
+        );
+        assert_eq!(
+            parse("oklch(62.8% 0.2577 29.23 / 50%)").unwrap().to_rgba8(),
+            [255, 0, 0, 128]
+        );
+    }
+
+    #[test]
+    fn parse_transparent_color() {
+        assert_eq!(parse("transparent").unwrap().to_rgba8(), [0, 0, 0, 0]);
+        assert_eq!(parse("Transparent").unwrap().to_rgba8(), [0, 0, 0, 0]);
+        assert!(is_transparent(&parse("transparent").unwrap()));
+        assert!(is_transparent(&parse("#ffffff00").unwrap()));
+        assert!(!is_transparent(&parse("white").unwrap()));
+    }
+
+    #[test]
+    fn list_named_colors() {
+        let colors = named_colors();
+        assert!(colors.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(colors[0].0, "aliceblue");
+        assert!(colors.contains(&("brown", Color::from_rgba8(165, 42, 42, u8::MAX))));
+        assert!(colors
+            .iter()
+            .all(|(name, color)| &parse(name).unwrap() == color));
+    }
+
+    #[test]
+    fn compute_contrast_ratio() {
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        assert!((contrast_ratio(&black, &white) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(&white, &black) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(&white, &white) - 1.0).abs() < 0.01);
+        let yellow = Color::new(1.0, 1.0, 0.0, 1.0);
+        assert!(contrast_ratio(&yellow, &white) < 1.1);
+    }
+
+    #[test]
+    fn parse_invalid_cmyk_color() {
+        assert_eq!(
+            parse("cmyk(0% 50% 50%)").unwrap_err(),
+            ParseColorError::InvalidCmyk
+        );
+        assert_eq!(
+            parse("cmyk(0% 50% 50% 0% 0%)").unwrap_err().to_string(),
+            "invalid cmyk format"
+        );
+        assert!(parse("cmyk(0% 50% 50% 200%)").is_err());
+        assert!(parse("cmyk(a b c d)").is_err());
+    }
+}
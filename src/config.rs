@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::{
+    fs,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use csscolorparser::Color;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::{cli::Ecc, color};
+
+/// Configuration loaded from a file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Default values for the `encode` command.
+    #[serde(default)]
+    pub encode: EncodeConfig,
+}
+
+/// Default values for the `encode` command.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncodeConfig {
+    /// The module size in pixels.
+    pub size: Option<NonZeroU32>,
+
+    /// Error correction level.
+    #[serde(default, deserialize_with = "deserialize_ecc")]
+    pub error_correction_level: Option<Ecc>,
+
+    /// The width of margin.
+    pub margin: Option<u32>,
+
+    /// Foreground color.
+    #[serde(default, deserialize_with = "deserialize_color")]
+    pub foreground: Option<Color>,
+
+    /// Background color.
+    #[serde(default, deserialize_with = "deserialize_color")]
+    pub background: Option<Color>,
+}
+
+impl Config {
+    /// Returns the path to the default configuration file.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("qrtool").join("config.toml"))
+    }
+
+    /// Reads the configuration from a file.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("could not read the configuration from {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("could not parse the configuration in {}", path.display()))
+    }
+}
+
+fn deserialize_ecc<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Ecc>, D::Error> {
+    let level = String::deserialize(deserializer)?;
+    Ecc::from_str(&level, true)
+        .map(Some)
+        .map_err(de::Error::custom)
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Color>, D::Error> {
+    let color = String::deserialize(deserializer)?;
+    color::parse(&color).map(Some).map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config: Config = toml::from_str(
+            r##"
+            [encode]
+            size = 3
+            error_correction_level = "H"
+            margin = 8
+            foreground = "brown"
+            background = "#eee"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.encode.size, NonZeroU32::new(3));
+        assert_eq!(config.encode.error_correction_level, Some(Ecc::H));
+        assert_eq!(config.encode.margin, Some(8));
+        assert_eq!(
+            config.encode.foreground,
+            Some(Color::from_rgba8(165, 42, 42, u8::MAX))
+        );
+        assert_eq!(
+            config.encode.background,
+            Some(Color::from_rgba8(238, 238, 238, u8::MAX))
+        );
+    }
+
+    #[test]
+    fn parse_config_with_cmyk_color() {
+        let config: Config =
+            toml::from_str("[encode]\nforeground = \"cmyk(0% 50% 50% 0%)\"").unwrap();
+        assert_eq!(
+            config.encode.foreground,
+            Some(Color::new(1.0, 0.5, 0.5, 1.0))
+        );
+    }
+
+    #[test]
+    fn parse_empty_config() {
+        let config: Config = toml::from_str("").unwrap();
+        assert!(config.encode.size.is_none());
+        assert!(config.encode.error_correction_level.is_none());
+        assert!(config.encode.margin.is_none());
+        assert!(config.encode.foreground.is_none());
+        assert!(config.encode.background.is_none());
+    }
+
+    #[test]
+    fn parse_config_with_unknown_key() {
+        assert!(toml::from_str::<Config>("[encode]\nsymbol_version = 1").is_err());
+    }
+
+    #[test]
+    fn parse_config_with_invalid_error_correction_level() {
+        assert!(toml::from_str::<Config>("[encode]\nerror_correction_level = \"a\"").is_err());
+    }
+}
@@ -2,17 +2,61 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use rqrr::{BitGrid, DeQRError, Grid, MetaData};
+//! Functions for decoding a QR code.
+
+use std::ops::Range;
+
+use csscolorparser::Color;
+use image::{imageops, GrayImage, Luma, RgbImage};
+use qrcode::{
+    bits::Bits,
+    canvas::{Canvas, MaskPattern},
+    ec, EcLevel, Version,
+};
+use rqrr::{BitGrid, DeQRError, Grid, MetaData, PreparedImage, SimpleGrid};
 
 use crate::{
-    cli::Ecc,
+    encode::Ecc,
+    matrix::{Matrix, Modules},
     metadata::{Extractor, Metadata},
 };
 
-type DecodedBytes = (MetaData, Vec<u8>);
+/// The metadata and the contents of a decoded QR code.
+pub type DecodedBytes = (MetaData, Vec<u8>);
+
+/// A decoded QR code with the metadata which has the number of erroneous
+/// codewords in each block.
+pub type DecodedWithErrors = (DecodedBytes, Metadata);
+
+/// A decoded QR code which can be obtained from a grid.
+trait FromGrid: Sized {
+    fn from_grid<G: BitGrid>(grid: &Grid<G>) -> Result<Self, DeQRError>;
+
+    fn contents(&self) -> &[u8];
+}
+
+impl FromGrid for DecodedBytes {
+    fn from_grid<G: BitGrid>(grid: &Grid<G>) -> Result<Self, DeQRError> {
+        grid_as_bytes(grid)
+    }
+
+    fn contents(&self) -> &[u8] {
+        &self.1
+    }
+}
+
+impl FromGrid for DecodedWithErrors {
+    fn from_grid<G: BitGrid>(grid: &Grid<G>) -> Result<Self, DeQRError> {
+        Ok((grid_as_bytes(grid)?, grid_metadata_with_errors(grid)?))
+    }
+
+    fn contents(&self) -> &[u8] {
+        &self.0 .1
+    }
+}
 
 #[cfg(feature = "decode-from-svg")]
-fn svg_to_png(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+fn svg_to_png(data: &[u8], scale: u32) -> anyhow::Result<Vec<u8>> {
     use anyhow::Context;
     use resvg::{
         tiny_skia::{Pixmap, Transform},
@@ -24,26 +68,388 @@ fn svg_to_png(data: &[u8]) -> anyhow::Result<Vec<u8>> {
     let tree = usvg::Tree::from_data(data, &opt)?;
 
     let pixmap_size = tree.size().to_int_size();
-    let mut pixmap = Pixmap::new(pixmap_size.width(), pixmap_size.height())
-        .context("could not allocate a new pixmap")?;
-    resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+    let (width, height) = pixmap_size
+        .width()
+        .checked_mul(scale)
+        .zip(pixmap_size.height().checked_mul(scale))
+        .context("the rasterized image is too large")?;
+    let mut pixmap = Pixmap::new(width, height).context("could not allocate a new pixmap")?;
+    #[allow(clippy::cast_precision_loss)]
+    let transform = Transform::from_scale(scale as f32, scale as f32);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
     pixmap.encode_png().map_err(anyhow::Error::from)
 }
 
 /// Reads the image from SVG.
+///
+/// The SVG image is rasterized at `scale` times its intrinsic size.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the SVG image cannot be parsed or rasterized.
 #[cfg(feature = "decode-from-svg")]
-pub fn from_svg(data: impl AsRef<[u8]>) -> anyhow::Result<image::DynamicImage> {
-    let image = svg_to_png(data.as_ref())?;
+pub fn from_svg(data: impl AsRef<[u8]>, scale: u32) -> anyhow::Result<image::DynamicImage> {
+    let image = svg_to_png(data.as_ref(), scale)?;
     image::load_from_memory_with_format(&image, image::ImageFormat::Png)
         .map_err(anyhow::Error::from)
 }
 
+/// Reads the data from the base64 data URI.
+///
+/// The data URI is in the form of `data:[<media type>][;base64],<data>`, and
+/// the media type is ignored.
+///
+/// # Errors
+///
+/// Returns [`Err`] if `uri` is not a data URI, if the data is not encoded in
+/// base64, or if the data is not valid base64.
+pub fn from_data_uri(uri: &str) -> anyhow::Result<Vec<u8>> {
+    use anyhow::{anyhow, Context};
+
+    let (header, data) = uri
+        .trim()
+        .strip_prefix("data:")
+        .and_then(|uri| uri.split_once(','))
+        .ok_or_else(|| anyhow!("invalid data URI"))?;
+    if !header
+        .rsplit(';')
+        .next()
+        .is_some_and(|param| param.eq_ignore_ascii_case("base64"))
+    {
+        return Err(anyhow!("data URI is not encoded in base64"));
+    }
+    crate::encode::from_base64(data).context("invalid base64 data")
+}
+
+/// Converts the image to grayscale using the relative luminance.
+///
+/// Unlike [`image::DynamicImage::into_luma8`], which weights the gamma-encoded
+/// components, this weights the linear components, so colors which have
+/// similar luma but different luminance are kept apart. The luminance is
+/// gamma-encoded again for an 8-bit image.
+#[must_use]
+pub fn to_luminance(image: &RgbImage) -> GrayImage {
+    let linear = (0..=u8::MAX)
+        .map(|c| Color::from_rgba8(c, c, c, u8::MAX).to_linear_rgba()[0])
+        .collect::<Vec<_>>();
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b] = image.get_pixel(x, y).0.map(|c| linear[usize::from(c)]);
+        let luminance = 0.2126_f32.mul_add(r, 0.7152_f32.mul_add(g, 0.0722 * b));
+        let [luma, ..] = Color::from_linear_rgba(luminance, luminance, luminance, 1.0).to_rgba8();
+        Luma([luma])
+    })
+}
+
+/// Converts the image to grayscale using the inverted saturation.
+///
+/// The saturation is the one of the HSV color model, and highly saturated
+/// pixels become dark, so a colored QR code on a white or gray background
+/// becomes dark on a light background regardless of its luma.
+#[must_use]
+pub fn to_saturation(image: &RgbImage) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let [r, g, b] = image.get_pixel(x, y).0;
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let saturation = if max == 0 {
+            0
+        } else {
+            u16::from(max - min) * u16::from(u8::MAX) / u16::from(max)
+        };
+        Luma([u8::MAX - u8::try_from(saturation).unwrap_or(u8::MAX)])
+    })
+}
+
+/// Binarizes the image with a global threshold.
+///
+/// Pixels whose luma is less than `threshold` become black, and the others
+/// become white.
+pub fn binarize(image: &mut GrayImage, threshold: u8) {
+    for pixel in image.pixels_mut() {
+        pixel.0[0] = if pixel.0[0] < threshold {
+            u8::MIN
+        } else {
+            u8::MAX
+        };
+    }
+}
+
+/// Computes the global threshold of the image using Otsu's method.
+#[must_use]
+pub fn otsu_threshold(image: &GrayImage) -> u8 {
+    let mut histogram = [0_u64; 256];
+    for pixel in image.pixels() {
+        histogram[usize::from(pixel.0[0])] += 1;
+    }
+    let total = histogram.iter().sum::<u64>();
+    let sum = histogram
+        .iter()
+        .zip(0_u64..)
+        .map(|(count, luma)| count * luma)
+        .sum::<u64>();
+
+    let (mut best_threshold, mut best_variance) = (0, 0.0);
+    let (mut background_count, mut background_sum) = (0_u64, 0_u64);
+    for (threshold, (count, luma)) in (0_u8..=u8::MAX).zip(histogram.iter().zip(0_u64..)) {
+        background_count += count;
+        background_sum += count * luma;
+        let foreground_count = total - background_count;
+        if background_count == 0 || foreground_count == 0 {
+            continue;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let (background_mean, foreground_mean) = (
+            background_sum as f64 / background_count as f64,
+            (sum - background_sum) as f64 / foreground_count as f64,
+        );
+        #[allow(clippy::cast_precision_loss)]
+        let variance = background_count as f64
+            * foreground_count as f64
+            * (background_mean - foreground_mean).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_threshold = threshold;
+        }
+    }
+    // Pixels whose luma is equal to the computed threshold belong to the
+    // background (dark) class.
+    best_threshold.saturating_add(1)
+}
+
+/// Escapes the non-printable bytes in the decoded content as `\xNN`.
+///
+/// The content is read as UTF-8, and the control characters other than tab and
+/// line feed, and the bytes which are not valid UTF-8 are escaped. The other
+/// characters are kept as is, so the printable text is not changed.
+#[must_use]
+pub fn escape(mut data: &[u8]) -> Vec<u8> {
+    fn escape_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+        for byte in bytes {
+            buf.extend_from_slice(format!("\\x{byte:02x}").as_bytes());
+        }
+    }
+
+    let mut buf = Vec::with_capacity(data.len());
+    loop {
+        let (valid, invalid) = match std::str::from_utf8(data) {
+            Ok(text) => (text, None),
+            Err(err) => {
+                let (valid, rest) = data.split_at(err.valid_up_to());
+                let invalid = err.error_len().unwrap_or(rest.len());
+                let valid = std::str::from_utf8(valid).unwrap_or_default();
+                (valid, Some(rest.split_at(invalid)))
+            }
+        };
+        for c in valid.chars() {
+            if c.is_control() && !matches!(c, '\t' | '\n') {
+                escape_bytes(&mut buf, c.encode_utf8(&mut [0; 4]).as_bytes());
+            } else {
+                buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+        match invalid {
+            Some((invalid, rest)) => {
+                escape_bytes(&mut buf, invalid);
+                data = rest;
+            }
+            None => return buf,
+        }
+    }
+}
+
+/// Upscales the image so that its smaller dimension is at least `min_size`
+/// pixels.
+///
+/// The aspect ratio is preserved. If the image is already large enough, it is
+/// returned unchanged.
+#[must_use]
+pub fn upscale(
+    image: image::DynamicImage,
+    min_size: u32,
+    filter: imageops::FilterType,
+) -> image::DynamicImage {
+    let (width, height) = (image.width(), image.height());
+    let shorter = width.min(height);
+    if shorter == 0 || shorter >= min_size {
+        return image;
+    }
+    let scale = |length: u32| {
+        let length = (u64::from(length) * u64::from(min_size)).div_ceil(u64::from(shorter));
+        u32::try_from(length).unwrap_or(u32::MAX)
+    };
+    image.resize_exact(scale(width), scale(height), filter)
+}
+
+/// Downscales the image so that its larger dimension is at most `max_size`
+/// pixels.
+///
+/// The aspect ratio is preserved. If the image is already small enough, it is
+/// returned unchanged.
+#[must_use]
+pub fn downscale(
+    image: image::DynamicImage,
+    max_size: u32,
+    filter: imageops::FilterType,
+) -> image::DynamicImage {
+    let (width, height) = (image.width(), image.height());
+    let longer = width.max(height);
+    if longer <= max_size {
+        return image;
+    }
+    let scale = |length: u32| {
+        let length = u64::from(length) * u64::from(max_size) / u64::from(longer);
+        u32::try_from(length).unwrap_or(u32::MAX).max(1)
+    };
+    image.resize_exact(scale(width), scale(height), filter)
+}
+
+/// Stitches the tiles into one image in a grid with `columns` columns.
+///
+/// The tiles are placed in row-major order, that is, from left to right and
+/// then from top to bottom. All tiles must have the same dimensions.
+///
+/// # Errors
+///
+/// Returns [`Err`] if there are no tiles, if the number of the tiles is not a
+/// multiple of `columns`, or if the dimensions of any tile differ from those of
+/// the first tile.
+pub fn stitch(tiles: &[image::DynamicImage], columns: u32) -> anyhow::Result<image::DynamicImage> {
+    use anyhow::{anyhow, bail};
+    use image::RgbaImage;
+
+    let first = tiles.first().ok_or_else(|| anyhow!("no tiles to stitch"))?;
+    let count = u32::try_from(tiles.len())?;
+    if columns == 0 || count % columns != 0 {
+        bail!("{count} tiles cannot be arranged in {columns} columns");
+    }
+    let (width, height) = (first.width(), first.height());
+    if let Some((i, tile)) = tiles
+        .iter()
+        .enumerate()
+        .find(|(_, tile)| (tile.width(), tile.height()) != (width, height))
+    {
+        bail!(
+            "tile {} is {}x{} pixels, but tile 1 is {width}x{height} pixels",
+            i + 1,
+            tile.width(),
+            tile.height()
+        );
+    }
+    let mut image = RgbaImage::new(width * columns, height * (count / columns));
+    for (i, tile) in (0..).zip(tiles) {
+        let (x, y) = (i % columns * width, i / columns * height);
+        imageops::replace(&mut image, &tile.to_rgba8(), x.into(), y.into());
+    }
+    Ok(image.into())
+}
+
+/// Reads the image, and rotates and flips it according to its orientation.
+///
+/// The orientation is read from the EXIF metadata of the image if the format
+/// supports it, such as JPEG, TIFF and WebP. Otherwise, this is the same as
+/// [`image::load_from_memory_with_format`].
+///
+/// # Errors
+///
+/// Returns [`Err`] if the image cannot be decoded.
+pub fn load_with_orientation(
+    data: impl AsRef<[u8]>,
+    format: image::ImageFormat,
+) -> image::ImageResult<image::DynamicImage> {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageDecoder, ImageReader};
+
+    let mut decoder =
+        ImageReader::with_format(Cursor::new(data.as_ref()), format).into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// Reads the dimensions of the image without decoding it.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the header of the image cannot be read.
+pub fn dimensions(
+    data: impl AsRef<[u8]>,
+    format: image::ImageFormat,
+) -> image::ImageResult<(u32, u32)> {
+    use std::io::Cursor;
+
+    use image::ImageReader;
+
+    ImageReader::with_format(Cursor::new(data.as_ref()), format).into_dimensions()
+}
+
+/// Checks that the dimensions do not have more than `max` pixels.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the dimensions have more than `max` pixels.
+pub fn check_dimensions((width, height): (u32, u32), max: u64) -> image::ImageResult<()> {
+    use image::error::{ImageError, LimitError, LimitErrorKind};
+
+    if u64::from(width) * u64::from(height) > max {
+        return Err(ImageError::Limits(LimitError::from_kind(
+            LimitErrorKind::DimensionError,
+        )));
+    }
+    Ok(())
+}
+
+/// Reads all frames of the animated image.
+///
+/// Returns [`None`] if the image format does not support animation, or if the
+/// image is not animated.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the image cannot be decoded.
+#[cfg(any(feature = "decode-from-gif", feature = "decode-from-webp"))]
+pub fn frames(
+    data: impl AsRef<[u8]>,
+    format: image::ImageFormat,
+) -> image::ImageResult<Option<Vec<image::DynamicImage>>> {
+    use std::io::Cursor;
+
+    use image::AnimationDecoder;
+
+    let data = Cursor::new(data.as_ref());
+    let frames = match format {
+        #[cfg(feature = "decode-from-gif")]
+        image::ImageFormat::Gif => image::codecs::gif::GifDecoder::new(data)?
+            .into_frames()
+            .collect_frames()?,
+        #[cfg(feature = "decode-from-webp")]
+        image::ImageFormat::WebP => {
+            let decoder = image::codecs::webp::WebPDecoder::new(data)?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            decoder.into_frames().collect_frames()?
+        }
+        _ => return Ok(None),
+    };
+    Ok((frames.len() > 1).then(|| {
+        frames
+            .into_iter()
+            .map(|frame| frame.into_buffer().into())
+            .collect()
+    }))
+}
+
 fn grid_as_bytes<G: BitGrid>(grid: &Grid<G>) -> Result<DecodedBytes, DeQRError> {
     let mut writer = Vec::new();
     grid.decode_to(&mut writer).map(|meta| (meta, writer))
 }
 
 /// Decodes the grids as bytes.
+///
+/// # Errors
+///
+/// Returns [`Err`] if decoding any of the grids fails.
 pub fn grids_as_bytes<G: BitGrid>(
     grids: impl AsRef<[Grid<G>]>,
 ) -> Result<Vec<DecodedBytes>, DeQRError> {
@@ -54,6 +460,509 @@ pub fn grids_as_bytes<G: BitGrid>(
         .collect()
 }
 
+/// Decodes the grids in order until one of them is decoded successfully.
+///
+/// The remaining grids are not decoded. Returns [`None`] if there are no
+/// grids.
+///
+/// # Errors
+///
+/// Returns [`Err`] if decoding all the grids fails.
+pub fn first_grid_as_bytes<G: BitGrid>(
+    grids: impl AsRef<[Grid<G>]>,
+) -> Result<Option<DecodedBytes>, DeQRError> {
+    first_grid(grids.as_ref())
+}
+
+fn first_grid<T: FromGrid, G: BitGrid>(grids: &[Grid<G>]) -> Result<Option<T>, DeQRError> {
+    let mut error = None;
+    for grid in grids {
+        match T::from_grid(grid) {
+            Ok(content) => return Ok(Some(content)),
+            Err(err) => {
+                error.get_or_insert(err);
+            }
+        }
+    }
+    error.map_or(Ok(None), Err)
+}
+
+fn detect_and_decode<T: FromGrid>(image: GrayImage, first: bool) -> Result<Vec<T>, DeQRError> {
+    let mut image = PreparedImage::prepare(image);
+    let grids = image.detect_grids();
+    if first {
+        first_grid(&grids).map(|content| content.into_iter().collect())
+    } else {
+        grids.iter().map(T::from_grid).collect()
+    }
+}
+
+fn decode_with<T: FromGrid>(mut image: GrayImage, first: bool) -> Result<Vec<T>, DeQRError> {
+    // NOTE: rqrr doesn't appear to work if the background is darker than
+    // the foreground. So we try with an inverted image if decoding fails
+    // or no content is returned.
+    match detect_and_decode(image.clone(), first) {
+        Err(e) => {
+            imageops::invert(&mut image);
+            detect_and_decode(image, first).map_err(|_| e)
+        }
+        Ok(contents) if contents.is_empty() => {
+            imageops::invert(&mut image);
+            Ok(detect_and_decode(image, first).unwrap_or(contents))
+        }
+        Ok(contents) => Ok(contents),
+    }
+}
+
+/// Detects and decodes QR codes in the grayscale image.
+///
+/// The contents are in the order in which the grids are detected, which is
+/// roughly from the top to the bottom of the image.
+///
+/// # Errors
+///
+/// Returns [`Err`] if decoding a detected QR code fails.
+pub fn decode(image: GrayImage) -> Result<Vec<DecodedBytes>, DeQRError> {
+    decode_with(image, false)
+}
+
+fn decode_or_mirrored_with<T: FromGrid>(
+    image: GrayImage,
+    first: bool,
+) -> Result<Vec<T>, DeQRError> {
+    let mirrored = imageops::flip_horizontal(&image);
+    match decode_with(image, first) {
+        Ok(contents) if !contents.is_empty() => Ok(contents),
+        result => match decode_with(mirrored, first) {
+            Ok(contents) if !contents.is_empty() => Ok(contents),
+            _ => result,
+        },
+    }
+}
+
+/// Detects and decodes QR codes in the grayscale image, and also tries with the
+/// horizontally flipped image if no QR code can be decoded.
+///
+/// This recovers a mirrored QR code, such as one printed on a transparent film
+/// and viewed from behind.
+///
+/// # Errors
+///
+/// Returns [`Err`] if decoding a detected QR code fails in both images.
+pub fn decode_or_mirrored(image: GrayImage) -> Result<Vec<DecodedBytes>, DeQRError> {
+    decode_or_mirrored_with(image, false)
+}
+
+fn detect_matrices(image: GrayImage) -> Vec<Matrix> {
+    let mut image = PreparedImage::prepare(image);
+    image.detect_grids().iter().map(Matrix::from).collect()
+}
+
+/// Detects QR codes in the grayscale images and returns their matrices of
+/// modules.
+///
+/// The QR codes are not decoded, so the matrices of damaged QR codes are also
+/// returned. Like [`decode`], this also tries with the inverted image if no QR
+/// code is detected. Matrices which have already been detected in the previous
+/// images are skipped.
+pub fn matrices(images: impl IntoIterator<Item = GrayImage>) -> Vec<Matrix> {
+    let mut matrices = Vec::<Matrix>::new();
+    for mut image in images {
+        let mut image_matrices = detect_matrices(image.clone());
+        if image_matrices.is_empty() {
+            imageops::invert(&mut image);
+            image_matrices = detect_matrices(image);
+        }
+        for matrix in image_matrices {
+            if !matrices.contains(&matrix) {
+                matrices.push(matrix);
+            }
+        }
+    }
+    matrices
+}
+
+/// Detects and decodes QR codes in each frame of the animated image.
+///
+/// Contents which have already been decoded in the previous frames are
+/// skipped. Frames in which decoding fails are ignored unless no QR code can be
+/// decoded in any frame. If `try_mirrored` is `true`, each frame is decoded
+/// with [`decode_or_mirrored`].
+///
+/// If `first` is `true`, this stops at the first QR code which is decoded
+/// successfully. The frames are tried in order, and in each frame, the original
+/// image is tried before the inverted image, and both are tried before the
+/// mirrored image. The grids in an image are tried in the order in which they
+/// are detected, which is roughly from the top to the bottom of the image.
+///
+/// # Errors
+///
+/// Returns [`Err`] if no QR code can be decoded and decoding a detected QR code
+/// fails in any frame.
+pub fn decode_frames(
+    frames: impl IntoIterator<Item = GrayImage>,
+    try_mirrored: bool,
+    first: bool,
+) -> Result<Vec<DecodedBytes>, DeQRError> {
+    decode_frames_as(frames, try_mirrored, first)
+}
+
+/// Detects and decodes QR codes in each frame of the animated image with the
+/// number of erroneous codewords in each block.
+///
+/// This is the same as [`decode_frames`], except that the number of erroneous
+/// codewords is read from the same grids as the contents.
+///
+/// # Errors
+///
+/// Returns [`Err`] if no QR code can be decoded and decoding a detected QR code
+/// fails in any frame, or if the errors cannot be corrected.
+pub fn decode_frames_with_errors(
+    frames: impl IntoIterator<Item = GrayImage>,
+    try_mirrored: bool,
+    first: bool,
+) -> Result<Vec<DecodedWithErrors>, DeQRError> {
+    decode_frames_as(frames, try_mirrored, first)
+}
+
+fn decode_frames_as<T: FromGrid>(
+    frames: impl IntoIterator<Item = GrayImage>,
+    try_mirrored: bool,
+    first: bool,
+) -> Result<Vec<T>, DeQRError> {
+    let mut contents = Vec::<T>::new();
+    let mut error = None;
+    for frame in frames {
+        let result = if try_mirrored {
+            decode_or_mirrored_with::<T>(frame, first)
+        } else {
+            decode_with(frame, first)
+        };
+        match result {
+            Ok(frame_contents) => {
+                let len = contents.len();
+                for content in frame_contents {
+                    if !contents[..len]
+                        .iter()
+                        .any(|c| c.contents() == content.contents())
+                    {
+                        contents.push(content);
+                    }
+                }
+            }
+            Err(err) => {
+                error.get_or_insert(err);
+            }
+        }
+        if first && !contents.is_empty() {
+            return Ok(contents);
+        }
+    }
+    match error {
+        Some(err) if contents.is_empty() => Err(err),
+        _ => Ok(contents),
+    }
+}
+
+const fn to_ec_level(ecc_level: u16) -> Result<EcLevel, DeQRError> {
+    match ecc_level {
+        0 => Ok(EcLevel::M),
+        1 => Ok(EcLevel::L),
+        2 => Ok(EcLevel::H),
+        3 => Ok(EcLevel::Q),
+        _ => Err(DeQRError::FormatEcc),
+    }
+}
+
+const fn to_mask_pattern(mask: u16) -> Result<MaskPattern, DeQRError> {
+    match mask {
+        0 => Ok(MaskPattern::Checkerboard),
+        1 => Ok(MaskPattern::HorizontalLines),
+        2 => Ok(MaskPattern::VerticalLines),
+        3 => Ok(MaskPattern::DiagonalLines),
+        4 => Ok(MaskPattern::LargeCheckerboard),
+        5 => Ok(MaskPattern::Fields),
+        6 => Ok(MaskPattern::Diamonds),
+        7 => Ok(MaskPattern::Meadow),
+        _ => Err(DeQRError::FormatEcc),
+    }
+}
+
+/// The codewords read from a grid, which are not corrected.
+pub struct Codewords {
+    pub meta: MetaData,
+
+    /// The data codewords in the order of the data.
+    pub data: Vec<u8>,
+
+    /// The error correction codewords in the order in which they are placed.
+    pub ec: Vec<u8>,
+
+    /// The range of the data codewords of each block.
+    pub blocks: Vec<Range<usize>>,
+}
+
+/// Reads the codewords from the grid without correcting errors.
+pub fn read_codewords<G: BitGrid>(grid: &Grid<G>) -> Result<Codewords, DeQRError> {
+    let (meta, raw) = grid.get_raw_data()?;
+    let version = i16::try_from(meta.version.0)
+        .map(Version::Normal)
+        .map_err(|_| DeQRError::InvalidVersion)?;
+    let level = to_ec_level(meta.ecc_level)?;
+    let data_len = Bits::new(version)
+        .max_len(level)
+        .map_err(|_| DeQRError::InvalidVersion)?
+        / 8;
+    let ec_len = ec::construct_codewords(&vec![0; data_len], version, level)
+        .map_err(|_| DeQRError::InvalidVersion)?
+        .1
+        .len();
+
+    // The raw data is still masked. Since rendering empty codewords with the
+    // same mask yields the mask itself, XORing them removes the mask.
+    let mut canvas = Canvas::new(version, level);
+    canvas.draw_all_functional_patterns();
+    canvas.draw_data(&vec![0; data_len], &vec![0; ec_len]);
+    canvas.apply_mask(to_mask_pattern(meta.mask)?);
+    let width = meta.version.to_size();
+    let colors = canvas.into_colors();
+    let mask = Grid::new(SimpleGrid::from_func(width, |x, y| {
+        colors[y * width + x] == qrcode::Color::Dark
+    }));
+    let (_, mask) = mask.get_raw_data()?;
+    let codewords = raw
+        .data
+        .iter()
+        .zip(mask.data)
+        .take(data_len + ec_len)
+        .map(|(raw, mask)| raw ^ mask)
+        .collect::<Vec<_>>();
+
+    // Find where each data codeword is placed by interleaving the indices.
+    let interleave = |byte: usize| {
+        let indices = (0..data_len)
+            .map(|i| u16::try_from(i).map_or(u8::MAX, |i| i.to_be_bytes()[byte]))
+            .collect::<Vec<_>>();
+        ec::construct_codewords(&indices, version, level)
+            .map(|(data, _)| data)
+            .map_err(|_| DeQRError::InvalidVersion)
+    };
+    let indices = interleave(0)?
+        .into_iter()
+        .zip(interleave(1)?)
+        .map(|index| usize::from(u16::from_be_bytes([index.0, index.1])))
+        .collect::<Vec<_>>();
+    let mut data = vec![0; data_len];
+    for (codeword, &index) in codewords.iter().zip(&indices) {
+        data[index] = *codeword;
+    }
+
+    // The first codeword of each block is placed first.
+    let starts = indices
+        .iter()
+        .position(|&index| index == 1)
+        .map_or(&indices[..1], |count| &indices[..count]);
+    let blocks = starts
+        .iter()
+        .zip(starts.iter().skip(1).chain([&data_len]))
+        .map(|(&start, &end)| start..end)
+        .collect();
+    Ok(Codewords {
+        meta,
+        data,
+        ec: codewords[data_len..].to_vec(),
+        blocks,
+    })
+}
+
+/// Arithmetic in GF(2^8) with the primitive polynomial x^8 + x^4 + x^3 + x^2 +
+/// 1, which is used by the Reed-Solomon codes of QR code.
+struct Gf256 {
+    exp: [u8; 255],
+    log: [u8; 256],
+}
+
+impl Gf256 {
+    fn new() -> Self {
+        let (mut exp, mut log) = ([0; 255], [0; 256]);
+        let mut x = 1_u8;
+        for (e, i) in exp.iter_mut().zip(0..) {
+            *e = x;
+            log[usize::from(x)] = i;
+            x = if x & 0x80 == 0 {
+                x << 1
+            } else {
+                (x << 1) ^ 0x1d
+            };
+        }
+        Self { exp, log }
+    }
+
+    /// Returns the `n`-th power of the generator.
+    const fn pow(&self, n: usize) -> u8 {
+        self.exp[n % 255]
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.pow(usize::from(self.log[usize::from(a)]) + usize::from(self.log[usize::from(b)]))
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            return 0;
+        }
+        self.pow(
+            usize::from(self.log[usize::from(a)]) + 255 - usize::from(self.log[usize::from(b)]),
+        )
+    }
+
+    /// Evaluates the polynomial, whose coefficients are in descending order.
+    fn eval(&self, poly: impl DoubleEndedIterator<Item = u8>, x: u8) -> u8 {
+        poly.fold(0, |acc, c| self.mul(acc, x) ^ c)
+    }
+}
+
+/// Computes the syndromes of the block.
+fn compute_syndromes(gf: &Gf256, block: &[u8], ec_len: usize) -> Vec<u8> {
+    (0..ec_len)
+        .map(|i| gf.eval(block.iter().copied(), gf.pow(i)))
+        .collect()
+}
+
+/// Corrects the erroneous codewords in the block.
+///
+/// The errors are located by the Berlekamp-Massey algorithm, and their values
+/// are computed by the Forney algorithm. Returns the number of corrected
+/// codewords, or [`None`] if the errors cannot be corrected.
+fn correct_errors(gf: &Gf256, block: &mut [u8], ec_len: usize) -> Option<usize> {
+    let syndromes = compute_syndromes(gf, block, ec_len);
+    if syndromes.iter().all(|&s| s == 0) {
+        return Some(0);
+    }
+    let (mut locator, mut prev) = (vec![1], vec![1]);
+    let (mut errors, mut shift, mut prev_discrepancy) = (0, 1, 1);
+    for n in 0..ec_len {
+        let discrepancy = (1..=errors).fold(syndromes[n], |d, i| {
+            d ^ gf.mul(
+                locator.get(i).copied().unwrap_or_default(),
+                syndromes[n - i],
+            )
+        });
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let coef = gf.div(discrepancy, prev_discrepancy);
+        let mut next = locator.clone();
+        next.resize(next.len().max(prev.len() + shift), 0);
+        for (i, &c) in prev.iter().enumerate() {
+            next[i + shift] ^= gf.mul(coef, c);
+        }
+        if 2 * errors <= n {
+            prev = locator;
+            errors = n + 1 - errors;
+            prev_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+        locator = next;
+    }
+    if 2 * errors > ec_len {
+        return None;
+    }
+
+    // Each error location is a root of the error locator polynomial. The
+    // location is the degree of the term, which is counted from the last
+    // codeword.
+    let locations = (0..block.len())
+        .filter(|&degree| gf.eval(locator.iter().rev().copied(), gf.pow(255 - degree % 255)) == 0)
+        .collect::<Vec<_>>();
+    if locations.len() != errors {
+        return None;
+    }
+
+    let evaluator = (0..ec_len)
+        .map(|i| {
+            (0..=i.min(locator.len() - 1))
+                .fold(0, |acc, j| acc ^ gf.mul(syndromes[i - j], locator[j]))
+        })
+        .collect::<Vec<_>>();
+    let derivative = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+        .collect::<Vec<_>>();
+    for degree in locations {
+        let inverse = gf.pow(255 - degree % 255);
+        let denominator = gf.eval(derivative.iter().rev().copied(), inverse);
+        if denominator == 0 {
+            return None;
+        }
+        let numerator = gf.mul(
+            gf.pow(degree),
+            gf.eval(evaluator.iter().rev().copied(), inverse),
+        );
+        block[block.len() - 1 - degree] ^= gf.div(numerator, denominator);
+    }
+    compute_syndromes(gf, block, ec_len)
+        .iter()
+        .all(|&s| s == 0)
+        .then_some(errors)
+}
+
+/// Corrects the errors in the data codewords block by block.
+///
+/// Returns the number of corrected codewords in each block.
+pub fn correct_codewords(codewords: &mut Codewords) -> Result<Vec<usize>, DeQRError> {
+    let gf = Gf256::new();
+    let count = codewords.blocks.len();
+    let ec_len = codewords.ec.len() / count;
+    codewords
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let ec = codewords.ec.iter().skip(i).step_by(count);
+            let mut block = codewords.data[range.clone()]
+                .iter()
+                .chain(ec)
+                .copied()
+                .collect::<Vec<_>>();
+            let errors = correct_errors(&gf, &mut block, ec_len).ok_or(DeQRError::DataEcc)?;
+            codewords.data[range.clone()].copy_from_slice(&block[..range.len()]);
+            Ok(errors)
+        })
+        .collect()
+}
+
+/// Reads the metadata of the QR code in the matrix with the number of
+/// erroneous codewords in each block.
+///
+/// The number of erroneous codewords is the number of codewords which are
+/// corrected when decoding.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the matrix cannot be read, or if the errors cannot be
+/// corrected.
+pub fn metadata_with_errors(matrix: &Matrix) -> Result<Metadata, DeQRError> {
+    let grid = Grid::new(SimpleGrid::from_func(matrix.width(), |x, y| {
+        matrix.module(x, y) == qrcode::Color::Dark
+    }));
+    grid_metadata_with_errors(&grid)
+}
+
+fn grid_metadata_with_errors<G: BitGrid>(grid: &Grid<G>) -> Result<Metadata, DeQRError> {
+    let mut codewords = read_codewords(grid)?;
+    let errors = correct_codewords(&mut codewords)?;
+    Ok(codewords.meta.metadata().with_block_errors(errors))
+}
+
 impl Extractor for MetaData {
     fn metadata(&self) -> Metadata {
         let symbol_version = self.version.0;
@@ -64,7 +973,13 @@ impl Extractor for MetaData {
             3 => Ecc::Q,
             _ => panic!("invalid error correction level"),
         };
-        Metadata::new(symbol_version, error_correction_level)
+        let mask_pattern = u8::try_from(self.mask).expect("invalid mask pattern");
+        Metadata::new(
+            symbol_version,
+            error_correction_level,
+            mask_pattern,
+            symbol_version * 4 + 17,
+        )
     }
 }
 
@@ -72,6 +987,51 @@ impl Extractor for MetaData {
 mod tests {
     use super::*;
 
+    #[test]
+    fn read_data_uri() {
+        assert_eq!(
+            from_data_uri("data:image/png;base64,UVIgY29kZQ==").unwrap(),
+            b"QR code"
+        );
+        assert_eq!(
+            from_data_uri("data:;BASE64,UVIgY29kZQ").unwrap(),
+            b"QR code"
+        );
+        assert!(from_data_uri("UVIgY29kZQ==").is_err());
+        assert!(from_data_uri("data:image/png,QR%20code").is_err());
+        assert!(from_data_uri("data:image/png;base64,UVIg*29kZQ==").is_err());
+    }
+
+    #[test]
+    fn correct_erroneous_codewords() {
+        let gf = Gf256::new();
+        let data = b"QR code error correction".to_vec();
+        let block = [data.clone(), ec::create_error_correction_code(&data, 10)].concat();
+        let mut damaged = block.clone();
+        assert_eq!(correct_errors(&gf, &mut damaged, 10), Some(0));
+        damaged[0] ^= 0xff;
+        damaged[30] ^= 0x01;
+        assert_eq!(correct_errors(&gf, &mut damaged.clone(), 10), Some(2));
+        for i in [3, 11, 33] {
+            damaged[i] = damaged[i].wrapping_add(1);
+        }
+        assert_eq!(correct_errors(&gf, &mut damaged, 10), Some(5));
+        assert_eq!(damaged, block);
+        for i in [1, 2, 4, 5, 6, 7] {
+            damaged[i] ^= 0x55;
+        }
+        assert_eq!(correct_errors(&gf, &mut damaged, 10), None);
+    }
+
+    #[test]
+    fn read_metadata_with_errors() {
+        let code =
+            qrcode::QrCode::with_version(b"QR code", Version::Normal(5), EcLevel::Q).unwrap();
+        let metadata = metadata_with_errors(&Matrix::from(&code)).unwrap();
+        assert_eq!(metadata.block_errors(), Some([0; 4].as_slice()));
+        assert_eq!(metadata.symbol_version(), 5);
+    }
+
     #[test]
     fn validate_metadata_extraction() {
         use rqrr::Version;
@@ -83,7 +1043,7 @@ mod tests {
                 mask: 4
             }
             .metadata(),
-            Metadata::new(1, Ecc::L)
+            Metadata::new(1, Ecc::L, 4, 21)
         );
         assert_eq!(
             MetaData {
@@ -92,7 +1052,7 @@ mod tests {
                 mask: 3
             }
             .metadata(),
-            Metadata::new(1, Ecc::M)
+            Metadata::new(1, Ecc::M, 3, 21)
         );
         assert_eq!(
             MetaData {
@@ -101,7 +1061,7 @@ mod tests {
                 mask: 7
             }
             .metadata(),
-            Metadata::new(1, Ecc::Q)
+            Metadata::new(1, Ecc::Q, 7, 21)
         );
         assert_eq!(
             MetaData {
@@ -110,7 +1070,115 @@ mod tests {
                 mask: 4
             }
             .metadata(),
-            Metadata::new(1, Ecc::H)
+            Metadata::new(1, Ecc::H, 4, 21)
+        );
+    }
+
+    #[test]
+    fn escape_content() {
+        assert_eq!(escape(b"QR code\n\tTab"), b"QR code\n\tTab");
+        assert_eq!(escape("漢字".as_bytes()), "漢字".as_bytes());
+        assert_eq!(escape(b"\x1b[2J\r\x7f"), br"\x1b[2J\x0d\x7f");
+        assert_eq!(escape("a\u{9b}b".as_bytes()), br"a\xc2\x9bb");
+        assert_eq!(escape(b"a\xffb\xe6\xbc"), br"a\xffb\xe6\xbc");
+        assert_eq!(escape(b""), b"");
+    }
+
+    #[test]
+    fn upscale_image() {
+        use image::{DynamicImage, GrayImage};
+
+        let image = DynamicImage::ImageLuma8(GrayImage::new(29, 58));
+        let upscaled = upscale(image.clone(), 300, imageops::FilterType::Nearest);
+        assert_eq!((upscaled.width(), upscaled.height()), (300, 600));
+        let upscaled = upscale(image.clone(), 29, imageops::FilterType::Nearest);
+        assert_eq!((upscaled.width(), upscaled.height()), (29, 58));
+        let upscaled = upscale(image, 10, imageops::FilterType::Nearest);
+        assert_eq!((upscaled.width(), upscaled.height()), (29, 58));
+    }
+
+    #[test]
+    fn downscale_image() {
+        use image::{DynamicImage, GrayImage};
+
+        let image = DynamicImage::ImageLuma8(GrayImage::new(600, 300));
+        let downscaled = downscale(image.clone(), 200, imageops::FilterType::Triangle);
+        assert_eq!((downscaled.width(), downscaled.height()), (200, 100));
+        let downscaled = downscale(image.clone(), 600, imageops::FilterType::Triangle);
+        assert_eq!((downscaled.width(), downscaled.height()), (600, 300));
+        let downscaled = downscale(image, 1, imageops::FilterType::Triangle);
+        assert_eq!((downscaled.width(), downscaled.height()), (1, 1));
+    }
+
+    #[test]
+    fn stitch_tiles() {
+        use image::{DynamicImage, GrayImage};
+
+        let tiles = (0..6)
+            .map(|i| DynamicImage::ImageLuma8(GrayImage::from_pixel(2, 3, Luma([i * 40]))))
+            .collect::<Vec<_>>();
+        let image = stitch(&tiles, 3).unwrap().into_luma8();
+        assert_eq!(image.dimensions(), (6, 6));
+        assert_eq!(image.get_pixel(0, 0), &Luma([0]));
+        assert_eq!(image.get_pixel(5, 2), &Luma([80]));
+        assert_eq!(image.get_pixel(2, 3), &Luma([160]));
+        assert_eq!(image.get_pixel(5, 5), &Luma([200]));
+        assert!(stitch(&tiles, 4).is_err());
+        assert!(stitch(&[], 1).is_err());
+        let mut tiles = tiles;
+        tiles[4] = DynamicImage::ImageLuma8(GrayImage::new(3, 2));
+        assert_eq!(
+            stitch(&tiles, 3).unwrap_err().to_string(),
+            "tile 5 is 3x2 pixels, but tile 1 is 2x3 pixels"
         );
     }
+
+    #[test]
+    fn convert_to_luminance() {
+        let image = RgbImage::from_fn(3, 1, |x, _| match x {
+            0 => image::Rgb([0, 0, 0]),
+            1 => image::Rgb([u8::MAX, u8::MAX, u8::MAX]),
+            _ => image::Rgb([u8::MAX, 0, 0]),
+        });
+        let image = to_luminance(&image);
+        assert_eq!(image.get_pixel(0, 0).0, [0]);
+        assert_eq!(image.get_pixel(1, 0).0, [255]);
+        // The relative luminance of red is 0.2126.
+        assert_eq!(image.get_pixel(2, 0).0, [127]);
+    }
+
+    #[test]
+    fn convert_to_saturation() {
+        let image = RgbImage::from_fn(4, 1, |x, _| match x {
+            0 => image::Rgb([0, 0, 0]),
+            1 => image::Rgb([u8::MAX, u8::MAX, u8::MAX]),
+            2 => image::Rgb([u8::MAX, 0, 0]),
+            _ => image::Rgb([200, 100, 100]),
+        });
+        let image = to_saturation(&image);
+        assert_eq!(image.get_pixel(0, 0).0, [255]);
+        assert_eq!(image.get_pixel(1, 0).0, [255]);
+        assert_eq!(image.get_pixel(2, 0).0, [0]);
+        assert_eq!(image.get_pixel(3, 0).0, [128]);
+    }
+
+    #[test]
+    fn binarize_image() {
+        let mut image = GrayImage::from_raw(4, 1, vec![0, 127, 128, 255]).unwrap();
+        binarize(&mut image, 128);
+        assert_eq!(image.into_raw(), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn compute_otsu_threshold() {
+        let image = GrayImage::from_raw(6, 1, vec![20, 30, 40, 200, 210, 220]).unwrap();
+        let threshold = otsu_threshold(&image);
+        assert!((41..=200).contains(&threshold));
+        let mut binarized = image;
+        binarize(&mut binarized, threshold);
+        assert_eq!(binarized.into_raw(), [0, 0, 0, 255, 255, 255]);
+
+        let image = GrayImage::from_raw(2, 1, vec![100, 100]).unwrap();
+        assert_eq!(otsu_threshold(&image), 1);
+    }
 }
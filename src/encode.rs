@@ -3,21 +3,315 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
+//! Functions for encoding data in a QR code.
+
+use std::{num::NonZeroU32, ops::RangeInclusive};
+
+use clap::ValueEnum;
 use csscolorparser::Color;
-use image::{Rgba, RgbaImage};
+use image::{imageops, DynamicImage, GrayImage, ImageBuffer, Luma, Pixel, Rgba, RgbaImage};
 use qrcode::{
     bits::Bits,
-    render::{pic, svg, unicode, Renderer},
+    canvas::{Canvas, MaskPattern},
+    ec,
+    render::{pic, unicode, Renderer},
     types::QrError,
     EcLevel, QrCode, QrResult, Version,
 };
+use serde::Serialize;
 
 use crate::{
-    cli::{Ecc, Mode, Variant},
+    color,
+    matrix::{Matrix, Modules},
     metadata::{Extractor, Metadata},
 };
 
+/// Error correction level.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub enum Ecc {
+    /// Level L.
+    ///
+    /// 7% of codewords can be restored.
+    L,
+
+    /// Level M.
+    ///
+    /// 15% of codewords can be restored.
+    #[default]
+    M,
+
+    /// Level Q.
+    ///
+    /// 25% of codewords can be restored.
+    Q,
+
+    /// Level H.
+    ///
+    /// 30% of codewords can be restored.
+    H,
+}
+
+impl From<Ecc> for EcLevel {
+    fn from(level: Ecc) -> Self {
+        match level {
+            Ecc::L => Self::L,
+            Ecc::M => Self::M,
+            Ecc::Q => Self::Q,
+            Ecc::H => Self::H,
+        }
+    }
+}
+
+/// The mode of the data.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// All digits.
+    Numeric,
+
+    /// Alphanumerics and few symbols.
+    Alphanumeric,
+
+    /// Arbitrary binary data.
+    Byte,
+
+    /// Shift JIS text.
+    Kanji,
+}
+
+/// The type of QR code.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+    /// Normal QR code.
+    #[default]
+    Normal,
+
+    /// Micro QR code.
+    Micro,
+
+    /// Micro QR code if the data can be stored in it, otherwise normal QR
+    /// code.
+    Auto,
+}
+
+/// The character set of a segment.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Charset {
+    /// US-ASCII.
+    Ascii,
+
+    /// ISO/IEC 8859-1.
+    #[value(name = "iso-8859-1", alias("latin1"))]
+    Iso8859_1,
+
+    /// Shift JIS.
+    #[value(name = "shift-jis", aliases(["shift_jis", "sjis"]))]
+    ShiftJis,
+
+    /// UTF-8.
+    #[value(name = "utf-8", alias("utf8"))]
+    Utf8,
+
+    /// Big5.
+    Big5,
+
+    /// GB 18030.
+    Gb18030,
+
+    /// EUC-KR.
+    #[value(name = "euc-kr")]
+    EucKr,
+}
+
+/// The position of the QR code in the padded image.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Alignment {
+    /// Center the QR code.
+    #[default]
+    Center,
+
+    /// Align the QR code to the top left corner.
+    #[value(alias("topleft"))]
+    TopLeft,
+}
+
+/// The shape of the dark modules.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ModuleShape {
+    /// Square which fills the module.
+    #[default]
+    Square,
+
+    /// Circle inscribed in the module.
+    #[value(alias("dot"))]
+    Circle,
+}
+
+impl Charset {
+    /// Returns the ECI assignment number of the character set.
+    #[must_use]
+    pub const fn eci(self) -> u32 {
+        match self {
+            Self::Iso8859_1 => 3,
+            Self::ShiftJis => 20,
+            Self::Utf8 => 26,
+            Self::Ascii => 27,
+            Self::Big5 => 28,
+            Self::Gb18030 => 29,
+            Self::EucKr => 30,
+        }
+    }
+
+    /// Encodes the text in the character set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the text contains a character which cannot be
+    /// encoded in the character set.
+    pub fn encode(self, text: &str) -> QrResult<Vec<u8>> {
+        let encoding = match self {
+            Self::Ascii => {
+                return text
+                    .is_ascii()
+                    .then(|| text.as_bytes().to_vec())
+                    .ok_or(QrError::UnsupportedCharacterSet);
+            }
+            Self::Iso8859_1 => {
+                return text
+                    .chars()
+                    .map(|c| u8::try_from(c).map_err(|_| QrError::UnsupportedCharacterSet))
+                    .collect();
+            }
+            Self::Utf8 => return Ok(text.as_bytes().to_vec()),
+            Self::ShiftJis => encoding_rs::SHIFT_JIS,
+            Self::Big5 => encoding_rs::BIG5,
+            Self::Gb18030 => encoding_rs::GB18030,
+            Self::EucKr => encoding_rs::EUC_KR,
+        };
+        let (bytes, _, had_errors) = encoding.encode(text);
+        if had_errors {
+            return Err(QrError::UnsupportedCharacterSet);
+        }
+        Ok(bytes.into_owned())
+    }
+}
+
+/// A segment of the data which is encoded in byte mode with the ECI
+/// designator of its character set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Segment {
+    charset: Charset,
+    data: Vec<u8>,
+}
+
+impl Segment {
+    /// Constructs a new segment from the text encoded in the character set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the text cannot be encoded in the character set.
+    pub fn new(text: &str, charset: Charset) -> QrResult<Self> {
+        let data = charset.encode(text)?;
+        Ok(Self { charset, data })
+    }
+
+    /// Returns the character set of the segment.
+    #[must_use]
+    pub const fn charset(&self) -> Charset {
+        self.charset
+    }
+
+    /// Returns the encoded data of the segment.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// Options for encoding data in a QR code.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct EncodeOptions {
+    /// Error correction level.
+    pub error_correction_level: Ecc,
+
+    /// The version of the symbol.
+    ///
+    /// If this is [`None`], the minimum version required to store the data
+    /// will be automatically chosen.
+    pub symbol_version: Option<i16>,
+
+    /// The type of QR code.
+    ///
+    /// This is only used if `symbol_version` is specified.
+    pub variant: Variant,
+
+    /// The mode of the data.
+    ///
+    /// This is only used if `symbol_version` is specified. If this is
+    /// [`None`], use the optimal encoding.
+    pub mode: Option<Mode>,
+
+    /// The width of margin.
+    ///
+    /// If this is [`None`], the margin will be 4 for normal QR code and 2 for
+    /// Micro QR code.
+    pub margin: Option<u32>,
+
+    /// The module size in pixels.
+    ///
+    /// If this is [`None`], the module size is 8.
+    pub module_size: Option<u32>,
+
+    /// Foreground and background colors.
+    pub colors: (Color, Color),
+}
+
+impl EncodeOptions {
+    /// Constructs a new QR code from the data with these options.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the version is invalid, or if the data cannot be
+    /// encoded.
+    pub fn to_qr_code(&self, data: impl AsRef<[u8]>) -> QrResult<QrCode> {
+        let version = self
+            .symbol_version
+            .map(|version| set_version(version, &self.variant))
+            .transpose()?;
+        to_qr_code(
+            data,
+            version,
+            self.mode.as_ref(),
+            self.error_correction_level.into(),
+        )
+    }
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self {
+            error_correction_level: Ecc::default(),
+            symbol_version: None,
+            variant: Variant::default(),
+            mode: None,
+            margin: None,
+            module_size: None,
+            colors: (
+                Color::from_rgba8(u8::MIN, u8::MIN, u8::MIN, u8::MAX),
+                Color::from_rgba8(u8::MAX, u8::MAX, u8::MAX, u8::MAX),
+            ),
+        }
+    }
+}
+
 /// Sets the version.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the version is out of range for the variant, or if the
+/// variant is [`Variant::Auto`].
 pub const fn set_version(version: i16, variant: &Variant) -> QrResult<Version> {
     match variant {
         Variant::Normal => {
@@ -34,10 +328,15 @@ pub const fn set_version(version: i16, variant: &Variant) -> QrResult<Version> {
                 Err(QrError::InvalidVersion)
             }
         }
+        Variant::Auto => Err(QrError::InvalidVersion),
     }
 }
 
 /// Encodes data for the selected mode to the bits.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be encoded in the mode.
 pub fn push_data_for_selected_mode(
     bits: &mut Bits,
     data: impl AsRef<[u8]>,
@@ -48,13 +347,534 @@ pub fn push_data_for_selected_mode(
         Mode::Numeric => bits.push_numeric_data(data),
         Mode::Alphanumeric => bits.push_alphanumeric_data(data),
         Mode::Byte => bits.push_byte_data(data),
-        Mode::Kanji => bits.push_kanji_data(data),
+        Mode::Kanji => {
+            if find_invalid_kanji(data).is_some() {
+                return Err(QrError::InvalidCharacter);
+            }
+            bits.push_kanji_data(data)
+        }
+    }
+}
+
+/// Returns the byte offset of the first character in `data` which cannot be
+/// encoded in kanji mode.
+///
+/// Kanji mode can only encode the double-byte Shift JIS characters in the
+/// ranges 0x8140 to 0x9FFC and 0xE040 to 0xEBBF.
+#[must_use]
+pub fn find_invalid_kanji(data: impl AsRef<[u8]>) -> Option<usize> {
+    let is_kanji = |c: &[u8]| match *c {
+        [0x81..=0x9f | 0xe0..=0xea, trail] => matches!(trail, 0x40..=0x7e | 0x80..=0xfc),
+        [0xeb, trail] => matches!(trail, 0x40..=0x7e | 0x80..=0xbf),
+        _ => false,
+    };
+    data.as_ref()
+        .chunks(2)
+        .position(|c| !is_kanji(c))
+        .map(|i| i * 2)
+}
+
+/// Returns the maximum number of characters which can be stored in the given
+/// version with the mode and the error correction level.
+///
+/// For [`Mode::Byte`], this is the number of bytes. For [`Mode::Kanji`], this
+/// is the number of double-byte characters.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the mode or the error correction level is not supported
+/// by the version.
+pub fn capacity(version: Version, mode: &Mode, level: EcLevel) -> QrResult<usize> {
+    let fits = |len: usize| {
+        let data = match mode {
+            Mode::Numeric => vec![b'0'; len],
+            Mode::Alphanumeric => vec![b'A'; len],
+            Mode::Byte => vec![u8::MIN; len],
+            // "亜" in Shift JIS.
+            Mode::Kanji => [0x88, 0x9f].repeat(len),
+        };
+        let mut bits = Bits::new(version);
+        push_data_for_selected_mode(&mut bits, data, mode)
+            .and_then(|()| bits.push_terminator(level))
+    };
+    fits(0)?;
+    // The capacity never exceeds the number of the data bits.
+    let (mut low, mut high) = (0, Bits::new(version).max_len(level)?);
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if fits(mid).is_ok() {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+/// Returns the number of data codewords used by the data and the number of
+/// data codewords of the QR code.
+///
+/// The data is encoded in the same way as [`to_qr_code`], and the terminator
+/// and the padding are not counted as used.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be stored in the QR code.
+pub fn codewords(
+    data: impl AsRef<[u8]>,
+    code: &QrCode,
+    mode: Option<&Mode>,
+) -> QrResult<(usize, usize)> {
+    let mut bits = Bits::new(code.version());
+    if let Some(mode) = mode {
+        push_data_for_selected_mode(&mut bits, data, mode)?;
+    } else {
+        bits.push_optimal_data(data.as_ref())?;
+    }
+    let total = bits.max_len(code.error_correction_level())?;
+    Ok((bits.len().div_ceil(8), total.div_ceil(8)))
+}
+
+/// Encodes the segments in order to the bits.
+///
+/// The ECI designator is only written when the character set differs from
+/// that of the previous segment. If `eci` is `false`, no ECI designator is
+/// written at all, and the encoded data of the segments are written as is.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the segments cannot be encoded, such as in a Micro QR
+/// code which does not support ECI.
+pub fn push_segments(bits: &mut Bits, segments: &[Segment], eci: bool) -> QrResult<()> {
+    let mut charset = None;
+    for segment in segments {
+        if eci && charset != Some(segment.charset) {
+            bits.push_eci_designator(segment.charset.eci())?;
+            charset = Some(segment.charset);
+        }
+        bits.push_byte_data(&segment.data)?;
+    }
+    Ok(())
+}
+
+/// Constructs a new QR code from the segments with the minimum version in
+/// `versions` required to store them.
+///
+/// Only normal QR code is supported because Micro QR code does not support
+/// ECI. If `level` is [`None`], the highest error correction level which can
+/// store the segments is chosen for each version, but the levels lower than
+/// `min_level` are not tried. `eci` is the same as [`push_segments`].
+///
+/// # Errors
+///
+/// Returns [`Err`] if the segments cannot be stored in any version in
+/// `versions`.
+pub fn to_qr_code_with_segments(
+    segments: &[Segment],
+    versions: RangeInclusive<i16>,
+    level: Option<EcLevel>,
+    min_level: EcLevel,
+    eci: bool,
+) -> QrResult<QrCode> {
+    let levels = level.map_or_else(|| levels_from(min_level).collect(), |level| vec![level]);
+    let mut result = Err(QrError::DataTooLong);
+    for version in versions {
+        for &level in &levels {
+            let mut bits = Bits::new(set_version(version, &Variant::Normal)?);
+            result = push_segments(&mut bits, segments, eci)
+                .and_then(|()| bits.push_terminator(level))
+                .and_then(|()| QrCode::with_bits(bits, level));
+            if !matches!(result, Err(QrError::DataTooLong)) {
+                return result;
+            }
+        }
     }
+    result
+}
+
+/// Returns the number of data codewords used by the segments and the number of
+/// data codewords of the QR code.
+///
+/// This is the same as [`codewords`], except that the segments are encoded in
+/// the same way as [`to_qr_code_with_segments`].
+///
+/// # Errors
+///
+/// Returns [`Err`] if the segments cannot be stored in the QR code.
+pub fn segments_codewords(
+    segments: &[Segment],
+    code: &QrCode,
+    eci: bool,
+) -> QrResult<(usize, usize)> {
+    let mut bits = Bits::new(code.version());
+    push_segments(&mut bits, segments, eci)?;
+    let total = bits.max_len(code.error_correction_level())?;
+    Ok((bits.len().div_ceil(8), total.div_ceil(8)))
+}
+
+/// The mask patterns in the order of their references.
+const MASK_PATTERNS: [MaskPattern; 8] = [
+    MaskPattern::Checkerboard,
+    MaskPattern::HorizontalLines,
+    MaskPattern::VerticalLines,
+    MaskPattern::DiagonalLines,
+    MaskPattern::LargeCheckerboard,
+    MaskPattern::Fields,
+    MaskPattern::Diamonds,
+    MaskPattern::Meadow,
+];
+
+/// Constructs the matrices of the QR code with each of the eight mask
+/// patterns.
+///
+/// The matrices are in the order of the mask pattern references. The data is
+/// encoded in the same way as `code`.
+///
+/// # Errors
+///
+/// Returns [`Err`] if `code` is a Micro QR code, or if the data cannot be
+/// encoded.
+#[allow(clippy::missing_panics_doc)]
+pub fn to_matrices_with_all_masks(
+    data: impl AsRef<[u8]>,
+    code: &QrCode,
+    mode: Option<&Mode>,
+) -> QrResult<Vec<Matrix>> {
+    let (version, level) = (code.version(), code.error_correction_level());
+    if version.is_micro() {
+        return Err(QrError::InvalidVersion);
+    }
+    let mut bits = Bits::new(version);
+    if let Some(mode) = mode {
+        push_data_for_selected_mode(&mut bits, data, mode)?;
+    } else {
+        bits.push_optimal_data(data.as_ref())?;
+    }
+    bits.push_terminator(level)?;
+    let (encoded, ec) = ec::construct_codewords(&bits.into_bytes(), version, level)?;
+    let mut canvas = Canvas::new(version, level);
+    canvas.draw_all_functional_patterns();
+    canvas.draw_data(&encoded, &ec);
+    let matrices = MASK_PATTERNS
+        .iter()
+        .map(|&pattern| {
+            let mut canvas = canvas.clone();
+            canvas.apply_mask(pattern);
+            Matrix::new(code.width(), canvas.into_colors())
+                .expect("width of the QR code should be valid")
+        })
+        .collect();
+    Ok(matrices)
+}
+
+/// Computes the penalty score of the QR code.
+///
+/// The score is the sum of the penalties for adjacent modules in the same
+/// color, 2x2 blocks, finder-like patterns and the proportion of dark modules,
+/// as defined in ISO/IEC 18004. A lower score is better.
+pub fn penalty_score(code: &impl Modules) -> usize {
+    let width = code.width();
+    let is_dark = |x, y| code.module(x, y) == qrcode::Color::Dark;
+    let lines = (0..width)
+        .map(|y| (0..width).map(|x| is_dark(x, y)).collect::<Vec<_>>())
+        .chain((0..width).map(|x| (0..width).map(|y| is_dark(x, y)).collect()));
+
+    let mut score = 0;
+    for line in lines {
+        let mut run = 0;
+        for (i, module) in line.iter().enumerate() {
+            run += 1;
+            if line.get(i + 1) != Some(module) {
+                if run >= 5 {
+                    score += run - 2;
+                }
+                run = 0;
+            }
+        }
+
+        let padded = [[false; 4].as_slice(), &line, &[false; 4]].concat();
+        let finder_like = padded
+            .windows(11)
+            .filter(|w| {
+                matches!(
+                    w,
+                    [false, false, false, false, true, false, true, true, true, false, true]
+                        | [true, false, true, true, true, false, true, false, false, false, false]
+                )
+            })
+            .count();
+        score += 40 * finder_like;
+    }
+
+    for y in 1..width {
+        for x in 1..width {
+            let block =
+                [(x - 1, y - 1), (x, y - 1), (x - 1, y), (x, y)].map(|(x, y)| is_dark(x, y));
+            if block.iter().all(|&m| m == block[0]) {
+                score += 3;
+            }
+        }
+    }
+
+    let total = width * width;
+    let dark = (0..width)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| is_dark(x, y))
+        .count();
+    score + 10 * ((dark * 20).abs_diff(total * 10) / total)
+}
+
+/// Decodes the hexadecimal string into bytes.
+///
+/// Both uppercase and lowercase digits are accepted, and ASCII whitespace is
+/// ignored. Returns [`None`] if `data` is not a valid hexadecimal string.
+#[must_use]
+pub fn from_hex(data: impl AsRef<[u8]>) -> Option<Vec<u8>> {
+    let digits = data
+        .as_ref()
+        .iter()
+        .filter(|c| !c.is_ascii_whitespace())
+        .map(|&c| char::from(c).to_digit(16))
+        .collect::<Option<Vec<_>>>()?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .chunks_exact(2)
+        .map(|pair| u8::try_from(pair[0] << 4 | pair[1]).ok())
+        .collect()
+}
+
+/// Decodes the base64 string into bytes.
+///
+/// The standard alphabet is used, and the padding is optional. ASCII whitespace
+/// is ignored.
+///
+/// # Errors
+///
+/// Returns [`Err`] if `data` is not a valid base64 string.
+pub fn from_base64(data: impl AsRef<[u8]>) -> Result<Vec<u8>, base64::DecodeError> {
+    use base64::{
+        alphabet,
+        engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+        Engine,
+    };
+
+    let engine = GeneralPurpose::new(
+        &alphabet::STANDARD,
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+    );
+    let data = data
+        .as_ref()
+        .iter()
+        .copied()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    engine.decode(data)
+}
+
+/// Converts UTF-8 text to Shift JIS.
+///
+/// # Errors
+///
+/// Returns [`Err`] if `data` is not valid UTF-8, or if it contains characters
+/// which cannot be represented in Shift JIS.
+pub fn to_shift_jis(data: impl AsRef<[u8]>) -> QrResult<Vec<u8>> {
+    let text = std::str::from_utf8(data.as_ref()).map_err(|_| QrError::UnsupportedCharacterSet)?;
+    let (bytes, _, had_errors) = encoding_rs::SHIFT_JIS.encode(text);
+    if had_errors {
+        return Err(QrError::UnsupportedCharacterSet);
+    }
+    Ok(bytes.into_owned())
+}
+
+/// Constructs a new QR code.
+///
+/// If `version` is [`None`], the minimum version required to store the data
+/// will be automatically chosen and `mode` is ignored.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be encoded.
+#[allow(clippy::option_if_let_else)]
+pub fn to_qr_code(
+    data: impl AsRef<[u8]>,
+    version: Option<Version>,
+    mode: Option<&Mode>,
+    level: EcLevel,
+) -> QrResult<QrCode> {
+    let data = data.as_ref();
+    if let Some(version) = version {
+        let mut bits = Bits::new(version);
+        if let Some(mode) = mode {
+            push_data_for_selected_mode(&mut bits, data, mode)
+        } else {
+            bits.push_optimal_data(data)
+        }
+        .and_then(|()| bits.push_terminator(level))
+        .and_then(|()| QrCode::with_bits(bits, level))
+    } else {
+        QrCode::with_error_correction_level(data, level)
+    }
+}
+
+/// Constructs a new QR code with the minimum version required to store the
+/// data.
+///
+/// Unlike [`QrCode::with_error_correction_level`], this tries versions of the
+/// given variant in ascending order, and uses `mode` if it is specified.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be stored in any version.
+pub fn to_qr_code_with_min_version(
+    data: impl AsRef<[u8]>,
+    variant: &Variant,
+    mode: Option<&Mode>,
+    level: EcLevel,
+) -> QrResult<QrCode> {
+    to_qr_code_in_versions(data, 1..=40, variant, mode, level)
+}
+
+/// Constructs a new QR code with the minimum version in `versions` required to
+/// store the data.
+///
+/// This is the same as [`to_qr_code_with_min_version`], except that only the
+/// versions in `versions` are tried. The versions beyond the maximum version
+/// of the given variant are ignored. If `variant` is [`Variant::Auto`], the
+/// versions of Micro QR code are tried before the versions of normal QR code.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be stored in any version in `versions`,
+/// or if `versions` contains no valid version.
+pub fn to_qr_code_in_versions(
+    data: impl AsRef<[u8]>,
+    versions: RangeInclusive<i16>,
+    variant: &Variant,
+    mode: Option<&Mode>,
+    level: EcLevel,
+) -> QrResult<QrCode> {
+    let data = data.as_ref();
+    let max_version = match variant {
+        Variant::Normal => 40,
+        Variant::Micro => 4,
+        Variant::Auto => {
+            return to_qr_code_in_versions(data, versions.clone(), &Variant::Micro, mode, level)
+                .or_else(|_| {
+                    to_qr_code_in_versions(data, versions, &Variant::Normal, mode, level)
+                });
+        }
+    };
+    let (start, end) = (*versions.start(), (*versions.end()).min(max_version));
+    if start > end {
+        return Err(QrError::InvalidVersion);
+    }
+    let mut result = Err(QrError::DataTooLong);
+    for version in start..=end {
+        let version = set_version(version, variant)?;
+        result = to_qr_code(data, Some(version), mode, level);
+        if !matches!(
+            result,
+            Err(QrError::DataTooLong | QrError::InvalidVersion | QrError::UnsupportedCharacterSet)
+        ) {
+            break;
+        }
+    }
+    result
+}
+
+/// Returns the error correction levels which are not lower than `min_level`, in
+/// the order of H, Q, M and L.
+fn levels_from(min_level: EcLevel) -> impl Iterator<Item = EcLevel> {
+    [EcLevel::H, EcLevel::Q, EcLevel::M, EcLevel::L]
+        .into_iter()
+        .filter(move |&level| level >= min_level)
+}
+
+/// Returns the minimum version required to store the data, with the number of
+/// data codewords used by the data and the number of data codewords of the
+/// version.
+///
+/// The versions are tried in the same order as [`to_qr_code_with_min_version`],
+/// but only the data bits are built, so no QR code is constructed.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be stored in any version.
+pub fn min_version(
+    data: impl AsRef<[u8]>,
+    variant: &Variant,
+    mode: Option<&Mode>,
+    level: EcLevel,
+) -> QrResult<(Version, (usize, usize))> {
+    let data = data.as_ref();
+    let versions = match variant {
+        Variant::Normal => (1..=40).map(Version::Normal).collect(),
+        Variant::Micro => (1..=4).map(Version::Micro).collect(),
+        Variant::Auto => (1..=4)
+            .map(Version::Micro)
+            .chain((1..=40).map(Version::Normal))
+            .collect::<Vec<_>>(),
+    };
+    let mut result = Err(QrError::DataTooLong);
+    for version in versions {
+        let mut bits = Bits::new(version);
+        result = if let Some(mode) = mode {
+            push_data_for_selected_mode(&mut bits, data, mode)
+        } else {
+            bits.push_optimal_data(data)
+        }
+        .and_then(|()| bits.max_len(level))
+        .and_then(|total| {
+            let used = bits.len().div_ceil(8);
+            bits.push_terminator(level)
+                .map(|()| (version, (used, total.div_ceil(8))))
+        });
+        if !matches!(
+            result,
+            Err(QrError::DataTooLong | QrError::InvalidVersion | QrError::UnsupportedCharacterSet)
+        ) {
+            break;
+        }
+    }
+    result
+}
+
+/// Constructs a new QR code with the highest error correction level which can
+/// store the data in the given version.
+///
+/// This tries the error correction levels in the order of H, Q, M and L, but
+/// the levels lower than `min_level` are not tried.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be stored with any error correction
+/// level which is not lower than `min_level`.
+pub fn to_qr_code_with_max_level(
+    data: impl AsRef<[u8]>,
+    version: Version,
+    mode: Option<&Mode>,
+    min_level: EcLevel,
+) -> QrResult<QrCode> {
+    let data = data.as_ref();
+    let mut result = Err(QrError::DataTooLong);
+    for level in levels_from(min_level) {
+        result = to_qr_code(data, Some(version), mode, level);
+        if !matches!(result, Err(QrError::DataTooLong | QrError::InvalidVersion)) {
+            break;
+        }
+    }
+    result
 }
 
 /// Renders the QR code into an image.
+///
+/// Each module is a solid square of `module_size` pixels, so the modules have
+/// hard edges and no pixel is interpolated.
+#[must_use]
 pub fn to_image(
-    code: &QrCode,
+    code: &impl Modules,
     margin: u32,
     colors: &(Color, Color),
     module_size: Option<u32>,
@@ -67,30 +887,1098 @@ pub fn to_image(
     if let Some(size) = module_size {
         renderer = renderer.module_dimensions(size, size);
     }
-    renderer.build()
+    renderer.build()
+}
+
+/// Renders the QR code into a 16-bit grayscale image.
+///
+/// The colors are converted to grayscale, and their alpha components are
+/// ignored.
+#[must_use]
+pub fn to_luma16_image(
+    code: &impl Modules,
+    margin: u32,
+    colors: &(Color, Color),
+    module_size: Option<u32>,
+) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+    let c = code.to_colors();
+    let mut renderer = &mut Renderer::<Luma<u16>>::new(&c, code.width(), margin);
+    renderer = renderer
+        .dark_color(to_luma16(&colors.0))
+        .light_color(to_luma16(&colors.1));
+    if let Some(size) = module_size {
+        renderer = renderer.module_dimensions(size, size);
+    }
+    renderer.build()
+}
+
+/// Returns `true` if each module of the image rendered by [`to_image`] is a
+/// solid square of `module_size` pixels.
+///
+/// This holds only if the modules are scaled by replicating pixels, and
+/// becomes `false` if the edges of modules are smoothed by interpolation.
+#[must_use]
+pub fn has_hard_edges<P: Pixel + PartialEq>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    module_size: Option<u32>,
+) -> bool {
+    let module_size = module_size.unwrap_or(8);
+    image.width() % module_size == 0
+        && image.height() % module_size == 0
+        && image
+            .enumerate_pixels()
+            .all(|(x, y, pixel)| pixel == image.get_pixel(x - x % module_size, y - y % module_size))
+}
+
+/// Encodes the image as a 1-bit grayscale PNG image.
+///
+/// Pixels whose luma is less than half become black, and the others become
+/// white. If `filter` is [`None`], the default filter of the encoder is used.
+///
+/// # Errors
+///
+/// Returns [`Err`] if encoding the image fails.
+pub fn to_bilevel_png(
+    image: &GrayImage,
+    compression: png::Compression,
+    filter: Option<(png::FilterType, png::AdaptiveFilterType)>,
+) -> Result<Vec<u8>, png::EncodingError> {
+    let (width, height) = image.dimensions();
+    let data = pack_rows(image, |luma| luma >= 0x80);
+    let mut buf = Vec::new();
+    let mut encoder = png::Encoder::new(&mut buf, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::One);
+    encoder.set_compression(compression);
+    if let Some((filter, adaptive_filter)) = filter {
+        encoder.set_filter(filter);
+        encoder.set_adaptive_filter(adaptive_filter);
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(buf)
+}
+
+/// Packs each row of the image into bytes, in which the most significant bit
+/// is the leftmost pixel.
+///
+/// A bit is set if `is_set` returns `true` for the luma of the pixel, and each
+/// row is padded to a byte boundary.
+fn pack_rows(image: &GrayImage, is_set: impl Fn(u8) -> bool) -> Vec<u8> {
+    image
+        .rows()
+        .flat_map(|row| {
+            row.collect::<Vec<_>>()
+                .chunks(8)
+                .map(|pixels| {
+                    (0..)
+                        .zip(pixels)
+                        .filter(|(_, pixel)| is_set(pixel.0[0]))
+                        .fold(u8::MIN, |byte, (i, _)| byte | (0x80 >> i))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Renders the QR code into a bitmap, and returns its width in pixels and its
+/// rows packed by [`pack_rows`], in which a set bit is a dark module.
+fn to_bitmap(code: &impl Modules, margin: u32, module_size: Option<u32>) -> (u32, Vec<u8>) {
+    let c = code.to_colors();
+    let mut renderer = &mut Renderer::<Luma<u8>>::new(&c, code.width(), margin);
+    if let Some(size) = module_size {
+        renderer = renderer.module_dimensions(size, size);
+    }
+    let image = renderer.build();
+    (image.width(), pack_rows(&image, |luma| luma < 0x80))
+}
+
+/// Renders the QR code into a binary PBM image.
+///
+/// The image is written in the raw (P4) format, in which each pixel is a
+/// single bit and a set bit is black.
+#[must_use]
+pub fn to_pbm(code: &impl Modules, margin: u32, module_size: Option<u32>) -> Vec<u8> {
+    let (width, bitmap) = to_bitmap(code, margin, module_size);
+    let header = format!("P4\n{width} {width}\n");
+    [header.as_bytes(), &bitmap].concat()
+}
+
+/// Formats the bytes as the elements of an array literal, 12 bytes per line.
+fn to_array_elements(bytes: &[u8]) -> String {
+    bytes
+        .chunks(12)
+        .map(|line| {
+            let line = line
+                .iter()
+                .map(|byte| format!("{byte:#04x},"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("    {line}\n")
+        })
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// Renders the QR code into a C array.
+///
+/// Each row is packed into bytes in the same way as [`to_pbm`], in which the
+/// most significant bit is the leftmost pixel and a set bit is a dark module.
+/// Unless `module_size` is specified, each module is a single pixel. The width
+/// and the height in pixels are defined as `QR_WIDTH`.
+#[must_use]
+pub fn to_c_array(code: &impl Modules, margin: u32, module_size: Option<u32>) -> String {
+    let (width, bitmap) = to_bitmap(code, margin, module_size.or(Some(1)));
+    format!(
+        "#include <stdint.h>\n\n#define QR_WIDTH {width}\n\nconst uint8_t qr[{len}] = {{\n{elements}}};\n",
+        len = bitmap.len(),
+        elements = to_array_elements(&bitmap)
+    )
+}
+
+/// Renders the QR code into a Rust array.
+///
+/// The array is the same as the one rendered by [`to_c_array`]. The width and
+/// the height in pixels are defined as `QR_WIDTH`.
+#[must_use]
+pub fn to_rust_array(code: &impl Modules, margin: u32, module_size: Option<u32>) -> String {
+    let (width, bitmap) = to_bitmap(code, margin, module_size.or(Some(1)));
+    format!(
+        "pub const QR_WIDTH: usize = {width};\n\npub const QR: [u8; {len}] = [\n{elements}];\n",
+        len = bitmap.len(),
+        elements = to_array_elements(&bitmap)
+    )
+}
+
+/// Returns the module size in pixels for the QR code to have the physical size
+/// at the resolution.
+///
+/// `length` is the length of each side of the QR code in millimeters, and
+/// `modules` is the number of modules on each side, both including the margin.
+/// The module size is rounded to the nearest integer, and [`None`] is returned
+/// if it is rounded to zero.
+#[must_use]
+pub fn fit_module_size(length: f64, dpi: u32, modules: u32) -> Option<NonZeroU32> {
+    let pixels = length / 25.4 * f64::from(dpi);
+    let size = (pixels / f64::from(modules))
+        .round()
+        .min(f64::from(u32::MAX));
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    NonZeroU32::new(size as u32)
+}
+
+/// Returns the number of pixels of the physical length at the resolution.
+///
+/// `length` is in millimeters, and the number of pixels is rounded to the
+/// nearest integer.
+#[must_use]
+pub fn to_pixels(length: f64, dpi: u32) -> u32 {
+    let pixels = (length / 25.4 * f64::from(dpi))
+        .round()
+        .min(f64::from(u32::MAX));
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let pixels = pixels as u32;
+    pixels
+}
+
+/// Pads the image to `size` pixels on each side with the background color.
+///
+/// The image is placed according to `alignment`. A side which is already
+/// `size` pixels or larger is not padded. A 16-bit grayscale image is padded
+/// with the grayscale background color, and any other image is converted to
+/// RGBA.
+#[must_use]
+pub fn pad_image(
+    image: DynamicImage,
+    size: u32,
+    background: &Color,
+    alignment: Alignment,
+) -> DynamicImage {
+    let (width, height) = (image.width().max(size), image.height().max(size));
+    if (width, height) == (image.width(), image.height()) {
+        return image;
+    }
+    let (x, y) = match alignment {
+        Alignment::Center => ((width - image.width()) / 2, (height - image.height()) / 2),
+        Alignment::TopLeft => (0, 0),
+    };
+    if let DynamicImage::ImageLuma16(image) = image {
+        let mut canvas = ImageBuffer::from_pixel(width, height, to_luma16(background));
+        imageops::replace(&mut canvas, &image, x.into(), y.into());
+        canvas.into()
+    } else {
+        let mut canvas = RgbaImage::from_pixel(width, height, Rgba(background.to_rgba8()));
+        imageops::replace(&mut canvas, &image.into_rgba8(), x.into(), y.into());
+        canvas.into()
+    }
+}
+
+/// Sets the resolution of the PNG image in dots per inch.
+///
+/// This inserts the `pHYs` chunk right after the `IHDR` chunk. If the data is
+/// not a PNG image, it is returned as is.
+#[must_use]
+pub fn set_png_dpi(png: &[u8], dpi: u32) -> Vec<u8> {
+    // The signature and the `IHDR` chunk.
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+
+    if png.get(12..16) != Some(b"IHDR") || png.len() < IHDR_END {
+        return png.into();
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let pixels_per_meter = (f64::from(dpi) / 0.0254).round() as u32;
+    let chunk = [
+        b"pHYs".as_slice(),
+        &pixels_per_meter.to_be_bytes(),
+        &pixels_per_meter.to_be_bytes(),
+        // The unit is the meter.
+        &[1],
+    ]
+    .concat();
+    let crc = crc32fast::hash(&chunk);
+    [
+        &png[..IHDR_END],
+        &9_u32.to_be_bytes(),
+        &chunk,
+        &crc.to_be_bytes(),
+        &png[IHDR_END..],
+    ]
+    .concat()
+}
+
+/// Converts the color to 16-bit grayscale.
+fn to_luma16(color: &Color) -> Luma<u16> {
+    let [r, g, b, _] = color.to_array();
+    let luma = 0.2126_f32.mul_add(r, 0.7152_f32.mul_add(g, 0.0722 * b));
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let luma = (luma.clamp(0.0, 1.0) * f32::from(u16::MAX)).round() as u16;
+    Luma([luma])
+}
+
+/// Renders the QR code into a SVG image.
+///
+/// If the background color is fully transparent, the light modules are not
+/// filled.
+#[must_use]
+pub fn to_svg(
+    code: &impl Modules,
+    margin: u32,
+    colors: &(Color, Color),
+    module_size: Option<u32>,
+) -> String {
+    to_svg_image(code, margin, colors, module_size).to_document(SvgSize::default(), None)
+}
+
+/// The size of the root element of a SVG image.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SvgSize {
+    /// The width and the height in pixels.
+    #[default]
+    Pixels,
+
+    /// The width of 100%, so that the image scales to its container.
+    Responsive,
+
+    /// The width of `percent` percent of the viewport per `module_size`
+    /// pixels.
+    Percentage { module_size: u32, percent: u32 },
+}
+
+/// A SVG image whose root element is not rendered yet.
+///
+/// The root element is rendered by [`SvgImage::to_document`], so that its
+/// attributes can be chosen after the content is drawn.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SvgImage {
+    /// The width in pixels.
+    pub width: u32,
+
+    /// The height in pixels.
+    pub height: u32,
+
+    /// Whether the root element has `shape-rendering="crispEdges"`.
+    pub crisp_edges: bool,
+
+    /// Whether the root element declares the `xlink` namespace.
+    pub xlink: bool,
+
+    /// The elements in the root element.
+    pub content: String,
+}
+
+impl SvgImage {
+    /// Returns the namespace declarations and the version of the root element.
+    const fn namespaces(&self) -> &'static str {
+        if self.xlink {
+            r#" xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1""#
+        } else {
+            r#" xmlns="http://www.w3.org/2000/svg" version="1.1""#
+        }
+    }
+
+    /// Returns the size, the `viewBox` and the `shape-rendering` attributes of
+    /// the root element.
+    fn dimensions(&self, size: SvgSize) -> String {
+        let (width, height) = (self.width, self.height);
+        let size = match size {
+            SvgSize::Pixels => format!(r#" width="{width}" height="{height}""#),
+            SvgSize::Responsive => String::from(r#" width="100%""#),
+            SvgSize::Percentage {
+                module_size,
+                percent,
+            } => {
+                let width = f64::from(width) * f64::from(percent) / f64::from(module_size);
+                format!(r#" width="{width}%""#)
+            }
+        };
+        let shape_rendering = if self.crisp_edges {
+            r#" shape-rendering="crispEdges""#
+        } else {
+            ""
+        };
+        format!(r#"{size} viewBox="0 0 {width} {height}"{shape_rendering}"#)
+    }
+
+    /// Returns the image as a `<svg>` element which is nested in another
+    /// image.
+    ///
+    /// `attribute` is placed before the other attributes, e.g. `x="8"`.
+    fn to_nested(&self, attribute: &str) -> String {
+        format!(
+            "<svg {attribute}{}{}>{}</svg>",
+            self.namespaces(),
+            self.dimensions(SvgSize::Pixels),
+            self.content
+        )
+    }
+
+    /// Renders the image with the root element into a SVG document.
+    ///
+    /// If `symbol` is [`Some`], the content is wrapped in a `<symbol>` element
+    /// with the ID, and the root element is hidden. The symbol can be
+    /// referenced with `<use href="#id"/>`.
+    #[must_use]
+    pub fn to_document(&self, size: SvgSize, symbol: Option<&str>) -> String {
+        let (namespaces, dimensions) = (self.namespaces(), self.dimensions(size));
+        let body = symbol.map_or_else(
+            || format!("<svg{namespaces}{dimensions}>{}</svg>", self.content),
+            |id| {
+                format!(
+                    r#"<svg{namespaces} style="display: none"><symbol id="{id}"{dimensions}>{}</symbol></svg>"#,
+                    self.content
+                )
+            },
+        );
+        format!("<?xml version=\"1.0\" standalone=\"yes\"?>{body}\n")
+    }
+}
+
+/// Returns the `<rect>` element which fills the background, or an empty
+/// string if the color is fully transparent.
+fn svg_background(width: u32, height: u32, color: &Color) -> String {
+    if color::is_transparent(color) {
+        String::new()
+    } else {
+        format!(
+            r#"<rect x="0" y="0" width="{width}" height="{height}" fill="{}"/>"#,
+            color.to_css_hex()
+        )
+    }
+}
+
+/// Renders the QR code into a SVG image whose root element is not rendered
+/// yet.
+///
+/// Each dark module is drawn as a square in a `<path>` element. If the
+/// background color is fully transparent, the background is not drawn.
+#[must_use]
+pub fn to_svg_image(
+    code: &impl Modules,
+    margin: u32,
+    colors: &(Color, Color),
+    module_size: Option<u32>,
+) -> SvgImage {
+    let module_size = module_size.unwrap_or(8);
+    let width = code.width();
+    let size = (u32::try_from(width).unwrap_or(u32::MAX) + 2 * margin) * module_size;
+    let to_pixels = |n: usize| (u32::try_from(n).unwrap_or(u32::MAX) + margin) * module_size;
+    let path = (0..width)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| code.module(x, y) == qrcode::Color::Dark)
+        .map(|(x, y)| {
+            let (left, top) = (to_pixels(x), to_pixels(y));
+            format!("M{left} {top}h{module_size}v{module_size}H{left}V{top}")
+        })
+        .collect::<Vec<_>>()
+        .concat();
+    let content = format!(
+        r#"{}<path fill="{}" d="{path}"/>"#,
+        svg_background(size, size, &colors.1),
+        colors.0.to_css_hex()
+    );
+    SvgImage {
+        width: size,
+        height: size,
+        crisp_edges: true,
+        xlink: false,
+        content,
+    }
+}
+
+/// Returns `true` if the module is in any of the finder patterns.
+fn is_finder_pattern(code: &impl Modules, x: usize, y: usize) -> bool {
+    let far = code.width() - 7;
+    match (x < 7, y < 7) {
+        (true, true) => true,
+        _ if code.is_micro() => false,
+        (true, false) => y >= far,
+        (false, true) => x >= far,
+        (false, false) => false,
+    }
+}
+
+/// Returns the coordinates of the dark modules of the finder patterns.
+fn finder_pattern_modules(code: &impl Modules) -> Vec<(usize, usize)> {
+    let width = code.width();
+    let origins: &[(usize, usize)] = if code.is_micro() {
+        &[(0, 0)]
+    } else {
+        &[(0, 0), (width - 7, 0), (0, width - 7)]
+    };
+    origins
+        .iter()
+        .flat_map(|&(x, y)| (x..x + 7).flat_map(move |x| (y..y + 7).map(move |y| (x, y))))
+        .filter(|&(x, y)| code.module(x, y) == qrcode::Color::Dark)
+        .collect()
+}
+
+/// Returns `true` if the center of the pixel at the offset in a module is
+/// inside the circle inscribed in the module.
+fn is_in_circle(dx: u32, dy: u32, module_size: u32) -> bool {
+    let radius = f64::from(module_size) / 2.0;
+    let (x, y) = (f64::from(dx) + 0.5 - radius, f64::from(dy) + 0.5 - radius);
+    x.hypot(y) <= radius
+}
+
+/// Redraws the dark modules of the image rendered by [`to_image`] in the
+/// shapes.
+///
+/// The modules of the finder patterns are drawn in `finder_shape`, and the
+/// others are drawn in `shape`. A circle is inscribed in its module, so
+/// adjacent circles touch but never overlap, and the pixels outside it are
+/// painted with the background color. The QR code is assumed to be
+/// horizontally centered at the top of the image.
+pub fn paint_module_shapes(
+    image: &mut RgbaImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    background: &Color,
+    (shape, finder_shape): (ModuleShape, ModuleShape),
+) {
+    let module_size = module_size.unwrap_or(8);
+    let size = (u32::try_from(code.width()).unwrap_or(u32::MAX) + 2 * margin) * module_size;
+    let offset = image.width().saturating_sub(size) / 2;
+    let background = Rgba::from(background.to_rgba8());
+    let width = code.width();
+    for (x, y) in (0..width).flat_map(|y| (0..width).map(move |x| (x, y))) {
+        let module_shape = if is_finder_pattern(code, x, y) {
+            finder_shape
+        } else {
+            shape
+        };
+        if code.module(x, y) == qrcode::Color::Light || module_shape == ModuleShape::Square {
+            continue;
+        }
+        let x = offset + (u32::try_from(x).unwrap_or(u32::MAX) + margin) * module_size;
+        let y = (u32::try_from(y).unwrap_or(u32::MAX) + margin) * module_size;
+        for (dx, dy) in (0..module_size).flat_map(|dx| (0..module_size).map(move |dy| (dx, dy))) {
+            if !is_in_circle(dx, dy, module_size) {
+                image.put_pixel(x + dx, y + dy, background);
+            }
+        }
+    }
+}
+
+/// Returns the SVG elements which draw the modules in the shape.
+fn to_svg_modules(
+    modules: impl IntoIterator<Item = (usize, usize)>,
+    margin: u32,
+    module_size: u32,
+    shape: ModuleShape,
+    fill: &str,
+) -> String {
+    let to_pixels = |n: usize| (u32::try_from(n).unwrap_or(u32::MAX) + margin) * module_size;
+    let origins = modules
+        .into_iter()
+        .map(|(x, y)| (to_pixels(x), to_pixels(y)));
+    match shape {
+        ModuleShape::Square => {
+            let path = origins
+                .map(|(x, y)| format!("M{x} {y}h{module_size}v{module_size}h-{module_size}z"))
+                .collect::<Vec<_>>()
+                .concat();
+            format!(r#"<path fill="{fill}" d="{path}"/>"#)
+        }
+        ModuleShape::Circle => {
+            let radius = f64::from(module_size) / 2.0;
+            let circles = origins
+                .map(|(x, y)| {
+                    let (cx, cy) = (f64::from(x) + radius, f64::from(y) + radius);
+                    format!(r#"<circle cx="{cx}" cy="{cy}" r="{radius}"/>"#)
+                })
+                .collect::<Vec<_>>()
+                .concat();
+            // Circles should be anti-aliased even if the root element has
+            // `shape-rendering="crispEdges"`.
+            format!(r#"<g fill="{fill}" shape-rendering="geometricPrecision">{circles}</g>"#)
+        }
+    }
+}
+
+/// Redraws the dark modules of the SVG image rendered by [`to_svg_image`] in
+/// the shapes.
+///
+/// This replaces the first `<path>` element, which draws the dark modules.
+/// The modules of the finder patterns are drawn in `finder_shape`, and the
+/// others are drawn in `shape`. Square modules are drawn as a `<path>`
+/// element, and circle modules are drawn as `<circle>` elements.
+pub fn paint_svg_module_shapes(
+    svg: &mut SvgImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    (shape, finder_shape): (ModuleShape, ModuleShape),
+) {
+    let content = &svg.content;
+    let Some(start) = content.find("<path") else {
+        return;
+    };
+    let Some(end) = content[start..].find("/>").map(|end| start + end + 2) else {
+        return;
+    };
+    let Some(fill) = content[start..end]
+        .split_once(r#"fill=""#)
+        .and_then(|(_, fill)| fill.split_once('"'))
+        .map(|(fill, _)| fill)
+    else {
+        return;
+    };
+    let module_size = module_size.unwrap_or(8);
+    let width = code.width();
+    let (squares, circles): (Vec<_>, Vec<_>) = (0..width)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .filter(|&(x, y)| code.module(x, y) == qrcode::Color::Dark)
+        .partition(|&(x, y)| {
+            let module_shape = if is_finder_pattern(code, x, y) {
+                finder_shape
+            } else {
+                shape
+            };
+            module_shape == ModuleShape::Square
+        });
+    let elements = [
+        (squares, ModuleShape::Square),
+        (circles, ModuleShape::Circle),
+    ]
+    .into_iter()
+    .filter(|(modules, _)| !modules.is_empty())
+    .map(|(modules, shape)| to_svg_modules(modules, margin, module_size, shape, fill))
+    .collect::<Vec<_>>()
+    .concat();
+    svg.content.replace_range(start..end, &elements);
+}
+
+/// Paints the finder patterns of the QR code in the image rendered by
+/// [`to_image`].
+///
+/// The QR code is assumed to be horizontally centered at the top of the image,
+/// so this also works with the image rendered by `to_image_with_title`.
+pub fn paint_finder_patterns(
+    image: &mut RgbaImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    color: &Color,
+) {
+    let module_size = module_size.unwrap_or(8);
+    let size = (u32::try_from(code.width()).unwrap_or(u32::MAX) + 2 * margin) * module_size;
+    let offset = image.width().saturating_sub(size) / 2;
+    let color = Rgba::from(color.to_rgba8());
+    for (x, y) in finder_pattern_modules(code) {
+        let x = offset + (u32::try_from(x).unwrap_or(u32::MAX) + margin) * module_size;
+        let y = (u32::try_from(y).unwrap_or(u32::MAX) + margin) * module_size;
+        for (dx, dy) in (0..module_size).flat_map(|dx| (0..module_size).map(move |dy| (dx, dy))) {
+            image.put_pixel(x + dx, y + dy, color);
+        }
+    }
+}
+
+/// Paints the finder patterns of the QR code in the SVG image rendered by
+/// [`to_svg_image`].
+///
+/// The finder patterns are drawn in the shape at the end of the content.
+pub fn paint_svg_finder_patterns(
+    svg: &mut SvgImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    color: &Color,
+    shape: ModuleShape,
+) {
+    let elements = to_svg_modules(
+        finder_pattern_modules(code),
+        margin,
+        module_size.unwrap_or(8),
+        shape,
+        &to_svg_fill(color),
+    );
+    svg.content.push_str(&elements);
+}
+
+/// Paints the quiet zone of the QR code in the image rendered by [`to_image`].
+///
+/// The quiet zone is the margin around the QR code. The QR code is assumed to
+/// be horizontally centered at the top of the image, so this also works with
+/// the image rendered by `to_image_with_title`.
+pub fn paint_quiet_zone(
+    image: &mut RgbaImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    color: &Color,
+) {
+    let module_size = module_size.unwrap_or(8);
+    let inner = u32::try_from(code.width()).unwrap_or(u32::MAX) * module_size;
+    let margin = margin * module_size;
+    let size = inner + 2 * margin;
+    let offset = image.width().saturating_sub(size) / 2;
+    let color = Rgba::from(color.to_rgba8());
+    let is_inner = |n: u32| (margin..margin + inner).contains(&n);
+    for (x, y) in (0..size).flat_map(|x| (0..size).map(move |y| (x, y))) {
+        if !(is_inner(x) && is_inner(y)) {
+            image.put_pixel(offset + x, y, color);
+        }
+    }
+}
+
+/// Paints the quiet zone of the QR code in the SVG image rendered by
+/// [`to_svg_image`].
+///
+/// The quiet zone is drawn as a frame at the end of the content. If `margin`
+/// is 0, nothing is drawn.
+pub fn paint_svg_quiet_zone(
+    svg: &mut SvgImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    color: &Color,
+) {
+    if margin == 0 {
+        return;
+    }
+    let module_size = module_size.unwrap_or(8);
+    let inner = u32::try_from(code.width()).unwrap_or(u32::MAX) * module_size;
+    let margin = margin * module_size;
+    let size = inner + 2 * margin;
+    // The inner square is drawn counterclockwise to cut it out of the frame.
+    let element = format!(
+        r#"<path fill="{}" d="M0 0h{size}v{size}h-{size}zM{margin} {margin}v{inner}h{inner}v-{inner}z"/>"#,
+        to_svg_fill(color)
+    );
+    svg.content.push_str(&element);
+}
+
+/// Function pattern or information region of a QR code.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Region {
+    /// Finder pattern and its separator.
+    Finder,
+
+    /// Timing pattern.
+    Timing,
+
+    /// Alignment pattern.
+    Alignment,
+
+    /// Format information.
+    Format,
+
+    /// Version information.
+    Version,
+}
+
+impl Region {
+    /// Returns the color for tinting the region in the debug overlay.
+    const fn tint(self) -> Rgba<u8> {
+        match self {
+            Self::Finder => Rgba([0xff, 0x00, 0x00, 0x80]),
+            Self::Timing => Rgba([0x00, 0xc0, 0x00, 0x80]),
+            Self::Alignment => Rgba([0x00, 0x00, 0xff, 0x80]),
+            Self::Format => Rgba([0xff, 0xa0, 0x00, 0x80]),
+            Self::Version => Rgba([0xc0, 0x00, 0xc0, 0x80]),
+        }
+    }
+}
+
+/// Returns the center coordinates of the alignment patterns in each
+/// direction.
+fn alignment_pattern_positions(width: usize) -> Vec<usize> {
+    let version = (width - 17) / 4;
+    if version < 2 {
+        return Vec::default();
+    }
+    let count = version / 7 + 2;
+    let step = if version == 32 {
+        26
+    } else {
+        (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2
+    };
+    let mut positions = (0..count - 1)
+        .map(|i| width - 7 - i * step)
+        .collect::<Vec<_>>();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// Returns the region to which the module at the coordinates belongs.
+///
+/// Returns [`None`] if the module is a data or error correction module.
+pub fn region(code: &impl Modules, x: usize, y: usize) -> Option<Region> {
+    let width = code.width();
+    if code.is_micro() {
+        return match (x, y) {
+            (0..=7, 0..=7) => Some(Region::Finder),
+            (0, _) | (_, 0) => Some(Region::Timing),
+            (1..=8, 8) | (8, 1..=8) => Some(Region::Format),
+            _ => None,
+        };
+    }
+    let is_near = |n: usize| n >= width - 8;
+    let is_finder = |x, y| match (x <= 7, y <= 7) {
+        (true, true) => true,
+        (true, false) => is_near(y),
+        (false, true) => is_near(x),
+        (false, false) => false,
+    };
+    if is_finder(x, y) {
+        return Some(Region::Finder);
+    }
+    if x == 6 || y == 6 {
+        return Some(Region::Timing);
+    }
+    if (x == 8 && (y <= 8 || is_near(y))) || (y == 8 && (x <= 8 || is_near(x))) {
+        return Some(Region::Format);
+    }
+    if width >= 45 && ((x <= 5 && is_near(y + 3)) || (y <= 5 && is_near(x + 3))) {
+        return Some(Region::Version);
+    }
+    let positions = alignment_pattern_positions(width);
+    let is_alignment = positions.iter().any(|&cy| {
+        positions
+            .iter()
+            .any(|&cx| !is_finder(cx, cy) && x.abs_diff(cx) <= 2 && y.abs_diff(cy) <= 2)
+    });
+    is_alignment.then_some(Region::Alignment)
+}
+
+/// Tints the function patterns and the information regions of the QR code in
+/// the image rendered by [`to_image`].
+///
+/// Each region is blended with a distinct color on top of the modules, so the
+/// resulting image is for learning the structure of the QR code and is not
+/// meant to be scanned.
+pub fn paint_debug_overlay(
+    image: &mut RgbaImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+) {
+    let module_size = module_size.unwrap_or(8);
+    let width = code.width();
+    let size = (u32::try_from(width).unwrap_or(u32::MAX) + 2 * margin) * module_size;
+    let offset = image.width().saturating_sub(size) / 2;
+    let modules = (0..width).flat_map(|y| (0..width).map(move |x| (x, y)));
+    for (x, y, region) in modules.filter_map(|(x, y)| region(code, x, y).map(|r| (x, y, r))) {
+        let x = offset + (u32::try_from(x).unwrap_or(u32::MAX) + margin) * module_size;
+        let y = (u32::try_from(y).unwrap_or(u32::MAX) + margin) * module_size;
+        for (dx, dy) in (0..module_size).flat_map(|dx| (0..module_size).map(move |dy| (dx, dy))) {
+            image.get_pixel_mut(x + dx, y + dy).blend(&region.tint());
+        }
+    }
+}
+
+/// The minimum width of a QR code whose SVG image is optimized by default.
+///
+/// This is the width of version 10. Smaller QR codes are rendered exactly as
+/// before, since merging their dark modules saves only a little.
+pub const OPTIMIZE_SVG_MIN_WIDTH: usize = 57;
+
+/// Merges the dark modules in the SVG image rendered by [`to_svg_image`] into
+/// horizontal runs.
+///
+/// This replaces the path data of the first `<path>` element, which draws one
+/// square per dark module, with one rectangle per run of adjacent dark modules
+/// in each row. The rendered image is not changed.
+pub fn optimize_svg(
+    svg: &mut SvgImage,
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+) {
+    let content = &svg.content;
+    let Some(start) = content
+        .find("<path")
+        .and_then(|start| content[start..].find(" d=\"").map(|d| start + d + 4))
+    else {
+        return;
+    };
+    let Some(end) = content[start..].find('"').map(|end| start + end) else {
+        return;
+    };
+    let module_size = module_size.unwrap_or(8);
+    let width = code.width();
+    let mut path = Vec::new();
+    for y in 0..width {
+        let mut x = 0;
+        while x < width {
+            if code.module(x, y) == qrcode::Color::Light {
+                x += 1;
+                continue;
+            }
+            let run = (x..width)
+                .take_while(|&x| code.module(x, y) == qrcode::Color::Dark)
+                .count();
+            let [left, top, length] = [x, y, run].map(|n| u32::try_from(n).unwrap_or(u32::MAX));
+            let left = (left + margin) * module_size;
+            let top = (top + margin) * module_size;
+            let length = length * module_size;
+            path.push(format!("M{left} {top}h{length}v{module_size}h-{length}z"));
+            x += run;
+        }
+    }
+    svg.content.replace_range(start..end, &path.concat());
+}
+
+/// Returns the length of `count` copies of `length` separated by `gap`, or
+/// [`None`] if it overflows.
+fn tiled_length(length: u32, count: u32, gap: u32) -> Option<u32> {
+    length.checked_add(gap)?.checked_mul(count)?.checked_sub(gap)
+}
+
+fn tile_buffer<P: Pixel>(
+    image: &ImageBuffer<P, Vec<P::Subpixel>>,
+    (columns, rows): (u32, u32),
+    gap: u32,
+    background: P,
+) -> Option<ImageBuffer<P, Vec<P::Subpixel>>> {
+    let (width, height) = image.dimensions();
+    let mut canvas = ImageBuffer::from_pixel(
+        tiled_length(width, columns, gap)?,
+        tiled_length(height, rows, gap)?,
+        background,
+    );
+    for (column, row) in (0..columns).flat_map(|column| (0..rows).map(move |row| (column, row))) {
+        imageops::replace(
+            &mut canvas,
+            image,
+            i64::from(column) * i64::from(width + gap),
+            i64::from(row) * i64::from(height + gap),
+        );
+    }
+    Some(canvas)
+}
+
+/// Tiles copies of the image in a grid.
+///
+/// `grid` is the number of columns and rows. The copies are separated by `gap`
+/// pixels, which are filled with the background color. The 16-bit grayscale
+/// image is tiled as is, and the other images are converted to RGBA.
+///
+/// Returns [`None`] if the width or the height of the tiled image overflows.
+#[must_use]
+pub fn tile_image(
+    image: &DynamicImage,
+    grid: (u32, u32),
+    gap: u32,
+    background: &Color,
+) -> Option<DynamicImage> {
+    match image {
+        DynamicImage::ImageLuma16(image) => {
+            tile_buffer(image, grid, gap, to_luma16(background)).map(Into::into)
+        }
+        image => tile_buffer(
+            &image.to_rgba8(),
+            grid,
+            gap,
+            Rgba::from(background.to_rgba8()),
+        )
+        .map(Into::into),
+    }
+}
+
+/// Tiles copies of the SVG image in a grid.
+///
+/// `grid` is the number of columns and rows. The SVG image is defined once,
+/// and each copy is emitted as a `<use>` element which refers to it. The
+/// copies are separated by `gap` pixels, which are filled with the background
+/// color.
+///
+/// Returns [`None`] if the width or the height of the tiled image overflows.
+#[must_use]
+pub fn tile_svg(
+    svg: &SvgImage,
+    (columns, rows): (u32, u32),
+    gap: u32,
+    background: &Color,
+) -> Option<SvgImage> {
+    let (width, height) = (
+        tiled_length(svg.width, columns, gap)?,
+        tiled_length(svg.height, rows, gap)?,
+    );
+    let uses = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            format!(
+                r##"<use xlink:href="#qrcode" x="{}" y="{}"/>"##,
+                column * (svg.width + gap),
+                row * (svg.height + gap)
+            )
+        })
+        .collect::<Vec<_>>()
+        .concat();
+    let content = format!(
+        "{}<defs>{}</defs>{uses}",
+        svg_background(width, height, background),
+        svg.to_nested(r#"id="qrcode""#)
+    );
+    Some(SvgImage {
+        width,
+        height,
+        crisp_edges: false,
+        xlink: true,
+        content,
+    })
+}
+
+/// Renders the QR code with the title beneath it into an image.
+///
+/// The title is drawn with a built-in bitmap font which supports only the
+/// printable ASCII characters, and its size scales with `module_size`. If
+/// `title` is empty, this is the same as [`to_image`].
+#[cfg(feature = "output-with-title")]
+#[must_use]
+pub fn to_image_with_title(
+    code: &impl Modules,
+    margin: u32,
+    colors: &(Color, Color),
+    module_size: Option<u32>,
+    title: &str,
+) -> RgbaImage {
+    use crate::font;
+
+    let image = to_image(code, margin, colors, module_size);
+    if title.is_empty() {
+        return image;
+    }
+    let (module_size, scale) = title_scale(module_size);
+    let text_width = font::text_width(title) * scale;
+    let width = image.width().max(text_width + 2 * module_size);
+    let height = image.height() + font::GLYPH_HEIGHT * scale + module_size;
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba::from(colors.1.to_rgba8()));
+    imageops::replace(
+        &mut canvas,
+        &image,
+        i64::from((width - image.width()) / 2),
+        0,
+    );
+    let foreground = Rgba::from(colors.0.to_rgba8());
+    let (x, y) = ((width - text_width) / 2, image.height());
+    for (i, c) in (0..).zip(title.chars()) {
+        let x = x + i * font::ADVANCE * scale;
+        for (column, bits) in (0..).zip(font::glyph(c)) {
+            for row in (0..font::GLYPH_HEIGHT).filter(|row| bits & (1 << row) != 0) {
+                for (dx, dy) in (0..scale).flat_map(|dx| (0..scale).map(move |dy| (dx, dy))) {
+                    canvas.put_pixel(x + column * scale + dx, y + row * scale + dy, foreground);
+                }
+            }
+        }
+    }
+    canvas
 }
 
-/// Renders the QR code into an image.
-pub fn to_svg(
-    code: &QrCode,
-    margin: u32,
+/// Adds the title beneath the SVG image rendered by [`to_svg_image`].
+///
+/// The SVG image is nested in a new image, and the title is emitted as a
+/// `<text>` element. If `title` is empty, the SVG image is returned as is.
+#[cfg(feature = "output-with-title")]
+#[must_use]
+pub fn to_svg_with_title(
+    svg: &SvgImage,
     colors: &(Color, Color),
     module_size: Option<u32>,
-) -> String {
-    let c = code.to_colors();
-    let mut renderer = &mut Renderer::<svg::Color<'_>>::new(&c, code.width(), margin);
-    let (foreground, background) = (colors.0.to_hex_string(), colors.1.to_hex_string());
-    renderer = renderer
-        .dark_color(svg::Color(&foreground))
-        .light_color(svg::Color(&background));
-    if let Some(size) = module_size {
-        renderer = renderer.module_dimensions(size, size);
+    title: &str,
+) -> SvgImage {
+    use crate::font;
+
+    if title.is_empty() {
+        return svg.clone();
+    }
+    let (module_size, scale) = title_scale(module_size);
+    let size = svg.width;
+    let text_width = font::text_width(title) * scale;
+    let width = size.max(text_width + 2 * module_size);
+    let height = size + font::GLYPH_HEIGHT * scale + module_size;
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let content = format!(
+        concat!(
+            "{background}{nested}",
+            r#"<text x="{center}" y="{baseline}" fill="{foreground}" font-family="monospace" font-size="{font_size}" text-anchor="middle">{title}</text>"#,
+        ),
+        background = svg_background(width, height, &colors.1),
+        nested = svg.to_nested(&format!(r#"x="{}""#, (width - size) / 2)),
+        center = width / 2,
+        baseline = size + font::GLYPH_HEIGHT * scale,
+        foreground = colors.0.to_css_hex(),
+        // The advance of monospace fonts is usually 0.6 em.
+        font_size = font::ADVANCE * scale * 5 / 3,
+        title = title,
+    );
+    SvgImage {
+        width,
+        height,
+        crisp_edges: false,
+        xlink: false,
+        content,
+    }
+}
+
+/// Returns the module size and the scale of the title font.
+#[cfg(feature = "output-with-title")]
+fn title_scale(module_size: Option<u32>) -> (u32, u32) {
+    let module_size = module_size.unwrap_or(8);
+    (module_size, (module_size / 2).max(1))
+}
+
+/// Returns the value of the `fill` attribute for the background color.
+///
+/// If the color is fully transparent, this returns `none`.
+fn to_svg_fill(color: &Color) -> String {
+    if color::is_transparent(color) {
+        String::from("none")
+    } else {
+        color.to_css_hex()
     }
-    renderer.build() + "\n"
 }
 
 /// Renders the QR code into a PIC image.
-pub fn to_pic(code: &QrCode, margin: u32, module_size: Option<u32>) -> String {
+#[must_use]
+pub fn to_pic(code: &impl Modules, margin: u32, module_size: Option<u32>) -> String {
     let c = code.to_colors();
     let mut renderer = &mut Renderer::<pic::Color>::new(&c, code.width(), margin);
     if let Some(size) = module_size {
@@ -101,8 +1989,9 @@ pub fn to_pic(code: &QrCode, margin: u32, module_size: Option<u32>) -> String {
 
 /// Renders the QR code into the terminal using 4-bit ANSI escape sequences.
 #[cfg(feature = "output-as-ansi")]
+#[must_use]
 pub fn to_ansi(
-    code: &QrCode,
+    code: &impl Modules,
     margin: u32,
     colors: &(Color, Color),
     module_size: Option<u32>,
@@ -135,8 +2024,9 @@ pub fn to_ansi(
 
 /// Renders the QR code into the terminal using 8-bit ANSI escape sequences.
 #[cfg(feature = "output-as-ansi")]
+#[must_use]
 pub fn to_ansi_256(
-    code: &QrCode,
+    code: &impl Modules,
     margin: u32,
     colors: &(Color, Color),
     module_size: Option<u32>,
@@ -162,8 +2052,9 @@ pub fn to_ansi_256(
 
 /// Renders the QR code into the terminal using 24-bit ANSI escape sequences.
 #[cfg(feature = "output-as-ansi")]
+#[must_use]
 pub fn to_ansi_true_color(
-    code: &QrCode,
+    code: &impl Modules,
     margin: u32,
     colors: &(Color, Color),
     module_size: Option<u32>,
@@ -190,7 +2081,13 @@ pub fn to_ansi_true_color(
 }
 
 /// Renders the QR code into the terminal as ASCII string.
-pub fn to_ascii(code: &QrCode, margin: u32, module_size: Option<u32>, invert: bool) -> String {
+#[must_use]
+pub fn to_ascii(
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    invert: bool,
+) -> String {
     let c = code.to_colors();
     let mut renderer = &mut Renderer::<&str>::new(&c, code.width(), margin);
     renderer = if invert {
@@ -205,7 +2102,13 @@ pub fn to_ascii(code: &QrCode, margin: u32, module_size: Option<u32>, invert: bo
 }
 
 /// Renders the QR code into the terminal as UTF-8 string.
-pub fn to_unicode(code: &QrCode, margin: u32, module_size: Option<u32>, invert: bool) -> String {
+#[must_use]
+pub fn to_unicode(
+    code: &impl Modules,
+    margin: u32,
+    module_size: Option<u32>,
+    invert: bool,
+) -> String {
     let c = code.to_colors();
     let mut renderer = &mut Renderer::<unicode::Dense1x2>::new(&c, code.width(), margin);
     if !invert {
@@ -232,14 +2135,111 @@ impl Extractor for QrCode {
             EcLevel::Q => Ecc::Q,
             EcLevel::H => Ecc::H,
         };
-        Metadata::new(symbol_version, error_correction_level)
+        Metadata::new(
+            symbol_version,
+            error_correction_level,
+            mask_pattern(self),
+            self.width(),
+        )
     }
 }
 
+/// Reads the mask pattern from the format information of the QR code.
+fn mask_pattern(code: &QrCode) -> u8 {
+    use qrcode::Color;
+
+    // The format information consists of 5 data bits followed by 10 error
+    // correction bits, and the mask pattern is the lower bits of the data bits.
+    let (coords, format_mask, bits): (&[_], _, _) = match code.version() {
+        Version::Normal(_) => (
+            &[
+                (0, 8),
+                (1, 8),
+                (2, 8),
+                (3, 8),
+                (4, 8),
+                (5, 8),
+                (7, 8),
+                (8, 8),
+                (8, 7),
+                (8, 5),
+                (8, 4),
+                (8, 3),
+                (8, 2),
+                (8, 1),
+                (8, 0),
+            ],
+            0x5412,
+            0b111,
+        ),
+        Version::Micro(_) => (
+            &[
+                (1, 8),
+                (2, 8),
+                (3, 8),
+                (4, 8),
+                (5, 8),
+                (6, 8),
+                (7, 8),
+                (8, 8),
+                (8, 7),
+                (8, 6),
+                (8, 5),
+                (8, 4),
+                (8, 3),
+                (8, 2),
+                (8, 1),
+            ],
+            0x4445,
+            0b11,
+        ),
+    };
+    let format_info = coords.iter().fold(0_u16, |acc, &(x, y)| {
+        (acc << 1) | u16::from(code[(x, y)] == Color::Dark)
+    });
+    u8::try_from(((format_info ^ format_mask) >> 10) & bits).expect("invalid mask pattern")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn default_ecc() {
+        assert_eq!(Ecc::default(), Ecc::M);
+    }
+
+    #[test]
+    fn from_ecc_to_ec_level() {
+        assert_eq!(EcLevel::from(Ecc::L), EcLevel::L);
+        assert_eq!(EcLevel::from(Ecc::M), EcLevel::M);
+        assert_eq!(EcLevel::from(Ecc::Q), EcLevel::Q);
+        assert_eq!(EcLevel::from(Ecc::H), EcLevel::H);
+    }
+
+    #[test]
+    fn default_variant() {
+        assert_eq!(Variant::default(), Variant::Normal);
+    }
+
+    #[test]
+    fn default_encode_options() {
+        let options = EncodeOptions::default();
+        assert_eq!(options.error_correction_level, Ecc::M);
+        assert!(options.symbol_version.is_none());
+        assert_eq!(options.variant, Variant::Normal);
+        assert!(options.mode.is_none());
+        assert!(options.margin.is_none());
+        assert!(options.module_size.is_none());
+        assert_eq!(
+            options.colors,
+            (
+                Color::from_rgba8(u8::MIN, u8::MIN, u8::MIN, u8::MAX),
+                Color::from_rgba8(u8::MAX, u8::MAX, u8::MAX, u8::MAX)
+            )
+        );
+    }
+
     #[test]
     fn validate_qr_code_version() {
         // Valid normal QR code version.
@@ -263,6 +2263,703 @@ mod tests {
         // Invalid Micro QR code version.
         assert!(set_version(0, &Variant::Micro).is_err());
         assert!(set_version(5, &Variant::Micro).is_err());
+
+        // The version cannot be set without the type of QR code.
+        assert!(set_version(1, &Variant::Auto).is_err());
+    }
+
+    #[test]
+    fn get_capacity() {
+        assert_eq!(
+            capacity(Version::Normal(1), &Mode::Numeric, EcLevel::L).unwrap(),
+            41
+        );
+        assert_eq!(
+            capacity(Version::Normal(1), &Mode::Alphanumeric, EcLevel::H).unwrap(),
+            10
+        );
+        assert_eq!(
+            capacity(Version::Normal(10), &Mode::Byte, EcLevel::M).unwrap(),
+            213
+        );
+        assert_eq!(
+            capacity(Version::Normal(40), &Mode::Byte, EcLevel::L).unwrap(),
+            2953
+        );
+        assert_eq!(
+            capacity(Version::Normal(40), &Mode::Numeric, EcLevel::L).unwrap(),
+            7089
+        );
+        assert_eq!(
+            capacity(Version::Normal(40), &Mode::Kanji, EcLevel::L).unwrap(),
+            1817
+        );
+        assert_eq!(
+            capacity(Version::Micro(1), &Mode::Numeric, EcLevel::L).unwrap(),
+            5
+        );
+        assert_eq!(
+            capacity(Version::Micro(4), &Mode::Byte, EcLevel::Q).unwrap(),
+            9
+        );
+        assert!(capacity(Version::Micro(1), &Mode::Byte, EcLevel::L).is_err());
+        assert!(capacity(Version::Micro(4), &Mode::Byte, EcLevel::H).is_err());
+    }
+
+    #[test]
+    fn construct_qr_code_with_max_level() {
+        let code =
+            to_qr_code_with_max_level(b"QR code", Version::Normal(1), None, EcLevel::L).unwrap();
+        assert_eq!(code.error_correction_level(), EcLevel::H);
+        let code =
+            to_qr_code_with_max_level(b"QR code!", Version::Normal(1), None, EcLevel::L).unwrap();
+        assert_eq!(code.error_correction_level(), EcLevel::Q);
+        let code = to_qr_code_with_max_level(
+            b"QR code QR code",
+            Version::Normal(1),
+            Some(&Mode::Byte),
+            EcLevel::L,
+        )
+        .unwrap();
+        assert_eq!(code.error_correction_level(), EcLevel::L);
+        let code =
+            to_qr_code_with_max_level(b"12345", Version::Micro(1), None, EcLevel::L).unwrap();
+        assert_eq!(code.error_correction_level(), EcLevel::L);
+        assert_eq!(
+            to_qr_code_with_max_level(b"QR code QR code QR", Version::Normal(1), None, EcLevel::L)
+                .err(),
+            Some(QrError::DataTooLong)
+        );
+        let code =
+            to_qr_code_with_max_level(b"QR code!", Version::Normal(1), None, EcLevel::Q).unwrap();
+        assert_eq!(code.error_correction_level(), EcLevel::Q);
+        assert_eq!(
+            to_qr_code_with_max_level(b"QR code!", Version::Normal(1), None, EcLevel::H).err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    fn construct_qr_code_with_min_version() {
+        let code =
+            to_qr_code_with_min_version(b"QR code", &Variant::Normal, None, EcLevel::M).unwrap();
+        assert_eq!(code.version(), Version::Normal(1));
+        let code = to_qr_code_with_min_version(
+            b"QR code!",
+            &Variant::Normal,
+            Some(&Mode::Byte),
+            EcLevel::H,
+        )
+        .unwrap();
+        assert_eq!(code.version(), Version::Normal(2));
+        let code =
+            to_qr_code_with_min_version(b"QR code", &Variant::Micro, Some(&Mode::Byte), EcLevel::L)
+                .unwrap();
+        assert_eq!(code.version(), Version::Micro(3));
+
+        assert_eq!(
+            to_qr_code_with_min_version([0; 3000], &Variant::Normal, None, EcLevel::L).err(),
+            Some(QrError::DataTooLong)
+        );
+        assert_eq!(
+            to_qr_code_with_min_version(b"QR code", &Variant::Micro, None, EcLevel::H).err(),
+            Some(QrError::InvalidVersion)
+        );
+    }
+
+    #[test]
+    fn compute_min_version() {
+        assert_eq!(
+            min_version(b"QR code", &Variant::Normal, None, EcLevel::M),
+            Ok((Version::Normal(1), (9, 16)))
+        );
+        assert_eq!(
+            min_version(b"QR code!", &Variant::Normal, Some(&Mode::Byte), EcLevel::H),
+            Ok((Version::Normal(2), (10, 16)))
+        );
+        assert_eq!(
+            min_version(b"QR code", &Variant::Auto, Some(&Mode::Byte), EcLevel::L),
+            Ok((Version::Micro(3), (8, 11)))
+        );
+        assert_eq!(
+            min_version([0; 3000], &Variant::Normal, None, EcLevel::L),
+            Err(QrError::DataTooLong)
+        );
+        assert_eq!(
+            min_version(b"QR code", &Variant::Micro, None, EcLevel::H),
+            Err(QrError::InvalidVersion)
+        );
+    }
+
+    #[test]
+    fn construct_qr_code_in_versions() {
+        let code =
+            to_qr_code_in_versions(b"QR code", 5..=20, &Variant::Normal, None, EcLevel::M).unwrap();
+        assert_eq!(code.version(), Version::Normal(5));
+        let code = to_qr_code_in_versions(
+            b"QR code!",
+            1..=40,
+            &Variant::Normal,
+            Some(&Mode::Byte),
+            EcLevel::H,
+        )
+        .unwrap();
+        assert_eq!(code.version(), Version::Normal(2));
+        let code = to_qr_code_in_versions(
+            b"QR code",
+            1..=40,
+            &Variant::Micro,
+            Some(&Mode::Byte),
+            EcLevel::L,
+        )
+        .unwrap();
+        assert_eq!(code.version(), Version::Micro(3));
+
+        assert_eq!(
+            to_qr_code_in_versions([0; 100], 1..=3, &Variant::Normal, None, EcLevel::L).err(),
+            Some(QrError::DataTooLong)
+        );
+        assert_eq!(
+            to_qr_code_in_versions(b"QR code", 5..=40, &Variant::Micro, None, EcLevel::L).err(),
+            Some(QrError::InvalidVersion)
+        );
+    }
+
+    #[test]
+    fn construct_qr_code_with_auto_variant() {
+        let code = to_qr_code_with_min_version(b"12345", &Variant::Auto, None, EcLevel::L).unwrap();
+        assert_eq!(code.version(), Version::Micro(1));
+        let code =
+            to_qr_code_with_min_version(b"QR code", &Variant::Auto, None, EcLevel::M).unwrap();
+        assert_eq!(code.version(), Version::Micro(3));
+        let code =
+            to_qr_code_with_min_version(b"QR code", &Variant::Auto, None, EcLevel::H).unwrap();
+        assert_eq!(code.version(), Version::Normal(1));
+        let code = to_qr_code_with_min_version([0; 100], &Variant::Auto, None, EcLevel::L).unwrap();
+        assert_eq!(code.version(), Version::Normal(5));
+        let code =
+            to_qr_code_in_versions(b"12345", 2..=40, &Variant::Auto, None, EcLevel::L).unwrap();
+        assert_eq!(code.version(), Version::Micro(2));
+        let code =
+            to_qr_code_in_versions(b"12345", 5..=40, &Variant::Auto, None, EcLevel::L).unwrap();
+        assert_eq!(code.version(), Version::Normal(5));
+
+        assert_eq!(
+            to_qr_code_with_min_version([0; 3000], &Variant::Auto, None, EcLevel::L).err(),
+            Some(QrError::DataTooLong)
+        );
+    }
+
+    #[test]
+    fn get_codewords() {
+        let code = to_qr_code(b"QR code", None, None, EcLevel::M).unwrap();
+        assert_eq!(codewords(b"QR code", &code, None).unwrap(), (9, 16));
+        let code = to_qr_code(
+            b"0123456789",
+            Some(Version::Normal(10)),
+            Some(&Mode::Numeric),
+            EcLevel::H,
+        )
+        .unwrap();
+        assert_eq!(
+            codewords(b"0123456789", &code, Some(&Mode::Numeric)).unwrap(),
+            (7, 122)
+        );
+        let code = to_qr_code(b"QR code", Some(Version::Micro(3)), None, EcLevel::M).unwrap();
+        assert_eq!(codewords(b"QR code", &code, None).unwrap(), (8, 9));
+    }
+
+    #[test]
+    fn construct_matrices_with_all_masks() {
+        let code = to_qr_code(b"QR code", None, None, EcLevel::M).unwrap();
+        let matrices = to_matrices_with_all_masks(b"QR code", &code, None).unwrap();
+        assert_eq!(matrices.len(), 8);
+        assert_eq!(
+            matrices[usize::from(code.metadata().mask_pattern())],
+            Matrix::from(&code)
+        );
+        assert!(matrices.iter().all(|m| m.width() == 21));
+        let code = to_qr_code(b"QR code", Some(Version::Micro(3)), None, EcLevel::M).unwrap();
+        assert_eq!(
+            to_matrices_with_all_masks(b"QR code", &code, None).unwrap_err(),
+            QrError::InvalidVersion
+        );
+    }
+
+    #[test]
+    fn compute_penalty_score() {
+        let dark = Matrix::new(21, vec![qrcode::Color::Dark; 21 * 21]).unwrap();
+        assert_eq!(penalty_score(&dark), 798 + 1200 + 100);
+        let code = to_qr_code(b"QR code", None, None, EcLevel::M).unwrap();
+        assert_eq!(penalty_score(&code), penalty_score(&Matrix::from(&code)));
+    }
+
+    #[test]
+    fn find_invalid_kanji_character() {
+        assert_eq!(find_invalid_kanji(b"\x93\x5f\xe4\xaa"), None);
+        assert_eq!(
+            find_invalid_kanji([0x81, 0x40, 0x9f, 0xfc, 0xeb, 0xbf]),
+            None
+        );
+        assert_eq!(find_invalid_kanji(b""), None);
+        assert_eq!(find_invalid_kanji(b"QR"), Some(0));
+        assert_eq!(find_invalid_kanji(b"\x93\x5fQR"), Some(2));
+        assert_eq!(find_invalid_kanji([0x93, 0x5f, 0x93]), Some(2));
+        assert_eq!(find_invalid_kanji([0x93, 0x7f]), Some(0));
+        assert_eq!(find_invalid_kanji([0xeb, 0xc0]), Some(0));
+        assert_eq!(find_invalid_kanji([0xa0, 0x40]), Some(0));
+    }
+
+    #[test]
+    fn encode_invalid_kanji_data() {
+        let mut bits = Bits::new(Version::Normal(1));
+        assert_eq!(
+            push_data_for_selected_mode(&mut bits, b"QR", &Mode::Kanji),
+            Err(QrError::InvalidCharacter)
+        );
+    }
+
+    #[test]
+    fn decode_hex() {
+        assert_eq!(from_hex("5152 20636f6465\n").unwrap(), b"QR code");
+        assert_eq!(from_hex("00FFff").unwrap(), [0x00, 0xff, 0xff]);
+        assert_eq!(from_hex("").unwrap(), b"");
+        assert!(from_hex("515").is_none());
+        assert!(from_hex("5g").is_none());
+    }
+
+    #[test]
+    fn decode_base64() {
+        assert_eq!(from_base64("UVIgY29kZQ==").unwrap(), b"QR code");
+        assert_eq!(from_base64("UVIg\nY29kZQ\n").unwrap(), b"QR code");
+        assert_eq!(from_base64("AP//").unwrap(), [0x00, 0xff, 0xff]);
+        assert!(from_base64("UVIgY29kZQ===").is_err());
+        assert!(from_base64("U*==").is_err());
+    }
+
+    #[test]
+    fn convert_to_shift_jis() {
+        assert_eq!(
+            to_shift_jis("日本語").unwrap(),
+            [0x93, 0xfa, 0x96, 0x7b, 0x8c, 0xea]
+        );
+        assert_eq!(to_shift_jis("QR").unwrap(), b"QR");
+        assert_eq!(
+            to_shift_jis([0xff]).unwrap_err(),
+            QrError::UnsupportedCharacterSet
+        );
+        assert_eq!(
+            to_shift_jis("😀").unwrap_err(),
+            QrError::UnsupportedCharacterSet
+        );
+    }
+
+    #[test]
+    fn render_svg_document() {
+        let svg = SvgImage {
+            width: 16,
+            height: 8,
+            crisp_edges: true,
+            xlink: false,
+            content: String::from(r#"<path d="M0 0h8"/>"#),
+        };
+        assert_eq!(
+            svg.to_document(SvgSize::Pixels, None),
+            concat!(
+                r#"<?xml version="1.0" standalone="yes"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="16" height="8" viewBox="0 0 16 8" shape-rendering="crispEdges">"#,
+                r#"<path d="M0 0h8"/></svg>"#,
+                "\n"
+            )
+        );
+        assert!(svg
+            .to_document(SvgSize::Responsive, None)
+            .contains(r#" version="1.1" width="100%" viewBox="0 0 16 8" "#));
+        let percentage = SvgSize::Percentage {
+            module_size: 8,
+            percent: 5,
+        };
+        assert!(svg
+            .to_document(percentage, None)
+            .contains(r#" version="1.1" width="10%" viewBox="0 0 16 8" "#));
+        let svg = SvgImage {
+            crisp_edges: false,
+            xlink: true,
+            ..svg
+        };
+        assert_eq!(
+            svg.to_document(SvgSize::Pixels, Some("qr")),
+            concat!(
+                r#"<?xml version="1.0" standalone="yes"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" style="display: none">"#,
+                r#"<symbol id="qr" width="16" height="8" viewBox="0 0 16 8"><path d="M0 0h8"/></symbol></svg>"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn render_svg_without_background() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+        let svg = to_svg_image(&code, 4, &colors, None);
+        assert!(svg
+            .content
+            .starts_with(r##"<rect x="0" y="0" width="232" height="232" fill="#ffffff"/><path "##));
+        let colors = (colors.0, Color::new(1.0, 1.0, 1.0, 0.0));
+        let svg = to_svg_image(&code, 4, &colors, None);
+        assert!(svg.content.starts_with(r##"<path fill="#000000" d="M32 32h8v8H32V32M"##));
+        let svg = tile_svg(&svg, (2, 1), 0, &colors.1).unwrap();
+        assert!(svg.content.starts_with(r#"<defs><svg id="qrcode" "#));
+    }
+
+    #[test]
+    fn render_hard_edged_modules() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+        for size in 1..=16 {
+            let image = to_image(&code, 4, &colors, Some(size));
+            assert!(has_hard_edges(&image, Some(size)));
+            let image = to_luma16_image(&code, 4, &colors, Some(size));
+            assert!(has_hard_edges(&image, Some(size)));
+        }
+        let mut image = to_image(&code, 4, &colors, None);
+        assert!(has_hard_edges(&image, None));
+        image.put_pixel(33, 32, Rgba([127, 127, 127, 255]));
+        assert!(!has_hard_edges(&image, None));
+        assert!(!has_hard_edges(&RgbaImage::new(12, 12), None));
+    }
+
+    #[test]
+    fn render_arrays() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let c = to_c_array(&code, 0, None);
+        assert!(c.starts_with(concat!(
+            "#include <stdint.h>\n\n",
+            "#define QR_WIDTH 21\n\n",
+            "const uint8_t qr[63] = {\n",
+            "    0xfe, 0x"
+        )));
+        assert!(c.ends_with(",\n};\n"));
+        // 63 bytes are split into 6 lines.
+        assert_eq!(
+            c.lines().filter(|line| line.starts_with("    0x")).count(),
+            6
+        );
+        let rust = to_rust_array(&code, 0, None);
+        assert!(rust.starts_with(concat!(
+            "pub const QR_WIDTH: usize = 21;\n\n",
+            "pub const QR: [u8; 63] = [\n",
+            "    0xfe, 0x"
+        )));
+        assert!(rust.ends_with(",\n];\n"));
+        assert_eq!(
+            c.lines()
+                .filter(|line| line.starts_with("    "))
+                .collect::<Vec<_>>(),
+            rust.lines()
+                .filter(|line| line.starts_with("    "))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn render_pbm() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let pbm = to_pbm(&code, 0, Some(1));
+        assert!(pbm.starts_with(b"P4\n21 21\n"));
+        // Each row of 21 pixels is padded to 3 bytes.
+        assert_eq!(pbm.len(), b"P4\n21 21\n".len() + 21 * 3);
+        let row = &pbm[b"P4\n21 21\n".len()..][..3];
+        assert_eq!((row[0], row[1] & 0x03, row[2]), (0xfe, 0x03, 0xf8));
+        assert!(to_pbm(&code, 4, None).starts_with(b"P4\n232 232\n"));
+    }
+
+    #[test]
+    fn fit_module_size_to_physical_size() {
+        assert_eq!(fit_module_size(25.4, 300, 29), NonZeroU32::new(10));
+        assert_eq!(fit_module_size(25.0, 300, 29), NonZeroU32::new(10));
+        assert_eq!(fit_module_size(1.0, 72, 29), None);
+    }
+
+    #[test]
+    fn pad_image_to_size() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 0.0, 0.0, 1.0),
+        );
+        assert_eq!(to_pixels(25.0, 300), 295);
+        let image = DynamicImage::from(to_image(&code, 4, &colors, Some(10)));
+        let padded = pad_image(image.clone(), 295, &colors.1, Alignment::Center).into_rgba8();
+        assert_eq!(padded.dimensions(), (295, 295));
+        assert_eq!(padded.get_pixel(0, 0), &Rgba([u8::MAX, 0, 0, u8::MAX]));
+        assert_eq!(padded.get_pixel(42, 42), &Rgba([0, 0, 0, u8::MAX]));
+        let padded = pad_image(image.clone(), 295, &colors.1, Alignment::TopLeft).into_rgba8();
+        assert_eq!(padded.get_pixel(40, 40), &Rgba([0, 0, 0, u8::MAX]));
+        assert_eq!(
+            pad_image(image.clone(), 200, &colors.1, Alignment::Center),
+            image
+        );
+        let image = DynamicImage::from(to_luma16_image(&code, 4, &colors, Some(10)));
+        assert!(matches!(
+            pad_image(image, 295, &colors.1, Alignment::Center),
+            DynamicImage::ImageLuma16(image) if image.dimensions() == (295, 295)
+        ));
+    }
+
+    #[test]
+    fn set_png_resolution() {
+        let image = GrayImage::new(1, 1);
+        let png = to_bilevel_png(&image, png::Compression::Default, None).unwrap();
+        let png = set_png_dpi(&png, 300);
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        let dims = reader.info().pixel_dims.unwrap();
+        assert_eq!((dims.xppu, dims.yppu), (11811, 11811));
+        assert_eq!(dims.unit, png::Unit::Meter);
+        assert_eq!(set_png_dpi(b"GIF89a", 300), b"GIF89a");
+    }
+
+    #[test]
+    fn encode_bilevel_png() {
+        let image =
+            GrayImage::from_fn(10, 2, |x, y| Luma([if (x + y) % 3 == 0 { 0 } else { 255 }]));
+        let png = to_bilevel_png(&image, png::Compression::Default, None).unwrap();
+        assert_eq!(png[24..26], [1, 0]);
+        assert_eq!(image::load_from_memory(&png).unwrap().to_luma8(), image);
+    }
+
+    #[test]
+    fn tile_svg_with_use_elements() {
+        let background = Color::new(1.0, 1.0, 1.0, 1.0);
+        let svg = SvgImage {
+            width: 10,
+            height: 20,
+            crisp_edges: true,
+            xlink: false,
+            content: String::from("<rect/>"),
+        };
+        assert_eq!(
+            tile_svg(&svg, (2, 1), 5, &background)
+                .unwrap()
+                .to_document(SvgSize::Pixels, None),
+            concat!(
+                r#"<?xml version="1.0" standalone="yes"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" width="25" height="20" viewBox="0 0 25 20">"#,
+                r##"<rect x="0" y="0" width="25" height="20" fill="#ffffff"/>"##,
+                r#"<defs><svg id="qrcode" xmlns="http://www.w3.org/2000/svg" version="1.1" width="10" height="20" viewBox="0 0 10 20" shape-rendering="crispEdges"><rect/></svg></defs>"#,
+                r##"<use xlink:href="#qrcode" x="0" y="0"/><use xlink:href="#qrcode" x="15" y="0"/>"##,
+                "</svg>\n"
+            )
+        );
+        let svg = SvgImage {
+            width: u32::MAX,
+            ..svg
+        };
+        assert!(tile_svg(&svg, (2, 1), 0, &background).is_none());
+    }
+
+    #[test]
+    fn optimize_svg_path() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+        let svg = to_svg_image(&code, 4, &colors, None);
+        let mut optimized = svg.clone();
+        optimize_svg(&mut optimized, &code, 4, None);
+        let (svg, optimized) = (svg.content, optimized.content);
+        assert!(optimized.len() < svg.len());
+        assert!(optimized.starts_with(&svg[..svg.find(" d=\"").unwrap()]));
+        assert!(optimized.contains(r#"d="M32 32h56v8h-56zM"#));
+        assert!(optimized.ends_with(&svg[svg.rfind('"').unwrap()..]));
+    }
+
+    #[test]
+    fn optimize_large_svg() {
+        let code = QrCode::with_version(b"QR code", Version::Normal(40), EcLevel::L).unwrap();
+        assert!(code.width() >= OPTIMIZE_SVG_MIN_WIDTH);
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+        let svg = to_svg_image(&code, 4, &colors, None);
+        let mut optimized = svg.clone();
+        optimize_svg(&mut optimized, &code, 4, None);
+        assert!(optimized.content.len() < svg.content.len() / 2);
+    }
+
+    #[test]
+    fn draw_svg_modules_as_circles() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+        let svg = to_svg_image(&code, 4, &colors, None);
+        let shapes = (ModuleShape::Circle, ModuleShape::Square);
+        let mut circles = svg.clone();
+        paint_svg_module_shapes(&mut circles, &code, 4, None, shapes);
+        let (content, circles) = (&svg.content, circles.content);
+        assert!(circles.starts_with(&content[..content.find("<path").unwrap()]));
+        assert!(circles.contains(r##"<path fill="#000000" d="M32 32h8v8h-8zM"##));
+        assert!(circles.contains(concat!(
+            r##"<g fill="#000000" shape-rendering="geometricPrecision">"##,
+            r#"<circle cx="100" cy="36" r="4"/>"#
+        )));
+        assert!(circles.ends_with("</g>"));
+        let shapes = (ModuleShape::Circle, ModuleShape::Circle);
+        let mut circles = svg;
+        paint_svg_module_shapes(&mut circles, &code, 4, None, shapes);
+        assert!(!circles.content.contains("<path"));
+        assert!(circles.content.contains(r#"<circle cx="36" cy="36" r="4"/>"#));
+    }
+
+    #[test]
+    fn paint_modules_as_circles() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+        let mut image = to_image(&code, 4, &colors, None);
+        let shapes = (ModuleShape::Circle, ModuleShape::Square);
+        paint_module_shapes(&mut image, &code, 4, None, &colors.1, shapes);
+        let (black, white) = (Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255]));
+        // The corners of the finder pattern are kept square.
+        assert_eq!(*image.get_pixel(32, 32), black);
+        // The first dark module outside the finder patterns is at (8, 0).
+        assert_eq!(*image.get_pixel(96, 32), white);
+        assert_eq!(*image.get_pixel(100, 36), black);
+        assert_eq!(*image.get_pixel(96, 36), black);
+        let shapes = (ModuleShape::Square, ModuleShape::Circle);
+        let mut image = to_image(&code, 4, &colors, None);
+        paint_module_shapes(&mut image, &code, 4, None, &colors.1, shapes);
+        assert_eq!(*image.get_pixel(32, 32), white);
+        assert_eq!(*image.get_pixel(36, 36), black);
+    }
+
+    #[test]
+    fn encode_in_charset() {
+        assert_eq!(Charset::Ascii.encode("QR code").unwrap(), b"QR code");
+        assert_eq!(
+            Charset::Ascii.encode("Café").unwrap_err(),
+            QrError::UnsupportedCharacterSet
+        );
+        assert_eq!(Charset::Iso8859_1.encode("Café").unwrap(), b"Caf\xe9");
+        assert_eq!(
+            Charset::Iso8859_1.encode("漢字").unwrap_err(),
+            QrError::UnsupportedCharacterSet
+        );
+        assert_eq!(
+            Charset::ShiftJis.encode("漢字").unwrap(),
+            [0x8a, 0xbf, 0x8e, 0x9a]
+        );
+        assert_eq!(Charset::Utf8.encode("漢字").unwrap(), "漢字".as_bytes());
+        assert_eq!(Charset::EucKr.encode("한").unwrap(), [0xc7, 0xd1]);
+        assert_eq!(
+            Charset::Big5.encode("한").unwrap_err(),
+            QrError::UnsupportedCharacterSet
+        );
+    }
+
+    #[test]
+    fn construct_qr_code_with_segments() {
+        let segments = [
+            Segment::new("Hello", Charset::Ascii).unwrap(),
+            Segment::new("漢字", Charset::ShiftJis).unwrap(),
+        ];
+        let code = to_qr_code_with_segments(&segments, 1..=40, Some(EcLevel::M), EcLevel::L, true)
+            .unwrap();
+        assert_eq!(code.version(), Version::Normal(1));
+        assert_eq!(code.error_correction_level(), EcLevel::M);
+        assert_eq!(
+            segments_codewords(&segments, &code, true).unwrap(),
+            (15, 16)
+        );
+        let code = to_qr_code_with_segments(&segments, 2..=2, None, EcLevel::L, true).unwrap();
+        assert_eq!(code.version(), Version::Normal(2));
+        assert_eq!(code.error_correction_level(), EcLevel::H);
+
+        let mut bits = Bits::new(Version::Normal(1));
+        push_segments(&mut bits, &[segments[0].clone(), segments[0].clone()], true).unwrap();
+        // One ECI designator, one mode indicator and character count for each
+        // segment.
+        assert_eq!(bits.len(), 12 + 2 * (12 + 5 * 8));
+        let mut bits = Bits::new(Version::Normal(1));
+        push_segments(&mut bits, &segments, false).unwrap();
+        assert_eq!(bits.len(), 2 * 12 + (5 + 4) * 8);
+
+        let long = Segment::new(&"a".repeat(3000), Charset::Ascii).unwrap();
+        assert!(matches!(
+            to_qr_code_with_segments(&[long], 1..=40, Some(EcLevel::H), EcLevel::L, true),
+            Err(QrError::DataTooLong)
+        ));
+        assert_eq!(
+            push_segments(&mut Bits::new(Version::Micro(4)), &segments, true).unwrap_err(),
+            QrError::UnsupportedCharacterSet
+        );
+    }
+
+    #[test]
+    fn paint_quiet_zones() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let colors = (
+            Color::new(0.0, 0.0, 0.0, 1.0),
+            Color::new(1.0, 1.0, 1.0, 1.0),
+        );
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let mut image = to_image(&code, 4, &colors, Some(1));
+        paint_quiet_zone(&mut image, &code, 4, Some(1), &red);
+        assert_eq!(image.width(), 29);
+        assert_eq!(image.get_pixel(0, 0), &Rgba([u8::MAX, 0, 0, u8::MAX]));
+        assert_eq!(image.get_pixel(3, 28), &Rgba([u8::MAX, 0, 0, u8::MAX]));
+        assert_eq!(image.get_pixel(4, 4), &Rgba([0, 0, 0, u8::MAX]));
+        assert_eq!(image.get_pixel(11, 4), &Rgba([u8::MAX; 4]));
+        assert_eq!(image.get_pixel(25, 24), &Rgba([u8::MAX, 0, 0, u8::MAX]));
+
+        let svg = to_svg_image(&code, 4, &colors, None);
+        let mut painted = svg.clone();
+        paint_svg_quiet_zone(&mut painted, &code, 4, None, &red);
+        assert!(painted.content.ends_with(
+            r##"<path fill="#ff0000" d="M0 0h232v232h-232zM32 32v168h168v-168z"/>"##
+        ));
+        let mut painted = svg.clone();
+        paint_svg_quiet_zone(&mut painted, &code, 0, None, &red);
+        assert_eq!(painted, svg);
+    }
+
+    #[test]
+    fn classify_regions() {
+        let code = QrCode::with_version(b"QR code", Version::Normal(7), EcLevel::L).unwrap();
+        assert_eq!(alignment_pattern_positions(45), [6, 22, 38]);
+        assert_eq!(alignment_pattern_positions(21), Vec::<usize>::new());
+        assert_eq!(region(&code, 0, 0), Some(Region::Finder));
+        assert_eq!(region(&code, 7, 44), Some(Region::Finder));
+        assert_eq!(region(&code, 20, 6), Some(Region::Timing));
+        assert_eq!(region(&code, 22, 22), Some(Region::Alignment));
+        assert_eq!(region(&code, 6, 22), Some(Region::Timing));
+        assert_eq!(region(&code, 4, 22), Some(Region::Alignment));
+        assert_eq!(region(&code, 8, 3), Some(Region::Format));
+        assert_eq!(region(&code, 8, 37), Some(Region::Format));
+        assert_eq!(region(&code, 0, 34), Some(Region::Version));
+        assert_eq!(region(&code, 36, 5), Some(Region::Version));
+        assert_eq!(region(&code, 37, 5), Some(Region::Finder));
+        assert_eq!(region(&code, 15, 15), None);
+
+        let code = QrCode::with_version(b"01234567", Version::Micro(2), EcLevel::L).unwrap();
+        assert_eq!(region(&code, 7, 7), Some(Region::Finder));
+        assert_eq!(region(&code, 12, 0), Some(Region::Timing));
+        assert_eq!(region(&code, 8, 8), Some(Region::Format));
+        assert_eq!(region(&code, 12, 12), None);
     }
 
     #[test]
@@ -273,25 +2970,31 @@ mod tests {
             QrCode::with_version(DATA, Version::Normal(1), EcLevel::L)
                 .unwrap()
                 .metadata(),
-            Metadata::new(1, Ecc::L)
+            Metadata::new(1, Ecc::L, 0, 21)
         );
         assert_eq!(
             QrCode::with_version(DATA, Version::Normal(1), EcLevel::M)
                 .unwrap()
                 .metadata(),
-            Metadata::new(1, Ecc::M)
+            Metadata::new(1, Ecc::M, 2, 21)
         );
         assert_eq!(
             QrCode::with_version(DATA, Version::Normal(1), EcLevel::Q)
                 .unwrap()
                 .metadata(),
-            Metadata::new(1, Ecc::Q)
+            Metadata::new(1, Ecc::Q, 1, 21)
         );
         assert_eq!(
             QrCode::with_version(DATA, Version::Normal(1), EcLevel::H)
                 .unwrap()
                 .metadata(),
-            Metadata::new(1, Ecc::H)
+            Metadata::new(1, Ecc::H, 2, 21)
+        );
+        assert_eq!(
+            QrCode::with_version(b"QR code", Version::Micro(3), EcLevel::M)
+                .unwrap()
+                .metadata(),
+            Metadata::new(3, Ecc::M, 0, 15)
         );
     }
 }
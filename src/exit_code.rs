@@ -0,0 +1,276 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::{io, process::ExitCode};
+
+use image::ImageError;
+use qrcode::types::QrError;
+use rqrr::DeQRError;
+
+use crate::{matrix::ParseMatrixError, structured_append::JoinError, verify::MismatchError};
+
+/// Exit statuses of the program.
+///
+/// The values other than [`ExitStatus::Success`], [`ExitStatus::Failure`],
+/// [`ExitStatus::Usage`] and [`ExitStatus::Mismatch`] are based on
+/// `<sysexits.h>`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExitStatus {
+    /// Successful program execution.
+    Success,
+
+    /// An error occurred.
+    Failure,
+
+    /// An error occurred while parsing command-line arguments.
+    Usage,
+
+    /// The decoded data did not match the expected data.
+    Mismatch,
+
+    /// The input data was incorrect in some way.
+    DataErr,
+
+    /// An input file did not exist or was not readable.
+    NoInput,
+
+    /// A service is unavailable.
+    Unavailable,
+
+    /// An operating system error has been detected.
+    OsErr,
+
+    /// A file could not be created.
+    CantCreat,
+
+    /// An error occurred while doing I/O on some file.
+    IoErr,
+
+    /// A temporary failure, such as the time limit being exceeded.
+    TempFail,
+
+    /// You did not have sufficient permission to perform the operation.
+    NoPerm,
+
+    /// Something was found in an unconfigured or misconfigured state.
+    Config,
+}
+
+impl ExitStatus {
+    /// All documented exit statuses in ascending order of the code.
+    pub const ALL: [Self; 13] = [
+        Self::Success,
+        Self::Failure,
+        Self::Usage,
+        Self::Mismatch,
+        Self::DataErr,
+        Self::NoInput,
+        Self::Unavailable,
+        Self::OsErr,
+        Self::CantCreat,
+        Self::IoErr,
+        Self::TempFail,
+        Self::NoPerm,
+        Self::Config,
+    ];
+
+    /// Returns the exit code.
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::Failure => 1,
+            Self::Usage => 2,
+            Self::Mismatch => 3,
+            Self::DataErr => sysexits::ExitCode::DataErr as u8,
+            Self::NoInput => sysexits::ExitCode::NoInput as u8,
+            Self::Unavailable => sysexits::ExitCode::Unavailable as u8,
+            Self::OsErr => sysexits::ExitCode::OsErr as u8,
+            Self::CantCreat => sysexits::ExitCode::CantCreat as u8,
+            Self::IoErr => sysexits::ExitCode::IoErr as u8,
+            Self::TempFail => sysexits::ExitCode::TempFail as u8,
+            Self::NoPerm => sysexits::ExitCode::NoPerm as u8,
+            Self::Config => sysexits::ExitCode::Config as u8,
+        }
+    }
+
+    /// Returns the description of the exit status.
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Success => "Successful program execution.",
+            Self::Failure => "An error occurred.",
+            Self::Usage => "An error occurred while parsing command-line arguments.",
+            Self::Mismatch => "The decoded data did not match the expected data.",
+            Self::DataErr => "The input data was incorrect in some way.",
+            Self::NoInput => "An input file did not exist or was not readable.",
+            Self::Unavailable => "A service is unavailable.",
+            Self::OsErr => "An operating system error has been detected.",
+            Self::CantCreat => "A file could not be created.",
+            Self::IoErr => "An error occurred while doing I/O on some file.",
+            Self::TempFail => "A temporary failure occurred, such as exceeding the time limit.",
+            Self::NoPerm => "You did not have sufficient permission to perform the operation.",
+            Self::Config => "Something was found in an unconfigured or misconfigured state.",
+        }
+    }
+}
+
+impl From<io::ErrorKind> for ExitStatus {
+    fn from(kind: io::ErrorKind) -> Self {
+        use io::ErrorKind;
+
+        match kind {
+            ErrorKind::NotFound => Self::NoInput,
+            ErrorKind::PermissionDenied => Self::NoPerm,
+            ErrorKind::ConnectionRefused | ErrorKind::OutOfMemory => Self::OsErr,
+            ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::BrokenPipe
+            | ErrorKind::TimedOut
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock => Self::TempFail,
+            ErrorKind::AddrInUse | ErrorKind::AddrNotAvailable | ErrorKind::Unsupported => {
+                Self::Unavailable
+            }
+            ErrorKind::AlreadyExists => Self::CantCreat,
+            ErrorKind::InvalidInput | ErrorKind::InvalidData => Self::DataErr,
+            _ => Self::IoErr,
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        status.code().into()
+    }
+}
+
+/// Returns the exit code corresponding to the error.
+pub fn from_error(err: &anyhow::Error) -> ExitCode {
+    if let Some(e) = err.downcast_ref::<io::Error>() {
+        return ExitStatus::from(e.kind()).into();
+    }
+    let status = if err.is::<MismatchError>() {
+        ExitStatus::Mismatch
+    } else if err.is::<toml::de::Error>() {
+        ExitStatus::Config
+    } else if err.is::<QrError>() || err.is::<JoinError>() || err.is::<ParseMatrixError>() {
+        ExitStatus::DataErr
+    } else if let Some(e) = err.downcast_ref::<DeQRError>() {
+        if matches!(e, DeQRError::IoError) {
+            ExitStatus::IoErr
+        } else {
+            ExitStatus::DataErr
+        }
+    } else if let Some(e) = err.downcast_ref::<ImageError>() {
+        match e {
+            ImageError::Limits(_) => ExitStatus::OsErr,
+            ImageError::Unsupported(_) => ExitStatus::Unavailable,
+            ImageError::IoError(_) => ExitStatus::IoErr,
+            _ => ExitStatus::DataErr,
+        }
+    } else {
+        from_svg_error(err)
+            .or_else(|| from_clipboard_error(err))
+            .unwrap_or(ExitStatus::Failure)
+    };
+    status.into()
+}
+
+#[cfg(feature = "decode-from-svg")]
+fn from_svg_error(err: &anyhow::Error) -> Option<ExitStatus> {
+    use resvg::usvg::Error;
+
+    err.downcast_ref::<Error>().map(|e| match e {
+        Error::NotAnUtf8Str | Error::ElementsLimitReached => ExitStatus::Unavailable,
+        Error::MalformedGZip | Error::InvalidSize | Error::ParsingFailed(_) => ExitStatus::DataErr,
+    })
+}
+
+#[cfg(not(feature = "decode-from-svg"))]
+#[allow(clippy::unnecessary_wraps)]
+const fn from_svg_error(_: &anyhow::Error) -> Option<ExitStatus> {
+    None
+}
+
+#[cfg(feature = "clipboard")]
+fn from_clipboard_error(err: &anyhow::Error) -> Option<ExitStatus> {
+    use arboard::Error;
+
+    err.downcast_ref::<Error>().map(|e| match e {
+        Error::ContentNotAvailable => ExitStatus::NoInput,
+        Error::ClipboardNotSupported | Error::ClipboardOccupied => ExitStatus::Unavailable,
+        Error::ConversionFailure => ExitStatus::DataErr,
+        _ => ExitStatus::Failure,
+    })
+}
+
+#[cfg(not(feature = "clipboard"))]
+#[allow(clippy::unnecessary_wraps)]
+const fn from_clipboard_error(_: &anyhow::Error) -> Option<ExitStatus> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_status_code() {
+        assert_eq!(ExitStatus::Success.code(), 0);
+        assert_eq!(ExitStatus::Failure.code(), 1);
+        assert_eq!(ExitStatus::Usage.code(), 2);
+        assert_eq!(ExitStatus::Mismatch.code(), 3);
+        assert_eq!(ExitStatus::DataErr.code(), 65);
+        assert_eq!(ExitStatus::NoInput.code(), 66);
+        assert_eq!(ExitStatus::Unavailable.code(), 69);
+        assert_eq!(ExitStatus::OsErr.code(), 71);
+        assert_eq!(ExitStatus::CantCreat.code(), 73);
+        assert_eq!(ExitStatus::IoErr.code(), 74);
+        assert_eq!(ExitStatus::TempFail.code(), 75);
+        assert_eq!(ExitStatus::NoPerm.code(), 77);
+        assert_eq!(ExitStatus::Config.code(), 78);
+    }
+
+    #[test]
+    fn exit_status_from_io_error_kind() {
+        assert_eq!(
+            ExitStatus::from(io::ErrorKind::NotFound),
+            ExitStatus::NoInput
+        );
+        assert_eq!(
+            ExitStatus::from(io::ErrorKind::AlreadyExists),
+            ExitStatus::CantCreat
+        );
+        assert_eq!(
+            ExitStatus::from(io::ErrorKind::UnexpectedEof),
+            ExitStatus::IoErr
+        );
+        assert_eq!(
+            ExitStatus::from(io::ErrorKind::Unsupported),
+            ExitStatus::Unavailable
+        );
+        assert_eq!(
+            ExitStatus::from(io::ErrorKind::WouldBlock),
+            ExitStatus::TempFail
+        );
+    }
+
+    #[cfg(feature = "clipboard")]
+    #[test]
+    fn exit_code_from_clipboard_error() {
+        let err = anyhow::Error::from(arboard::Error::ContentNotAvailable)
+            .context("the clipboard does not contain an image");
+        assert_eq!(from_clipboard_error(&err), Some(ExitStatus::NoInput));
+        let err = anyhow::Error::from(arboard::Error::ClipboardNotSupported);
+        assert_eq!(from_clipboard_error(&err), Some(ExitStatus::Unavailable));
+        assert_eq!(from_clipboard_error(&anyhow::anyhow!("error")), None);
+    }
+
+    #[test]
+    fn all_exit_statuses_are_sorted() {
+        assert!(ExitStatus::ALL
+            .windows(2)
+            .all(|w| w[0].code() < w[1].code()));
+    }
+}
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 use std::{
+    collections::VecDeque,
     fs::File,
     io::{self, Cursor, Read, Stdin},
 };
@@ -10,7 +11,7 @@ use std::{
 #[derive(Debug)]
 pub enum Input {
     String(Cursor<String>),
-    File(File),
+    Files(VecDeque<File>),
     Stdin(Stdin),
 }
 
@@ -18,7 +19,17 @@ impl Read for Input {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         match *self {
             Self::String(ref mut string) => string.read(buf),
-            Self::File(ref mut file) => file.read(buf),
+            Self::Files(ref mut files) => {
+                while let Some(file) = files.front_mut() {
+                    match file.read(buf)? {
+                        0 if !buf.is_empty() => {
+                            files.pop_front();
+                        }
+                        n => return Ok(n),
+                    }
+                }
+                Ok(0)
+            }
             Self::Stdin(ref mut stdin) => stdin.read(buf),
         }
     }
@@ -0,0 +1,105 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! The `qrtool` crate is a library for encoding or decoding QR code.
+//!
+//! This crate provides the encoding and decoding logic used by the `qrtool`
+//! command-line utility, so it can be used from other Rust programs without
+//! executing the command.
+//!
+//! # Examples
+//!
+//! ```
+//! use qrtool::EncodeOptions;
+//!
+//! let image = qrtool::encode_to_png(b"QR code", &EncodeOptions::default()).unwrap();
+//! let image = image::load_from_memory(&image).unwrap();
+//! let contents = qrtool::decode_image(&image).unwrap();
+//! assert_eq!(contents[0].1, b"QR code");
+//! ```
+//!
+//! # Stability
+//!
+//! The items re-exported at the root of this crate (e.g. [`encode_to_png`],
+//! [`EncodeOptions`], [`to_image`], [`to_svg`], [`to_terminal`],
+//! [`decode_image`] and [`Metadata`]) follow [Semantic Versioning] with the
+//! same version number as the command-line utility. Until version 1.0.0 is
+//! released, breaking changes to them may be made in minor releases (e.g.
+//! 0.11.x to 0.12.0), but not in patch releases.
+//!
+//! The implementation details of the command-line utility are private and
+//! are not part of the public API of this crate.
+//!
+//! [Semantic Versioning]: https://semver.org/
+
+// Lint levels of rustc.
+#![forbid(unsafe_code)]
+#![deny(missing_debug_implementations)]
+#![warn(missing_docs, rust_2018_idioms)]
+// Lint levels of Clippy.
+#![warn(clippy::cargo, clippy::nursery, clippy::pedantic)]
+#![allow(clippy::multiple_crate_versions)]
+
+// These modules are also compiled into the command-line utility, and most of
+// their items are only used by it.
+#[allow(dead_code)]
+mod color;
+#[allow(dead_code)]
+mod decode;
+#[allow(dead_code)]
+mod encode;
+#[cfg(feature = "output-with-title")]
+mod font;
+#[allow(dead_code)]
+mod matrix;
+mod metadata;
+#[allow(dead_code)]
+mod palette;
+#[allow(dead_code)]
+mod structured_append;
+
+use std::io::Cursor;
+
+use anyhow::Context;
+pub use csscolorparser::Color;
+use image::{DynamicImage, ImageFormat};
+
+pub use crate::{
+    decode::DecodedBytes,
+    encode::{to_image, to_svg, to_unicode as to_terminal, Ecc, EncodeOptions, Mode, Variant},
+    matrix::Modules,
+    metadata::{Codewords, Extractor, Metadata},
+};
+
+/// Encodes the data in a QR code and renders it into a PNG image.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the data cannot be encoded with the given options, or if
+/// writing the PNG image fails.
+pub fn encode_to_png(data: impl AsRef<[u8]>, options: &EncodeOptions) -> anyhow::Result<Vec<u8>> {
+    let code = options
+        .to_qr_code(data)
+        .context("could not construct a QR code")?;
+    let margin = options
+        .margin
+        .unwrap_or_else(|| if code.version().is_micro() { 2 } else { 4 });
+    let image = encode::to_image(&code, margin, &options.colors, options.module_size);
+    let mut buf = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+        .context("could not write the image to the buffer")?;
+    Ok(buf)
+}
+
+/// Detects and decodes QR codes in the image.
+///
+/// If no QR code can be decoded, this also tries with the inverted image.
+///
+/// # Errors
+///
+/// Returns [`Err`] if decoding a detected QR code fails.
+pub fn decode_image(image: &DynamicImage) -> Result<Vec<DecodedBytes>, rqrr::DeQRError> {
+    decode::decode(image.to_luma8())
+}
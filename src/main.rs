@@ -11,56 +11,36 @@
 #![allow(clippy::multiple_crate_versions)]
 
 mod app;
+mod benchmark;
 mod cli;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+mod color;
+mod config;
+// Some items of these modules are only used by the library.
+#[allow(dead_code)]
 mod decode;
+#[allow(dead_code)]
 mod encode;
+mod exit_code;
+#[cfg(feature = "output-with-title")]
+mod font;
 mod input;
+mod matrix;
 mod metadata;
+mod palette;
+mod structured_append;
+mod verify;
+mod watchdog;
 
-use std::{io, process::ExitCode};
-
-use image::ImageError;
-use qrcode::types::QrError;
-use rqrr::DeQRError;
+use std::process::ExitCode;
 
 fn main() -> ExitCode {
     match app::run() {
         Ok(()) => ExitCode::SUCCESS,
         Err(err) => {
             eprintln!("Error: {err:?}");
-            if let Some(e) = err.downcast_ref::<io::Error>() {
-                return sysexits::ExitCode::from(e.kind()).into();
-            }
-            if err.is::<QrError>() {
-                return sysexits::ExitCode::DataErr.into();
-            }
-            if let Some(e) = err.downcast_ref::<DeQRError>() {
-                return if matches!(e, DeQRError::IoError) {
-                    sysexits::ExitCode::IoErr.into()
-                } else {
-                    sysexits::ExitCode::DataErr.into()
-                };
-            }
-            if let Some(e) = err.downcast_ref::<ImageError>() {
-                return match e {
-                    ImageError::Limits(_) => sysexits::ExitCode::OsErr.into(),
-                    ImageError::Unsupported(_) => sysexits::ExitCode::Unavailable.into(),
-                    ImageError::IoError(_) => sysexits::ExitCode::IoErr.into(),
-                    _ => sysexits::ExitCode::DataErr.into(),
-                };
-            }
-            #[cfg(feature = "decode-from-svg")]
-            if let Some(e) = err.downcast_ref::<resvg::usvg::Error>() {
-                return match e {
-                    resvg::usvg::Error::NotAnUtf8Str | resvg::usvg::Error::ElementsLimitReached => {
-                        sysexits::ExitCode::Unavailable.into()
-                    }
-                    resvg::usvg::Error::MalformedGZip
-                    | resvg::usvg::Error::InvalidSize
-                    | resvg::usvg::Error::ParsingFailed(_) => sysexits::ExitCode::DataErr.into(),
-                };
-            }
-            ExitCode::FAILURE
+            exit_code::from_error(&err)
         }
     }
 }
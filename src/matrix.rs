@@ -0,0 +1,242 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Matrices of modules.
+//!
+//! A matrix is written as text, in which each line is a row of modules, `1`
+//! is a dark module and `0` is a light module. This allows inspecting a
+//! detected symbol and rendering exactly the same symbol again.
+
+use std::{error, fmt, str::FromStr};
+
+use qrcode::{Color, QrCode};
+use rqrr::{BitGrid, Grid};
+
+/// A trait for square matrices of modules which can be rendered.
+pub trait Modules {
+    /// Returns the number of modules per side.
+    fn width(&self) -> usize;
+
+    /// Returns the color of the module at the coordinates.
+    fn module(&self, x: usize, y: usize) -> Color;
+
+    /// Returns `true` if the matrix is a Micro QR code.
+    fn is_micro(&self) -> bool;
+
+    /// Returns the colors of the modules in row-major order.
+    fn to_colors(&self) -> Vec<Color> {
+        let width = self.width();
+        (0..width)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.module(x, y))
+            .collect()
+    }
+}
+
+impl Modules for QrCode {
+    fn width(&self) -> usize {
+        self.width()
+    }
+
+    fn module(&self, x: usize, y: usize) -> Color {
+        self[(x, y)]
+    }
+
+    fn is_micro(&self) -> bool {
+        self.version().is_micro()
+    }
+
+    fn to_colors(&self) -> Vec<Color> {
+        self.to_colors()
+    }
+}
+
+/// A square matrix of modules.
+///
+/// The matrix is not required to be a valid QR code, but its width must be
+/// the width of any version of QR code or Micro QR code.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Matrix {
+    width: usize,
+    colors: Vec<Color>,
+}
+
+impl Matrix {
+    /// Constructs a new matrix from the colors of the modules in row-major
+    /// order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the matrix is not square, or if the width is not the
+    /// width of any version.
+    pub fn new(width: usize, colors: Vec<Color>) -> Result<Self, ParseMatrixError> {
+        if colors.len() != width * width {
+            return Err(ParseMatrixError::NotSquare);
+        }
+        let is_valid_width = match width {
+            11..=17 => width % 2 == 1,
+            21..=177 => (width - 21) % 4 == 0,
+            _ => false,
+        };
+        if !is_valid_width {
+            return Err(ParseMatrixError::InvalidWidth(width));
+        }
+        Ok(Self { width, colors })
+    }
+}
+
+impl Modules for Matrix {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn module(&self, x: usize, y: usize) -> Color {
+        self.colors[y * self.width + x]
+    }
+
+    fn is_micro(&self) -> bool {
+        self.width < 21
+    }
+
+    fn to_colors(&self) -> Vec<Color> {
+        self.colors.clone()
+    }
+}
+
+impl From<&QrCode> for Matrix {
+    fn from(code: &QrCode) -> Self {
+        Self {
+            width: code.width(),
+            colors: code.to_colors(),
+        }
+    }
+}
+
+impl<G: BitGrid> From<&Grid<G>> for Matrix {
+    fn from(grid: &Grid<G>) -> Self {
+        let width = grid.grid.size();
+        let colors = (0..width)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                if grid.grid.bit(y, x) {
+                    Color::Dark
+                } else {
+                    Color::Light
+                }
+            })
+            .collect();
+        Self { width, colors }
+    }
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.colors.chunks(self.width) {
+            for color in row {
+                f.write_str(if *color == Color::Dark { "1" } else { "0" })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Matrix {
+    type Err = ParseMatrixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows = s
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        '0' => Ok(Color::Light),
+                        '1' => Ok(Color::Dark),
+                        c => Err(ParseMatrixError::InvalidCharacter(c)),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if rows.iter().any(|row| row.len() != rows.len()) {
+            return Err(ParseMatrixError::NotSquare);
+        }
+        Self::new(rows.len(), rows.concat())
+    }
+}
+
+/// An error which can be returned when parsing a matrix.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ParseMatrixError {
+    /// The character is neither `0` nor `1`.
+    InvalidCharacter(char),
+
+    /// The matrix is not square.
+    NotSquare,
+
+    /// The width is not the width of any version.
+    InvalidWidth(usize),
+}
+
+impl fmt::Display for ParseMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCharacter(c) => write!(f, "invalid character {c:?} in the matrix"),
+            Self::NotSquare => write!(f, "matrix is not square"),
+            Self::InvalidWidth(width) => write!(f, "invalid matrix width {width}"),
+        }
+    }
+}
+
+impl error::Error for ParseMatrixError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_round_trip() {
+        let code = QrCode::new(b"QR code").unwrap();
+        let matrix = Matrix::from(&code);
+        assert_eq!(matrix.width(), 21);
+        assert!(!matrix.is_micro());
+        assert_eq!(Modules::to_colors(&matrix), code.to_colors());
+        let text = matrix.to_string();
+        assert_eq!(text.lines().count(), 21);
+        assert!(text.starts_with("1111111"));
+        assert_eq!(text.parse::<Matrix>().unwrap(), matrix);
+    }
+
+    #[test]
+    fn parse_micro_qr_code_matrix() {
+        let code = QrCode::with_version(b"01234567", qrcode::Version::Micro(2), qrcode::EcLevel::L)
+            .unwrap();
+        let matrix = format!("\n{}\n", Matrix::from(&code))
+            .parse::<Matrix>()
+            .unwrap();
+        assert_eq!(matrix.width(), 13);
+        assert!(matrix.is_micro());
+    }
+
+    #[test]
+    fn parse_invalid_matrix() {
+        assert_eq!(
+            "10\n0x".parse::<Matrix>().unwrap_err(),
+            ParseMatrixError::InvalidCharacter('x')
+        );
+        assert_eq!(
+            "10\n0".parse::<Matrix>().unwrap_err(),
+            ParseMatrixError::NotSquare
+        );
+        assert_eq!(
+            "10\n01".parse::<Matrix>().unwrap_err(),
+            ParseMatrixError::InvalidWidth(2)
+        );
+        assert_eq!(
+            "".parse::<Matrix>().unwrap_err(),
+            ParseMatrixError::InvalidWidth(0)
+        );
+    }
+}
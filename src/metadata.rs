@@ -2,15 +2,38 @@
 //
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
-use crate::cli::Ecc;
+use serde::Serialize;
+
+use crate::encode::{Ecc, Variant};
 
 /// Metadata for a QR code.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize)]
 pub struct Metadata {
+    #[serde(rename = "version")]
     symbol_version: usize,
+    #[serde(rename = "level")]
     error_correction_level: Ecc,
+    #[serde(rename = "mask")]
+    mask_pattern: u8,
+    width: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    codewords: Option<Codewords>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_errors: Option<Vec<usize>>,
+}
+
+/// The number of data codewords used by the data and the number of data
+/// codewords of the symbol.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub struct Codewords {
+    /// The number of data codewords used by the data.
+    pub used: usize,
+
+    /// The number of data codewords of the symbol.
+    pub total: usize,
 }
 
+/// A trait for extracting the metadata from a QR code.
 pub trait Extractor {
     /// Extracts the metadata.
     fn metadata(&self) -> Metadata;
@@ -18,20 +41,140 @@ pub trait Extractor {
 
 impl Metadata {
     /// Constructs a new metadata.
-    pub const fn new(symbol_version: usize, error_correction_level: Ecc) -> Self {
+    #[must_use]
+    pub const fn new(
+        symbol_version: usize,
+        error_correction_level: Ecc,
+        mask_pattern: u8,
+        width: usize,
+    ) -> Self {
         Self {
             symbol_version,
             error_correction_level,
+            mask_pattern,
+            width,
+            codewords: None,
+            block_errors: None,
         }
     }
 
+    /// Sets the number of data codewords used by the data and the number of
+    /// data codewords of the symbol.
+    #[must_use]
+    pub const fn with_codewords(mut self, used: usize, total: usize) -> Self {
+        self.codewords = Some(Codewords { used, total });
+        self
+    }
+
+    /// Sets the number of erroneous codewords in each block.
+    #[must_use]
+    pub fn with_block_errors(mut self, errors: Vec<usize>) -> Self {
+        self.block_errors = Some(errors);
+        self
+    }
+
     /// Gets the symbol version.
+    #[must_use]
     pub const fn symbol_version(&self) -> usize {
         self.symbol_version
     }
 
     /// Gets the error correction level.
+    #[must_use]
     pub const fn error_correction_level(&self) -> Ecc {
         self.error_correction_level
     }
+
+    /// Gets the mask pattern.
+    ///
+    /// This is between 0 and 7 for normal QR code, and between 0 and 3 for
+    /// Micro QR code.
+    #[must_use]
+    pub const fn mask_pattern(&self) -> u8 {
+        self.mask_pattern
+    }
+
+    /// Gets the number of modules on each side, excluding the margin.
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Gets the number of data codewords used by the data and the number of
+    /// data codewords of the symbol.
+    ///
+    /// Returns [`None`] if they are unknown.
+    #[must_use]
+    pub const fn codewords(&self) -> Option<Codewords> {
+        self.codewords
+    }
+
+    /// Gets the number of erroneous codewords in each block.
+    ///
+    /// Returns [`None`] if they are unknown.
+    #[must_use]
+    pub fn block_errors(&self) -> Option<&[usize]> {
+        self.block_errors.as_deref()
+    }
+
+    /// Returns the metadata as a JSON object.
+    ///
+    /// The type of QR code is determined from the width. The number of
+    /// codewords and the number of erroneous codewords are only included if
+    /// they are known.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the metadata cannot be serialized, which should not happen.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        #[derive(Serialize)]
+        struct Json<'a> {
+            variant: Variant,
+            #[serde(flatten)]
+            metadata: &'a Metadata,
+        }
+
+        let variant = if self.width < 21 {
+            Variant::Micro
+        } else {
+            Variant::Normal
+        };
+        serde_json::to_string(&Json {
+            variant,
+            metadata: self,
+        })
+        .expect("metadata should be serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_to_json() {
+        assert_eq!(
+            Metadata::new(1, Ecc::M, 2, 21).to_json(),
+            r#"{"variant":"normal","version":1,"level":"M","mask":2,"width":21}"#
+        );
+        assert_eq!(
+            Metadata::new(3, Ecc::L, 0, 15)
+                .with_codewords(5, 11)
+                .with_block_errors(vec![0, 2])
+                .to_json(),
+            r#"{"variant":"micro","version":3,"level":"L","mask":0,"width":15,"codewords":{"used":5,"total":11},"block_errors":[0,2]}"#
+        );
+    }
+
+    #[test]
+    fn metadata_codewords() {
+        assert_eq!(Metadata::new(1, Ecc::M, 2, 21).codewords(), None);
+        assert_eq!(
+            Metadata::new(1, Ecc::M, 2, 21)
+                .with_codewords(9, 16)
+                .codewords(),
+            Some(Codewords { used: 9, total: 16 })
+        );
+    }
 }
@@ -0,0 +1,132 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Palettes of named colors.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::Context;
+use csscolorparser::Color;
+use serde::{de, Deserialize, Deserializer};
+
+use crate::color::{self, ParseColorError};
+
+/// A set of named colors loaded from a file.
+///
+/// The palette file is a TOML file which has the `colors` table. Each key is
+/// the name of a color, and each value takes the same color string as
+/// [`color::parse`].
+///
+/// ```toml
+/// [colors]
+/// brand-primary = "#1a73e8"
+/// brand-background = "cmyk(0% 0% 5% 0%)"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Palette {
+    #[serde(default, deserialize_with = "deserialize_colors")]
+    colors: HashMap<String, Color>,
+}
+
+impl Palette {
+    /// Reads the palette from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the file cannot be read, or if the palette is
+    /// invalid.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("could not read the palette from {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("could not parse the palette in {}", path.display()))
+    }
+
+    /// Returns the color with the name.
+    ///
+    /// The name is matched case-insensitively.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Color> {
+        let name = name.trim();
+        self.colors
+            .iter()
+            .find_map(|(key, color)| key.eq_ignore_ascii_case(name).then_some(color))
+    }
+
+    /// Parses a color, resolving the names in the palette first.
+    ///
+    /// If `s` is not a name in the palette, this is the same as
+    /// [`color::parse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if `s` is neither a name in the palette nor a valid
+    /// color.
+    pub fn parse(&self, s: &str) -> Result<Color, ParseColorError> {
+        self.get(s).cloned().map_or_else(|| color::parse(s), Ok)
+    }
+}
+
+fn deserialize_colors<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<String, Color>, D::Error> {
+    HashMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, color)| {
+            color::parse(&color)
+                .map(|color| (name, color))
+                .map_err(de::Error::custom)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_palette() {
+        let palette: Palette = toml::from_str(
+            r##"
+            [colors]
+            brand-primary = "#1a73e8"
+            brand-background = "cmyk(0% 0% 0% 10%)"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(
+            palette.get("brand-primary"),
+            Some(&Color::from_rgba8(26, 115, 232, u8::MAX))
+        );
+        assert_eq!(
+            palette.get("Brand-Background"),
+            Some(&Color::new(0.9, 0.9, 0.9, 1.0))
+        );
+        assert_eq!(palette.get("brand-secondary"), None);
+    }
+
+    #[test]
+    fn parse_invalid_palette() {
+        assert!(toml::from_str::<Palette>("[colors]\nbrand-primary = \"#zzz\"").is_err());
+        assert!(toml::from_str::<Palette>("[colours]").is_err());
+    }
+
+    #[test]
+    fn parse_color_with_palette() {
+        let palette: Palette = toml::from_str("[colors]\nred = \"#1a73e8\"").unwrap();
+        assert_eq!(
+            palette.parse("red"),
+            Ok(Color::from_rgba8(26, 115, 232, u8::MAX))
+        );
+        assert_eq!(
+            palette.parse("blue"),
+            Ok(Color::from_rgba8(0, 0, u8::MAX, u8::MAX))
+        );
+        assert_eq!(palette.parse("#ff0000"), color::parse("#ff0000"));
+        assert!(palette.parse("brand-primary").is_err());
+        assert!(Palette::default().parse("brand-primary").is_err());
+    }
+}
@@ -0,0 +1,402 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Functions for decoding QR codes in Structured Append mode.
+//!
+//! In Structured Append mode, the data is divided into up to 16 QR codes. Each
+//! QR code has a header which consists of the position of the symbol in the
+//! sequence, the total number of symbols and the parity of the whole data.
+
+use std::{error, fmt};
+
+use image::{imageops, GrayImage};
+use rqrr::{BitGrid, DeQRError, Grid, MetaData, PreparedImage};
+
+use crate::decode::{correct_codewords, read_codewords, DecodedBytes};
+
+/// The mode indicator of Structured Append mode.
+const MODE_INDICATOR: u32 = 0b0011;
+
+/// The header of a QR code in Structured Append mode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Header {
+    /// The position of the symbol in the sequence, starting from 0.
+    pub index: u8,
+
+    /// The total number of symbols in the sequence.
+    pub total: u8,
+
+    /// The parity of the whole data.
+    ///
+    /// This is the XOR of all bytes of the whole data.
+    pub parity: u8,
+}
+
+/// A decoded QR code in Structured Append mode.
+pub type Symbol = (Header, DecodedBytes);
+
+/// An error which can occur when reassembling QR codes in Structured Append
+/// mode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum JoinError {
+    /// No QR code was found.
+    Empty,
+
+    /// A QR code is not in Structured Append mode.
+    NotStructuredAppend,
+
+    /// The total numbers of symbols in the headers do not match.
+    TotalMismatch,
+
+    /// The parities in the headers do not match.
+    ParityMismatch,
+
+    /// The position is out of the sequence.
+    InvalidPosition(u8),
+
+    /// Different contents have the same position in the sequence.
+    Duplicate(u8),
+
+    /// The symbol at the position is missing.
+    Missing(u8),
+
+    /// The parity does not match the reassembled data.
+    InvalidParity,
+}
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no QR code was found"),
+            Self::NotStructuredAppend => write!(f, "QR code is not in Structured Append mode"),
+            Self::TotalMismatch => write!(f, "total numbers of symbols do not match"),
+            Self::ParityMismatch => write!(f, "parities of symbols do not match"),
+            Self::InvalidPosition(index) => {
+                write!(f, "symbol {} is out of the sequence", index + 1)
+            }
+            Self::Duplicate(index) => write!(f, "symbol {} appears more than once", index + 1),
+            Self::Missing(index) => write!(f, "symbol {} is missing", index + 1),
+            Self::InvalidParity => write!(f, "parity does not match the data"),
+        }
+    }
+}
+
+impl error::Error for JoinError {}
+
+/// Reads bits from the data codewords.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    const fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.position
+    }
+
+    fn take(&mut self, n: usize) -> Result<u32, DeQRError> {
+        if self.remaining() < n {
+            return Err(DeQRError::DataUnderflow);
+        }
+        let mut value = 0;
+        for _ in 0..n {
+            let bit = (self.data[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Reads the data codewords from the grid.
+///
+/// The errors in the data codewords are corrected with the error correction
+/// codewords in the same way as the ordinary decoding.
+fn data_codewords<G: BitGrid>(grid: &Grid<G>) -> Result<(MetaData, Vec<u8>), DeQRError> {
+    let mut codewords = read_codewords(grid)?;
+    correct_codewords(&mut codewords)?;
+    Ok((codewords.meta, codewords.data))
+}
+
+/// Returns the number of bits of the character count indicator.
+const fn char_count_bits(mode: u32, version: usize) -> Result<usize, DeQRError> {
+    let group = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let bits = match mode {
+        0b0001 => [10, 12, 14],
+        0b0010 => [9, 11, 13],
+        0b0100 => [8, 16, 16],
+        0b1000 => [8, 10, 12],
+        _ => return Err(DeQRError::UnknownDataType),
+    };
+    Ok(bits[group])
+}
+
+/// Decodes the segments which follow the header.
+fn decode_segments(reader: &mut BitReader<'_>, version: usize) -> Result<Vec<u8>, DeQRError> {
+    const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+    let mut writer = Vec::new();
+    while reader.remaining() >= 4 {
+        let mode = reader.take(4)?;
+        if mode == 0 {
+            break;
+        }
+        if mode == 0b0111 {
+            // ECI designators are ignored in the same way as rqrr.
+            let eci = reader.take(8)?;
+            if eci & 0xc0 == 0x80 {
+                reader.take(8)?;
+            } else if eci & 0xe0 == 0xc0 {
+                reader.take(16)?;
+            }
+            continue;
+        }
+        let count = reader.take(char_count_bits(mode, version)?)?;
+        match mode {
+            0b0001 => {
+                let mut remaining = count;
+                while remaining > 0 {
+                    let (digits, bits) = match remaining {
+                        1 => (1, 4),
+                        2 => (2, 7),
+                        _ => (3, 10),
+                    };
+                    let value = reader.take(bits)?;
+                    if value >= 10_u32.pow(digits) {
+                        return Err(DeQRError::DataOverflow);
+                    }
+                    writer.extend(format!("{value:0width$}", width = digits as usize).bytes());
+                    remaining -= digits;
+                }
+            }
+            0b0010 => {
+                let mut remaining = count;
+                while remaining > 0 {
+                    let (chars, bits) = if remaining == 1 { (1, 6) } else { (2, 11) };
+                    let value =
+                        usize::try_from(reader.take(bits)?).map_err(|_| DeQRError::DataOverflow)?;
+                    let (first, second) = (value / 45, value % 45);
+                    if chars == 2 {
+                        writer.push(*ALPHANUMERIC.get(first).ok_or(DeQRError::DataOverflow)?);
+                    }
+                    writer.push(ALPHANUMERIC[second]);
+                    remaining -= chars;
+                }
+            }
+            0b0100 => {
+                for _ in 0..count {
+                    writer
+                        .push(u8::try_from(reader.take(8)?).map_err(|_| DeQRError::DataOverflow)?);
+                }
+            }
+            _ => {
+                for _ in 0..count {
+                    let value = reader.take(13)?;
+                    let value = ((value / 0xc0) << 8) | (value % 0xc0);
+                    let value = if value + 0x8140 <= 0x9ffc {
+                        value + 0x8140
+                    } else {
+                        value + 0xc140
+                    };
+                    let value = u16::try_from(value).map_err(|_| DeQRError::DataOverflow)?;
+                    writer.extend(value.to_be_bytes());
+                }
+            }
+        }
+    }
+    Ok(writer)
+}
+
+/// Decodes the grid if it is in Structured Append mode.
+///
+/// Returns [`None`] if the grid is not in Structured Append mode.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the grid cannot be read, or if the data is damaged.
+pub fn decode_grid<G: BitGrid>(grid: &Grid<G>) -> Result<Option<Symbol>, DeQRError> {
+    let (meta, data) = data_codewords(grid)?;
+    let mut reader = BitReader::new(&data);
+    if reader.take(4)? != MODE_INDICATOR {
+        return Ok(None);
+    }
+    let header = Header {
+        index: u8::try_from(reader.take(4)?).map_err(|_| DeQRError::DataOverflow)?,
+        total: u8::try_from(reader.take(4)? + 1).map_err(|_| DeQRError::DataOverflow)?,
+        parity: u8::try_from(reader.take(8)?).map_err(|_| DeQRError::DataOverflow)?,
+    };
+    let contents = decode_segments(&mut reader, meta.version.0)?;
+    Ok(Some((header, (meta, contents))))
+}
+
+fn detect_and_decode(image: GrayImage) -> Result<Vec<Option<Symbol>>, DeQRError> {
+    let mut image = PreparedImage::prepare(image);
+    image.detect_grids().iter().map(decode_grid).collect()
+}
+
+/// Detects and decodes QR codes in Structured Append mode in the grayscale
+/// image.
+///
+/// [`None`] is returned for each QR code which is not in Structured Append
+/// mode. Like [`decode`](crate::decode::decode), this also tries with the
+/// inverted image if no QR code can be decoded.
+///
+/// # Errors
+///
+/// Returns [`Err`] if decoding a detected QR code fails.
+pub fn decode(mut image: GrayImage) -> Result<Vec<Option<Symbol>>, DeQRError> {
+    match detect_and_decode(image.clone()) {
+        Err(e) => {
+            imageops::invert(&mut image);
+            detect_and_decode(image).map_err(|_| e)
+        }
+        Ok(symbols) if symbols.is_empty() => {
+            imageops::invert(&mut image);
+            Ok(detect_and_decode(image).unwrap_or(symbols))
+        }
+        Ok(symbols) => Ok(symbols),
+    }
+}
+
+/// Reassembles the contents of the symbols in the order of their positions.
+///
+/// The same symbol may appear more than once.
+///
+/// # Errors
+///
+/// Returns [`Err`] if the symbols are not a complete and consistent sequence,
+/// or if the parity does not match the reassembled data.
+pub fn join(symbols: impl IntoIterator<Item = Option<Symbol>>) -> Result<Vec<u8>, JoinError> {
+    let mut sequence = Vec::<Option<Vec<u8>>>::new();
+    let mut first = None;
+    for symbol in symbols {
+        let (header, (_, contents)) = symbol.ok_or(JoinError::NotStructuredAppend)?;
+        let first = *first.get_or_insert(header);
+        if header.total != first.total {
+            return Err(JoinError::TotalMismatch);
+        }
+        if header.parity != first.parity {
+            return Err(JoinError::ParityMismatch);
+        }
+        if header.index >= header.total {
+            return Err(JoinError::InvalidPosition(header.index));
+        }
+        sequence.resize(usize::from(header.total), None);
+        match sequence[usize::from(header.index)] {
+            Some(ref c) if c != &contents => return Err(JoinError::Duplicate(header.index)),
+            _ => sequence[usize::from(header.index)] = Some(contents),
+        }
+    }
+    let first = first.ok_or(JoinError::Empty)?;
+    let data = sequence
+        .into_iter()
+        .zip(0..)
+        .map(|(contents, index)| contents.ok_or(JoinError::Missing(index)))
+        .collect::<Result<Vec<_>, _>>()?
+        .concat();
+    if data.iter().fold(0, |parity, byte| parity ^ byte) != first.parity {
+        return Err(JoinError::InvalidParity);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(index: u8, total: u8, parity: u8, contents: &[u8]) -> Symbol {
+        let meta = MetaData {
+            version: rqrr::Version(1),
+            ecc_level: 0,
+            mask: 0,
+        };
+        (
+            Header {
+                index,
+                total,
+                parity,
+            },
+            (meta, contents.to_vec()),
+        )
+    }
+
+    #[test]
+    fn read_bits() {
+        let mut reader = BitReader::new(&[0b1010_0101, 0xff]);
+        assert_eq!(reader.remaining(), 16);
+        assert_eq!(reader.take(3).unwrap(), 0b101);
+        assert_eq!(reader.take(9).unwrap(), 0b0_0101_1111);
+        assert_eq!(reader.remaining(), 4);
+        assert_eq!(reader.take(5).unwrap_err(), DeQRError::DataUnderflow);
+    }
+
+    #[test]
+    fn decode_segments_in_each_mode() {
+        // Numeric "01234567", alphanumeric "AC-42", byte "a" and kanji "点".
+        let data = [
+            0x10, 0x20, 0x0c, 0x56, 0x61, 0x90, 0x14, 0xe7, 0x73, 0x90, 0x90, 0x05, 0x86, 0x00,
+            0x5b, 0x3e, 0x00,
+        ];
+        let mut reader = BitReader::new(&data);
+        assert_eq!(
+            decode_segments(&mut reader, 1).unwrap(),
+            [b"01234567AC-42a".as_slice(), &[0x93, 0x5f]].concat()
+        );
+    }
+
+    #[test]
+    fn join_symbols() {
+        let parity = b"QR code".iter().fold(0, |parity, byte| parity ^ byte);
+        assert_eq!(
+            join(
+                [
+                    symbol(1, 3, parity, b"co"),
+                    symbol(0, 3, parity, b"QR "),
+                    symbol(2, 3, parity, b"de"),
+                    symbol(1, 3, parity, b"co"),
+                ]
+                .map(Some)
+            )
+            .unwrap(),
+            b"QR code"
+        );
+        assert_eq!(join([]).unwrap_err(), JoinError::Empty);
+        assert_eq!(
+            join([Some(symbol(0, 2, parity, b"QR ")), None]).unwrap_err(),
+            JoinError::NotStructuredAppend
+        );
+        assert_eq!(
+            join([symbol(0, 2, parity, b"QR "), symbol(1, 3, parity, b"code")].map(Some))
+                .unwrap_err(),
+            JoinError::TotalMismatch
+        );
+        assert_eq!(
+            join([symbol(0, 2, parity, b"QR "), symbol(1, 2, 0, b"code")].map(Some)).unwrap_err(),
+            JoinError::ParityMismatch
+        );
+        assert_eq!(
+            join([symbol(0, 2, parity, b"QR "), symbol(0, 2, parity, b"code")].map(Some))
+                .unwrap_err(),
+            JoinError::Duplicate(0)
+        );
+        assert_eq!(
+            join([symbol(0, 3, parity, b"QR "), symbol(2, 3, parity, b"de")].map(Some))
+                .unwrap_err(),
+            JoinError::Missing(1)
+        );
+        assert_eq!(
+            join([symbol(0, 2, parity, b"QR "), symbol(1, 2, parity, b"codf")].map(Some))
+                .unwrap_err(),
+            JoinError::InvalidParity
+        );
+    }
+}
@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::{error, fmt};
+
+/// An error which is returned when the decoded data does not match the
+/// expected data.
+#[derive(Debug, Eq, PartialEq)]
+pub struct MismatchError {
+    expected: Vec<u8>,
+    actual: Option<Vec<u8>>,
+}
+
+impl fmt::Display for MismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.actual {
+            Some(ref actual) => write!(
+                f,
+                "expected \"{}\", but decoded \"{}\"",
+                self.expected.escape_ascii(),
+                actual.escape_ascii()
+            ),
+            None => write!(
+                f,
+                "expected \"{}\", but no QR code was decoded",
+                self.expected.escape_ascii()
+            ),
+        }
+    }
+}
+
+impl error::Error for MismatchError {}
+
+/// Checks that the decoded data matches the expected data.
+pub fn verify(expected: &[u8], actual: &[u8]) -> Result<(), MismatchError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(MismatchError {
+            expected: expected.to_vec(),
+            actual: Some(actual.to_vec()),
+        })
+    }
+}
+
+/// Checks that all of the decoded data match the expected data.
+///
+/// Returns [`Err`] if no data was decoded, so that an image without a QR code
+/// does not pass the check.
+pub fn verify_all<'a>(
+    expected: &[u8],
+    actual: impl IntoIterator<Item = &'a [u8]>,
+) -> Result<(), MismatchError> {
+    let mut actual = actual.into_iter().peekable();
+    if actual.peek().is_none() {
+        return Err(MismatchError {
+            expected: expected.to_vec(),
+            actual: None,
+        });
+    }
+    actual.try_for_each(|actual| verify(expected, actual))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_data() {
+        assert!(verify(b"QR code", b"QR code").is_ok());
+        let err = verify(b"QR code", b"QR\ncode\xff").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"expected "QR code", but decoded "QR\ncode\xff""#
+        );
+    }
+
+    #[test]
+    fn verify_all_data() {
+        assert!(verify_all(b"QR code", [b"QR code".as_slice(), b"QR code"]).is_ok());
+        assert!(verify_all(b"QR code", [b"QR code".as_slice(), b"QR"]).is_err());
+        let err = verify_all(b"QR code", []).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"expected "QR code", but no QR code was decoded"#
+        );
+    }
+}
@@ -0,0 +1,19 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+use std::{process, thread, time::Duration};
+
+use crate::exit_code::ExitStatus;
+
+/// Spawns a thread which terminates the process when the timeout expires.
+///
+/// The process exits with [`ExitStatus::TempFail`] unless it finishes before
+/// the timeout.
+pub fn spawn(timeout: Duration) {
+    thread::spawn(move || {
+        thread::sleep(timeout);
+        eprintln!("Error: could not finish decoding within {timeout:?}");
+        process::exit(ExitStatus::TempFail.code().into());
+    });
+}
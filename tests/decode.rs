@@ -213,6 +213,50 @@ fn decode_from_gif() {
         .stderr(predicate::str::contains("could not read the image"));
 }
 
+#[cfg(feature = "decode-from-gif")]
+#[test]
+fn decode_from_animated_gif() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/animated.gif")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR codeHello"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--metadata")
+        .arg("data/decode/animated.gif")
+        .assert()
+        .success()
+        .stderr(predicate::eq(
+            "Version: 1\nLevel: M\nMask: 3\nWidth: 21\nVersion: 1\nLevel: M\nMask: 2\nWidth: 21\n",
+        ));
+}
+
+#[cfg(feature = "decode-from-gif")]
+#[test]
+fn decode_from_animated_gif_with_print0() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--print0")
+        .arg("data/decode/animated.gif")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code\0Hello\0"));
+}
+
+#[cfg(feature = "decode-from-gif")]
+#[test]
+fn decode_first_from_animated_gif() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--first")
+        .arg("data/decode/animated.gif")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
 #[cfg(feature = "decode-from-hdr")]
 #[test]
 fn decode_from_hdr() {
@@ -409,6 +453,23 @@ fn decode_from_jpeg() {
         .stderr(predicate::str::contains("could not read the image"));
 }
 
+#[cfg(feature = "decode-from-jpeg")]
+#[test]
+fn decode_with_orientation() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/orientation.jpeg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--ignore-orientation")
+        .arg("data/decode/orientation.jpeg")
+        .assert()
+        .stdout(predicate::ne("QR code"));
+}
+
 #[cfg(feature = "decode-from-exr")]
 #[test]
 fn decode_from_open_exr() {
@@ -677,272 +738,1230 @@ fn decode_from_qoi() {
         .stderr(predicate::str::contains("could not read the image"));
 }
 
-#[cfg(feature = "decode-from-svg")]
 #[test]
-fn decode_from_svg() {
+fn decode_with_escape() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--input-encoding")
+        .arg("hex")
+        .arg("1b5b324a5152200d0a636f6465ff")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
     utils::command::command()
         .arg("decode")
-        .arg("data/decode/decode.svg")
+        .arg("--escape")
+        .write_stdin(output.stdout.clone())
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::eq("\\x1b[2JQR \\x0d\ncode\\xff"));
     utils::command::command()
         .arg("decode")
-        .write_stdin(include_bytes!("data/decode/decode.svg"))
+        .write_stdin(output.stdout)
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::eq(b"\x1b[2JQR \r\ncode\xff" as &[u8]));
+}
+
+#[test]
+fn decode_with_print0() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("svg")
-        .arg("data/decode/decode.svg")
+        .arg("--print0")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::eq("QR code\0"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--print0")
+        .arg("--metadata")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--print0' cannot be used with '--metadata'",
+        ));
 }
 
-#[cfg(feature = "decode-from-svg")]
 #[test]
-fn decode_from_svgz() {
+fn decode_with_max_pixels() {
     utils::command::command()
         .arg("decode")
-        .arg("data/decode/decode.svgz")
+        .arg("--max-pixels")
+        .arg("53824")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
     utils::command::command()
         .arg("decode")
-        .write_stdin(include_bytes!("data/decode/decode.svgz"))
+        .arg("--max-pixels")
+        .arg("53823")
+        .arg("data/decode/decode.png")
         .assert()
-        .success()
-        .stdout(predicate::eq("QR code"));
+        .failure()
+        .code(71)
+        .stderr(predicate::str::contains(
+            "the image has more than 53823 pixels",
+        ));
+}
+
+#[test]
+fn decode_with_timeout() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("svg")
-        .arg("data/decode/decode.svgz")
+        .arg("--timeout")
+        .arg("60")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--timeout")
+        .arg("0.000001")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(75)
+        .stderr(predicate::str::contains("could not finish decoding within"));
 }
 
-#[cfg(feature = "decode-from-svg")]
 #[test]
-fn decode_from_svg_with_wrong_format() {
+fn decode_with_invalid_timeout() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("svg")
+        .arg("--timeout")
+        .arg("0")
         .arg("data/decode/decode.png")
         .assert()
         .failure()
-        .code(69)
-        .stderr(predicate::str::contains("could not read the image"));
+        .code(2)
+        .stderr(predicate::str::contains(
+            "timeout must be a positive number of seconds",
+        ));
 }
 
-#[cfg(feature = "decode-from-tga")]
 #[test]
-fn decode_from_tga() {
+fn decode_from_data_uri() {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
     utils::command::command()
         .arg("decode")
-        .arg("data/decode/decode.tga")
+        .arg("--data-uri")
+        .arg(format!(
+            "data:image/png;base64,{}",
+            STANDARD.encode(include_bytes!("data/decode/decode.png"))
+        ))
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
+}
+
+#[test]
+fn decode_from_invalid_data_uri() {
     utils::command::command()
         .arg("decode")
-        .write_stdin(include_bytes!("data/decode/decode.tga"))
+        .arg("--data-uri")
+        .arg("data:image/png,QR%20code")
         .assert()
         .failure()
-        .code(69)
+        .code(2)
         .stderr(predicate::str::contains(
-            "could not determine the image format",
+            "data URI is not encoded in base64",
         ));
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("tga")
-        .arg("data/decode/decode.tga")
+        .arg("--data-uri")
+        .arg("data:image/png;base64,UVIg*29kZQ==")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("invalid base64 data"));
+}
+
+#[test]
+fn decode_with_preprocess() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/saturation.png")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    utils::command::command()
+        .arg("decode")
+        .arg("--preprocess")
+        .arg("saturation")
+        .arg("data/decode/saturation.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--preprocess")
+        .arg("luminance")
+        .arg("data/decode/saturation.png")
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
+}
 
+#[test]
+fn decode_with_invalid_preprocess() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("tga")
-        .arg("data/decode/decode.svg")
+        .arg("--preprocess")
+        .arg("hue")
+        .arg("data/decode/decode.png")
         .assert()
         .failure()
-        .code(69)
-        .stderr(predicate::str::contains("could not read the image"));
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'hue' for '--preprocess <METHOD>'",
+        ));
 }
 
-#[cfg(feature = "decode-from-tiff")]
 #[test]
-fn decode_from_tiff() {
+fn decode_mirrored() {
     utils::command::command()
         .arg("decode")
-        .arg("data/decode/decode.tiff")
+        .arg("data/decode/mirror.png")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/mirror.png")
+        .arg("--mirror")
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
     utils::command::command()
         .arg("decode")
-        .write_stdin(include_bytes!("data/decode/decode.tiff"))
+        .arg("--mirror")
+        .arg("auto")
+        .arg("data/decode/mirror.png")
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("tiff")
-        .arg("data/decode/decode.tiff")
+        .arg("--mirror")
+        .arg("auto")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
+}
 
+#[test]
+fn decode_with_invalid_mirror_mode() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("tiff")
-        .arg("data/decode/decode.svg")
+        .arg("--mirror")
+        .arg("vertical")
+        .arg("data/decode/mirror.png")
         .assert()
         .failure()
-        .code(65)
-        .stderr(predicate::str::contains("could not read the image"));
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'vertical' for '--mirror [<MODE>]'",
+        ));
 }
 
-#[cfg(feature = "decode-from-webp")]
 #[test]
-fn decode_from_lossy_web_p() {
+fn decode_with_error_details() {
     utils::command::command()
         .arg("decode")
-        .arg("data/decode/lossy.webp")
+        .arg("--error-details")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::eq("QR code"))
+        .stderr(predicate::eq("Errors: 0 codewords corrected\n"));
     utils::command::command()
         .arg("decode")
-        .write_stdin(include_bytes!("data/decode/lossy.webp"))
+        .arg("--error-details")
+        .arg("data/decode/damaged.png")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::eq("QR code"))
+        .stderr(predicate::eq(
+            "Errors: 3 codewords corrected (block 0: 1, block 1: 1, block 3: 1)\n",
+        ));
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("webp")
-        .arg("data/decode/lossy.webp")
+        .arg("--error-details")
+        .arg("--mirror")
+        .arg("auto")
+        .arg("data/decode/mirror.png")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::eq("QR code"))
+        .stderr(predicate::eq("Errors: 0 codewords corrected\n"));
 }
 
-#[cfg(feature = "decode-from-webp")]
 #[test]
-fn decode_from_lossless_web_p() {
+fn decode_with_dump() {
     utils::command::command()
         .arg("decode")
-        .arg("data/decode/lossless.webp")
+        .arg("--dump")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::eq(include_str!("data/matrix/matrix.txt")));
     utils::command::command()
         .arg("decode")
-        .write_stdin(include_bytes!("data/decode/lossless.webp"))
+        .arg("--dump")
+        .arg("data/decode/structured_append.png")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::function(|output: &str| {
+            output.split("\n\n").count() == 3
+        }));
+}
+
+#[test]
+fn decode_with_list_formats() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("webp")
-        .arg("data/decode/lossless.webp")
+        .arg("--list-formats")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::str::contains("png Portable Network Graphics\n"));
 }
 
-#[cfg(feature = "decode-from-webp")]
+#[cfg(feature = "decode-from-svg")]
 #[test]
-fn decode_from_web_p_with_wrong_format() {
+fn decode_with_list_formats_with_svg() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("webp")
-        .arg("data/decode/decode.svg")
+        .arg("--list-formats")
         .assert()
-        .failure()
-        .code(65)
-        .stderr(predicate::str::contains("could not read the image"));
+        .success()
+        .stdout(predicate::str::contains("\nsvg Scalable Vector Graphics\n"));
 }
 
 #[test]
-fn decode_from_invalid_input_format() {
+fn decode_with_list_formats_and_input() {
     utils::command::command()
         .arg("decode")
-        .arg("-t")
-        .arg("a")
+        .arg("--list-formats")
         .arg("data/decode/decode.png")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'a' for '--type <FORMAT>'",
+            "the argument '--list-formats' cannot be used with",
         ));
 }
 
 #[test]
-fn decode_with_invert() {
+fn decode_with_benchmark() {
     utils::command::command()
         .arg("decode")
-        .arg("data/invert/basic.png")
+        .arg("--benchmark")
+        .arg("3")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
-        .stdout(predicate::eq("QR code"));
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_match("^Decode: min .+, median .+, max .+\n$").unwrap());
 }
 
-#[cfg(feature = "decode-from-svg")]
 #[test]
-fn decode_from_svg_with_invert() {
+fn decode_with_expect() {
     utils::command::command()
         .arg("decode")
-        .arg("data/invert/basic.svg")
+        .arg("--expect")
+        .arg("QR code")
+        .arg("data/decode/decode.png")
         .assert()
         .success()
         .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--expect")
+        .arg("QR")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(3)
+        .stdout(predicate::eq("QR code"))
+        .stderr(predicate::str::contains(
+            "the decoded data does not match the expected data\n\nCaused by:\n    expected \"QR\", but decoded \"QR code\"\n",
+        ));
 }
 
 #[test]
-fn decode_with_verbose() {
+fn decode_with_expect_when_no_qr_code_is_found() {
     utils::command::command()
         .arg("decode")
-        .arg("--verbose")
-        .arg("data/basic/basic.png")
+        .arg("--expect")
+        .arg("QR code")
+        .arg("data/decode/blank.png")
         .assert()
-        .success()
-        .stdout(predicate::ne(&[] as &[u8]))
-        .stderr(predicate::eq("Version: 1\nLevel: M\n"));
+        .failure()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "expected \"QR code\", but no QR code was decoded",
+        ));
+    utils::command::command()
+        .arg("decode")
+        .arg("--expect")
+        .arg("QR code")
+        .arg("data/decode/decode.png")
+        .arg("data/decode/blank.png")
+        .assert()
+        .failure()
+        .code(3)
+        .stderr(predicate::str::contains(
+            "could not decode data/decode/blank.png",
+        ));
 }
 
 #[test]
-fn decode_with_metadata() {
+fn decode_with_expect_file() {
     utils::command::command()
         .arg("decode")
-        .arg("--metadata")
-        .arg("data/basic/basic.png")
+        .arg("--expect-file")
+        .arg("data/encode/data.txt")
+        .arg("data/decode/decode.png")
         .assert()
-        .success()
-        .stdout(predicate::eq(&[] as &[u8]))
-        .stderr(predicate::eq("Version: 1\nLevel: M\n"));
+        .success();
+    utils::command::command()
+        .arg("decode")
+        .arg("--expect-file")
+        .arg("data/encode/data_1.txt")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(3);
+    utils::command::command()
+        .arg("decode")
+        .arg("--expect-file")
+        .arg("data/nonexistent.txt")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(66)
+        .stderr(predicate::str::contains(
+            "could not read the expected data from data/nonexistent.txt",
+        ));
 }
 
 #[test]
-fn validate_the_options_dependencies_for_decode_command() {
+fn decode_with_expect_and_join() {
     utils::command::command()
         .arg("decode")
-        .arg("--verbose")
+        .arg("--join")
+        .arg("--expect")
+        .arg("QR code in Structured Append mode")
+        .arg("data/decode/structured_append.png")
+        .assert()
+        .success();
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("--expect")
+        .arg("QR code")
+        .arg("data/decode/structured_append.png")
+        .assert()
+        .failure()
+        .code(3);
+}
+
+#[test]
+fn decode_with_join() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("data/decode/structured_append.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code in Structured Append mode"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("data/decode/structured_append_3.png")
+        .arg("data/decode/structured_append_1.png")
+        .arg("data/decode/structured_append_2.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code in Structured Append mode"));
+}
+
+#[test]
+fn decode_with_join_when_symbol_is_damaged() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("data/decode/structured_append_1_damaged.png")
+        .arg("data/decode/structured_append_2.png")
+        .arg("data/decode/structured_append_3.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code in Structured Append mode"));
+}
+
+#[test]
+fn decode_with_join_and_verbose() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("--verbose")
+        .arg("data/decode/structured_append_2.png")
+        .arg("data/decode/structured_append_1.png")
+        .arg("data/decode/structured_append_3.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code in Structured Append mode"))
+        .stderr(predicate::str::starts_with(
+            "Position: 2/3\nVersion: 2\nLevel: M\nMask: 0\nWidth: 25\nPosition: 1/3\n",
+        ));
+}
+
+#[test]
+fn decode_with_join_when_symbol_is_missing() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("data/decode/structured_append_1.png")
+        .arg("data/decode/structured_append_3.png")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "could not reassemble the structured append symbols",
+        ))
+        .stderr(predicate::str::contains("symbol 2 is missing"));
+}
+
+#[test]
+fn decode_with_join_when_parity_is_mismatched() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("data/decode/structured_append_invalid_parity.png")
+        .arg("data/decode/structured_append_2.png")
+        .arg("data/decode/structured_append_3.png")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("parities of symbols do not match"));
+}
+
+#[test]
+fn decode_with_join_when_not_structured_append() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--join")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "QR code is not in Structured Append mode",
+        ));
+}
+
+#[test]
+fn decode_multiple_images() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/decode.png")
+        .arg("data/decode/decode.bmp")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR codeQR code"));
+}
+
+#[test]
+fn decode_multiple_images_with_invalid_image() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/decode.png")
+        .arg("non_existent.png")
+        .arg("data/decode/decode.bmp")
+        .assert()
+        .failure()
+        .code(66)
+        .stdout(predicate::eq("QR codeQR code"))
+        .stderr(predicate::str::contains(
+            "Warning: could not decode non_existent.png",
+        ))
+        .stderr(predicate::str::contains("could not decode 1 of 3 files"));
+}
+
+#[test]
+fn decode_multiple_images_with_all_invalid_images() {
+    utils::command::command()
+        .arg("decode")
+        .arg("non_existent_1.png")
+        .arg("non_existent_2.png")
+        .assert()
+        .failure()
+        .code(66)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(
+            "Warning: could not decode non_existent_1.png",
+        ))
+        .stderr(predicate::str::contains(
+            "Warning: could not decode non_existent_2.png",
+        ))
+        .stderr(predicate::str::contains("could not decode 2 of 2 files"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--quiet")
+        .arg("non_existent_1.png")
+        .arg("non_existent_2.png")
+        .assert()
+        .failure()
+        .code(66)
+        .stderr(predicate::str::starts_with("Error: could not decode 2 of 2 files"));
+}
+
+#[test]
+fn decode_first_with_join() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--first")
+        .arg("--join")
+        .arg("data/decode/structured_append.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--first' cannot be used with '--join'",
+        ));
+}
+
+#[test]
+fn decode_multiple_images_with_strict() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--strict")
+        .arg("data/decode/decode.png")
+        .arg("non_existent.png")
+        .arg("data/decode/decode.bmp")
+        .assert()
+        .failure()
+        .code(66)
+        .stdout(predicate::eq("QR code"))
+        .stderr(predicate::str::contains(
+            "could not decode non_existent.png",
+        ));
+}
+
+#[test]
+fn decode_with_mosaic() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--mosaic")
+        .arg("2x2")
+        .arg("data/decode/mosaic_1.png")
+        .arg("data/decode/mosaic_2.png")
+        .arg("data/decode/mosaic_3.png")
+        .arg("data/decode/mosaic_4.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[test]
+fn decode_with_misordered_mosaic() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--mosaic")
+        .arg("2x2")
+        .arg("data/decode/mosaic_2.png")
+        .arg("data/decode/mosaic_1.png")
+        .arg("data/decode/mosaic_3.png")
+        .arg("data/decode/mosaic_4.png")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "could not find a QR code in the stitched image",
+        ));
+}
+
+#[test]
+fn decode_with_mismatched_mosaic() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--mosaic")
+        .arg("2x2")
+        .arg("data/decode/mosaic_1.png")
+        .arg("data/decode/mosaic_2.png")
+        .arg("data/decode/mosaic_3.png")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "3 images cannot be stitched in a grid of 2x2",
+        ));
+    utils::command::command()
+        .arg("decode")
+        .arg("--mosaic")
+        .arg("2x2")
+        .arg("data/decode/mosaic_1.png")
+        .arg("data/decode/mosaic_2.png")
+        .arg("data/decode/mosaic_3.png")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "tile 4 is 232x232 pixels, but tile 1 is 116x116 pixels",
+        ));
+}
+
+#[cfg(feature = "clipboard")]
+#[test]
+fn decode_with_clipboard_and_input() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--clipboard")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--clipboard' cannot be used with '[IMAGE]...'",
+        ));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_svg() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(include_bytes!("data/decode/decode.svg"))
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("svg")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_svg_with_use_elements() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/repeat.svg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR codeQR code"));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_optimized_svg() {
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("svg")
+        .arg("data/decode/optimize.svg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_svgz() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/decode.svgz")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(include_bytes!("data/decode/decode.svgz"))
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("svg")
+        .arg("data/decode/decode.svgz")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_svg_with_scale() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--svg-scale")
+        .arg("4")
+        .arg("data/decode/small_module.svg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--svg-scale")
+        .arg("16")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_svg_with_invalid_scale() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--svg-scale")
+        .arg("0")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--svg-scale <FACTOR>': 0 is not in 1..=16",
+        ));
+    utils::command::command()
+        .arg("decode")
+        .arg("--svg-scale")
+        .arg("17")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '17' for '--svg-scale <FACTOR>': 17 is not in 1..=16",
+        ));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_svg_with_wrong_format() {
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("svg")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(69)
+        .stderr(predicate::str::contains("could not read the image"));
+}
+
+#[cfg(feature = "decode-from-tga")]
+#[test]
+fn decode_from_tga() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/decode.tga")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(include_bytes!("data/decode/decode.tga"))
+        .assert()
+        .failure()
+        .code(69)
+        .stderr(predicate::str::contains(
+            "could not determine the image format",
+        ));
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("tga")
+        .arg("data/decode/decode.tga")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("tga")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .failure()
+        .code(69)
+        .stderr(predicate::str::contains("could not read the image"));
+}
+
+#[cfg(feature = "decode-from-tiff")]
+#[test]
+fn decode_from_tiff() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/decode.tiff")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(include_bytes!("data/decode/decode.tiff"))
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("tiff")
+        .arg("data/decode/decode.tiff")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("tiff")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not read the image"));
+}
+
+#[cfg(feature = "decode-from-webp")]
+#[test]
+fn decode_from_lossy_web_p() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/lossy.webp")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(include_bytes!("data/decode/lossy.webp"))
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("webp")
+        .arg("data/decode/lossy.webp")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[cfg(feature = "decode-from-webp")]
+#[test]
+fn decode_from_lossless_web_p() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/decode/lossless.webp")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(include_bytes!("data/decode/lossless.webp"))
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("webp")
+        .arg("data/decode/lossless.webp")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[cfg(feature = "decode-from-webp")]
+#[test]
+fn decode_from_web_p_with_wrong_format() {
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("webp")
+        .arg("data/decode/decode.svg")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not read the image"));
+}
+
+#[test]
+fn decode_from_invalid_input_format() {
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("a")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'a' for '--type <FORMAT>'",
+        ));
+}
+
+#[test]
+fn decode_with_invert() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/invert/basic.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[cfg(feature = "decode-from-svg")]
+#[test]
+fn decode_from_svg_with_invert() {
+    utils::command::command()
+        .arg("decode")
+        .arg("data/invert/basic.svg")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[test]
+fn decode_with_min_size() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--min-size")
+        .arg("300")
+        .arg("data/decode/tiny.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--min-size")
+        .arg("300")
+        .arg("--resize-filter")
+        .arg("nearest")
+        .arg("data/decode/tiny.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--min-size")
+        .arg("10")
+        .arg("data/decode/decode.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[test]
+fn decode_with_invalid_min_size() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--min-size")
+        .arg("0")
+        .arg("data/decode/tiny.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--min-size <PIXELS>': 0 is not in 1..=16384",
+        ));
+}
+
+#[test]
+fn decode_with_resize_filter_without_min_size() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--resize-filter")
+        .arg("lanczos3")
+        .arg("data/decode/tiny.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ))
+        .stderr(predicate::str::contains("--min-size <PIXELS>"));
+}
+
+#[test]
+fn decode_with_max_size() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--max-size")
+        .arg("200")
+        .arg("data/decode/large.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    for filter in ["nearest", "triangle", "lanczos3"] {
+        utils::command::command()
+            .arg("decode")
+            .arg("--max-size")
+            .arg("200")
+            .arg("--scale-filter")
+            .arg(filter)
+            .arg("data/decode/large.png")
+            .assert()
+            .success()
+            .stdout(predicate::eq("QR code"));
+    }
+}
+
+#[test]
+fn decode_with_invalid_max_size() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--max-size")
+        .arg("0")
+        .arg("data/decode/large.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--max-size <PIXELS>'",
+        ));
+    utils::command::command()
+        .arg("decode")
+        .arg("--min-size")
+        .arg("300")
+        .arg("--max-size")
+        .arg("200")
+        .arg("data/decode/large.png")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "minimum size is greater than the maximum size",
+        ));
+}
+
+#[test]
+fn decode_with_scale_filter_without_max_size() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--scale-filter")
+        .arg("lanczos3")
+        .arg("data/decode/large.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ))
+        .stderr(predicate::str::contains("--max-size <PIXELS>"));
+}
+
+#[test]
+fn decode_with_threshold() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--threshold")
+        .arg("128")
+        .arg("data/decode/decode.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("decode")
+        .arg("--threshold")
+        .arg("otsu")
+        .arg("data/decode/decode.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
+#[test]
+fn decode_with_invalid_threshold() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--threshold")
+        .arg("256")
+        .arg("data/decode/decode.png")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '256' for '--threshold <THRESHOLD>': threshold must be a number between 0 and 255, or \"otsu\"",
+        ));
+}
+
+#[test]
+fn decode_with_verbose() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--verbose")
+        .arg("data/basic/basic.png")
+        .assert()
+        .success()
+        .stdout(predicate::ne(&[] as &[u8]))
+        .stderr(predicate::eq("Version: 1\nLevel: M\nMask: 3\nWidth: 21\n"));
+}
+
+#[test]
+fn decode_with_metadata() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--metadata")
+        .arg("data/basic/basic.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq(&[] as &[u8]))
+        .stderr(predicate::eq("Version: 1\nLevel: M\nMask: 3\nWidth: 21\n"));
+}
+
+#[test]
+fn decode_with_quiet() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--quiet")
+        .arg("data/basic/basic.png")
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn validate_the_options_dependencies_for_decode_command() {
+    utils::command::command()
+        .arg("decode")
+        .arg("--verbose")
+        .arg("--metadata")
+        .assert()
+        .failure()
+        .code(2);
+    utils::command::command()
+        .arg("decode")
+        .arg("--quiet")
+        .arg("--verbose")
+        .assert()
+        .failure()
+        .code(2);
+    utils::command::command()
+        .arg("decode")
+        .arg("--quiet")
         .arg("--metadata")
         .assert()
         .failure()
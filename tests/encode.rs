@@ -98,6 +98,37 @@ fn encode_from_file() {
     assert!(output.status.success());
 }
 
+#[test]
+fn encode_from_multiple_files() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/encode/data_1.txt")
+        .arg("-r")
+        .arg("data/encode/data_2.txt")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/basic/basic.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_from_multiple_files_with_non_existent_file() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/encode/data_1.txt")
+        .arg("-r")
+        .arg("non_existent.txt")
+        .assert()
+        .failure()
+        .code(66)
+        .stderr(predicate::str::contains("could not open non_existent.txt"));
+}
+
 #[test]
 fn encode_from_non_existent_file() {
     let command = utils::command::command()
@@ -163,6 +194,84 @@ fn encode_to_pic_with_module_size() {
         .stdout(predicate::eq(include_str!("data/module_size/3.pic")));
 }
 
+#[test]
+fn encode_to_c_array() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("c-array")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(concat!(
+            "#include <stdint.h>\n\n",
+            "#define QR_WIDTH 29\n\n",
+            "const uint8_t qr[116] = {\n",
+            "    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,\n",
+            "    0x00, 0x00, 0x00, 0x00, 0x0f, 0xea, 0xbf, 0x80, 0x08, 0x2a, 0xa0, 0x80,\n",
+        )))
+        .stdout(predicate::str::ends_with(
+            "    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,\n};\n",
+        ));
+}
+
+#[test]
+fn encode_to_rust_array() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("rust-array")
+        .arg("-s")
+        .arg("2")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(concat!(
+            "pub const QR_WIDTH: usize = 58;\n\n",
+            "pub const QR: [u8; 464] = [\n",
+        )))
+        .stdout(predicate::str::ends_with(",\n];\n"));
+}
+
+#[test]
+fn encode_to_c_array_with_foreground() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("c-array")
+        .arg("--foreground")
+        .arg("red")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "foreground and/or background colors cannot be changed",
+        ));
+}
+
+#[cfg(feature = "decode-from-pnm")]
+#[test]
+fn encode_to_pbm() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("pbm")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.starts_with(b"P4\n232 232\n"));
+    utils::command::command()
+        .arg("decode")
+        .arg("-t")
+        .arg("pnm")
+        .write_stdin(output.stdout)
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+}
+
 #[cfg(feature = "output-as-ansi")]
 #[test]
 fn encode_to_ansi_with_module_size() {
@@ -304,6 +413,150 @@ fn encode_with_invalid_module_size() {
         .stderr(predicate::str::contains(
             "number too large to fit in target type",
         ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("-s")
+        .arg("0%")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '0%' for '--size <NUMBER>'",
+        ))
+        .stderr(predicate::str::contains(
+            "number would be zero for non-zero type",
+        ));
+}
+
+#[test]
+fn encode_with_physical_size() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--physical-size")
+        .arg("25mm")
+        .arg("--dpi")
+        .arg("300")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let decoder = png::Decoder::new(output.stdout.as_slice());
+    let reader = decoder.read_info().unwrap();
+    let info = reader.info();
+    assert_eq!((info.width, info.height), (290, 290));
+    let dims = info.pixel_dims.unwrap();
+    assert_eq!((dims.xppu, dims.yppu), (11811, 11811));
+    assert_eq!(dims.unit, png::Unit::Meter);
+}
+
+#[test]
+fn encode_with_physical_size_and_pad() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--physical-size")
+        .arg("25mm")
+        .arg("--dpi")
+        .arg("300")
+        .arg("--pad")
+        .arg("center")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let image = image::load_from_memory(&output.stdout).unwrap().to_luma8();
+    assert_eq!(image.dimensions(), (295, 295));
+    assert_eq!(image.get_pixel(41, 41).0, [u8::MAX]);
+    assert_eq!(image.get_pixel(42, 42).0, [u8::MIN]);
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(output.stdout)
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--physical-size")
+        .arg("25mm")
+        .arg("--dpi")
+        .arg("300")
+        .arg("--pad")
+        .arg("top-left")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let image = image::load_from_memory(&output.stdout).unwrap().to_luma8();
+    assert_eq!(image.dimensions(), (295, 295));
+    assert_eq!(image.get_pixel(40, 40).0, [u8::MIN]);
+}
+
+#[test]
+fn encode_with_pad_without_physical_size() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--pad")
+        .arg("center")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ));
+}
+
+#[test]
+fn encode_with_invalid_physical_size() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--physical-size")
+        .arg("25")
+        .arg("--dpi")
+        .arg("300")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "length must be a positive number with the unit mm, cm or in",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--physical-size")
+        .arg("25mm")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("--dpi <DPI>"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--physical-size")
+        .arg("1mm")
+        .arg("--dpi")
+        .arg("72")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "the physical size is too small for the resolution",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--dpi")
+        .arg("300")
+        .arg("-t")
+        .arg("svg")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG"));
 }
 
 #[test]
@@ -382,6 +635,100 @@ fn validate_alias_for_error_correction_level_option_of_encode_command() {
     assert!(output.status.success());
 }
 
+#[test]
+fn encode_with_auto_error_correction_level() {
+    for (data, level) in [
+        ("QR code", "H"),
+        ("QR code!", "Q"),
+        ("QR code QR code", "L"),
+    ] {
+        utils::command::command()
+            .arg("encode")
+            .arg("-l")
+            .arg("auto")
+            .arg("-v")
+            .arg("1")
+            .arg("--verbose")
+            .arg(data)
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(format!("Level: {level}\n")));
+    }
+    utils::command::command()
+        .arg("encode")
+        .arg("-l")
+        .arg("auto")
+        .arg("-v")
+        .arg("1")
+        .arg("QR code QR code QR")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_with_auto_error_correction_level_without_symbol_version() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-l")
+        .arg("auto")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "automatic error correction level requires the symbol version",
+        ));
+}
+
+#[test]
+fn encode_with_min_error_correction_level() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-l")
+        .arg("auto")
+        .arg("-v")
+        .arg("1")
+        .arg("--min-ecc")
+        .arg("q")
+        .arg("--verbose")
+        .arg("QR code!")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Level: Q\n"));
+    utils::command::command()
+        .arg("encode")
+        .arg("-l")
+        .arg("auto")
+        .arg("-v")
+        .arg("1")
+        .arg("--min-ecc")
+        .arg("h")
+        .arg("QR code!")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_with_min_error_correction_level_without_auto() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--min-ecc")
+        .arg("q")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "minimum error correction level requires the automatic error correction level",
+        ));
+}
+
 #[test]
 fn encode_with_invalid_error_correction_level() {
     utils::command::command()
@@ -458,48 +805,217 @@ fn encode_with_invalid_symbol_version() {
 }
 
 #[test]
-fn encode_with_margin() {
+fn encode_with_symbol_version_from_stdin() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("-m")
-        .arg("8")
-        .arg("QR code")
+        .arg("-v")
+        .arg("-")
+        .arg("--verbose")
+        .write_stdin("5\nQR code")
         .output()
         .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/margin/8.png").unwrap()
-    );
     assert!(output.status.success());
-}
-
-#[test]
-fn encode_to_svg_with_margin() {
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("Version: 5\n"));
     utils::command::command()
-        .arg("encode")
-        .arg("-m")
-        .arg("8")
-        .arg("-t")
-        .arg("svg")
-        .arg("QR code")
+        .arg("decode")
+        .write_stdin(output.stdout)
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/margin/8.svg")));
+        .stdout(predicate::eq("QR code"));
 }
 
 #[test]
-fn encode_to_pic_with_margin() {
+fn encode_with_invalid_symbol_version_from_stdin() {
     utils::command::command()
         .arg("encode")
-        .arg("-m")
-        .arg("8")
-        .arg("-t")
-        .arg("pic")
+        .arg("-v")
+        .arg("-")
+        .write_stdin("41\nQR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid symbol version \"41\" in standard input: 41 is not in 1..=40",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("-")
         .arg("QR code")
         .assert()
-        .success()
-        .stdout(predicate::eq(include_str!("data/margin/8.pic")));
-}
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "symbol version can only be read from standard input with the input data",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("-")
+        .arg("--mode")
+        .arg("numeric")
+        .arg("--capacity")
+        .write_stdin("5\nQR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "symbol version can only be read from standard input with the input data",
+        ));
+}
+
+#[test]
+fn encode_with_margin() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-m")
+        .arg("8")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/margin/8.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_with_small_margin() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-m")
+        .arg("3")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::eq(
+            "Warning: the margin is smaller than the recommended quiet zone of 4 modules, which may make the QR code hard to scan\n",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-m")
+        .arg("1")
+        .arg("--variant")
+        .arg("micro")
+        .arg("-v")
+        .arg("2")
+        .arg("12")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "recommended quiet zone of 2 modules",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-m")
+        .arg("2")
+        .arg("--variant")
+        .arg("micro")
+        .arg("-v")
+        .arg("2")
+        .arg("12")
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn encode_with_small_margin_without_warning() {
+    for arg in ["--allow-small-margin", "--quiet"] {
+        utils::command::command()
+            .arg("encode")
+            .arg("-m")
+            .arg("0")
+            .arg(arg)
+            .arg("QR code")
+            .assert()
+            .success()
+            .stderr(predicate::str::is_empty());
+    }
+}
+
+#[test]
+fn encode_with_margin_in_pixels() {
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("-m")
+            .arg("20")
+            .arg("--margin-unit")
+            .arg("pixels")
+            .arg("-s")
+            .arg("4")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let image = image::load_from_memory(&output.stdout).unwrap();
+        assert_eq!(image.width(), (21 + 5 * 2) * 4);
+    }
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("-m")
+            .arg("64")
+            .arg("--margin-unit")
+            .arg("pixels")
+            .arg("-t")
+            .arg("svg")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            include_str!("data/margin/8.svg")
+        );
+    }
+    utils::command::command()
+        .arg("encode")
+        .arg("-m")
+        .arg("8")
+        .arg("--margin-unit")
+        .arg("pixels")
+        .arg("-t")
+        .arg("pic")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "recommended quiet zone of 4 modules",
+        ));
+}
+
+#[test]
+fn encode_to_svg_with_margin() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-m")
+        .arg("8")
+        .arg("-t")
+        .arg("svg")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/margin/8.svg")));
+}
+
+#[test]
+fn encode_to_pic_with_margin() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-m")
+        .arg("8")
+        .arg("-t")
+        .arg("pic")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/margin/8.pic")));
+}
 
 #[cfg(feature = "output-as-ansi")]
 #[test]
@@ -609,548 +1125,2664 @@ fn encode_to_unicode_invert_with_margin() {
 }
 
 #[test]
-fn encode_with_invalid_margin() {
-    utils::command::command()
-        .arg("encode")
-        .arg("-m")
-        .arg("-1")
-        .arg("QR code")
-        .assert()
-        .failure()
-        .code(2)
-        .stderr(predicate::str::contains("unexpected argument '-1' found"));
-    utils::command::command()
-        .arg("encode")
-        .arg("-m")
-        .arg("4294967296")
-        .arg("QR code")
-        .assert()
-        .failure()
-        .code(2)
-        .stderr(predicate::str::contains(
-            "invalid value '4294967296' for '--margin <NUMBER>'",
-        ))
-        .stderr(predicate::str::contains(
-            "4294967296 is not in 0..=4294967295",
-        ));
-}
-
-#[test]
-fn encode_to_png() {
+fn encode_with_zero_margin() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("-t")
-        .arg("png")
+        .arg("-m")
+        .arg("0")
         .arg("QR code")
         .output()
         .unwrap();
     assert_eq!(
         DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/encode/encode.png").unwrap()
+        image::open("tests/data/encode/encode.png")
+            .unwrap()
+            .crop_imm(32, 32, 168, 168)
     );
     assert!(output.status.success());
 }
 
 #[test]
-fn encode_to_svg() {
+fn encode_to_svg_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("svg")
         .arg("QR code")
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
+        .stdout(predicate::eq(include_str!("data/margin/0.svg")));
 }
 
 #[test]
-fn encode_to_pic() {
+fn encode_to_pic_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("pic")
         .arg("QR code")
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/encode/encode.pic")));
+        .stdout(predicate::eq(include_str!("data/margin/0.pic")));
 }
 
 #[cfg(feature = "output-as-ansi")]
 #[test]
-fn encode_to_ansi() {
+fn encode_to_ansi_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("ansi")
         .arg("QR code")
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/encode/ansi.txt")));
+        .stdout(predicate::eq(include_str!("data/margin/0_ansi.txt")));
 }
 
 #[cfg(feature = "output-as-ansi")]
 #[test]
-fn encode_to_ansi_256() {
+fn encode_to_ansi_256_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("ansi256")
         .arg("QR code")
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/encode/ansi_256.txt")));
+        .stdout(predicate::eq(include_str!("data/margin/0_ansi_256.txt")));
 }
 
 #[cfg(feature = "output-as-ansi")]
 #[test]
-fn encode_to_ansi_true_color() {
+fn encode_to_ansi_true_color_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("ansi-true-color")
         .arg("QR code")
         .assert()
         .success()
         .stdout(predicate::eq(include_str!(
-            "data/encode/ansi_true_color.txt"
+            "data/margin/0_ansi_true_color.txt"
         )));
 }
 
 #[test]
-fn encode_to_ascii() {
+fn encode_to_ascii_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("ascii")
         .arg("QR code")
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/encode/ascii.txt")));
+        .stdout(predicate::eq(include_str!("data/margin/0_ascii.txt")));
 }
 
 #[test]
-fn encode_to_ascii_invert() {
+fn encode_to_ascii_invert_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("ascii-invert")
         .arg("QR code")
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/encode/ascii_invert.txt")));
-    utils::command::command()
-        .arg("encode")
-        .arg("-t")
-        .arg("ASCIIi")
-        .arg("QR code")
-        .assert()
-        .success()
-        .stdout(predicate::eq(include_str!("data/encode/ascii_invert.txt")));
+        .stdout(predicate::eq(include_str!(
+            "data/margin/0_ascii_invert.txt"
+        )));
 }
 
 #[test]
-fn encode_to_unicode() {
+fn encode_to_unicode_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("unicode")
         .arg("QR code")
         .assert()
         .success()
-        .stdout(predicate::eq(include_str!("data/encode/unicode.txt")));
-    utils::command::command()
-        .arg("encode")
-        .arg("-t")
-        .arg("terminal")
-        .arg("QR code")
-        .assert()
-        .success()
-        .stdout(predicate::eq(include_str!("data/encode/unicode.txt")));
-    utils::command::command()
-        .arg("encode")
-        .arg("-t")
-        .arg("UTF8")
-        .arg("QR code")
-        .assert()
-        .success()
-        .stdout(predicate::eq(include_str!("data/encode/unicode.txt")));
+        .stdout(predicate::eq(include_str!("data/margin/0_unicode.txt")));
 }
 
 #[test]
-fn encode_to_unicode_invert() {
+fn encode_to_unicode_invert_with_zero_margin() {
     utils::command::command()
         .arg("encode")
+        .arg("-m")
+        .arg("0")
         .arg("-t")
         .arg("unicode-invert")
         .arg("QR code")
         .assert()
         .success()
         .stdout(predicate::eq(include_str!(
-            "data/encode/unicode_invert.txt"
+            "data/margin/0_unicode_invert.txt"
         )));
+}
+
+#[test]
+fn encode_with_invalid_margin() {
     utils::command::command()
         .arg("encode")
-        .arg("-t")
-        .arg("UTF8i")
+        .arg("-m")
+        .arg("-1")
         .arg("QR code")
         .assert()
-        .success()
-        .stdout(predicate::eq(include_str!(
-            "data/encode/unicode_invert.txt"
-        )));
-}
-
-#[test]
-fn encode_to_invalid_output_format() {
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("unexpected argument '-1' found"));
     utils::command::command()
         .arg("encode")
-        .arg("-t")
-        .arg("a")
+        .arg("-m")
+        .arg("4294967296")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'a' for '--type <FORMAT>'",
+            "invalid value '4294967296' for '--margin <NUMBER>'",
+        ))
+        .stderr(predicate::str::contains(
+            "4294967296 is not in 0..=4294967295",
         ));
 }
 
-#[cfg(feature = "optimize-output-png")]
 #[test]
-#[allow(clippy::too_many_lines)]
-fn encode_to_optimized_png() {
-    let default_output = utils::command::command()
-        .arg("encode")
-        .arg("-t")
-        .arg("png")
-        .arg("QR code")
-        .output()
-        .unwrap();
-
-    let level0_output = utils::command::command()
+fn encode_to_png() {
+    let output = utils::command::command()
         .arg("encode")
         .arg("-t")
         .arg("png")
-        .arg("--optimize-png")
-        .arg("0")
         .arg("QR code")
         .output()
         .unwrap();
     assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&level0_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
         image::open("tests/data/encode/encode.png").unwrap()
     );
-    assert!(level0_output.stdout.len() <= default_output.stdout.len());
-    assert!(level0_output.status.success());
+    assert!(output.status.success());
+}
 
-    let level1_output = utils::command::command()
+#[test]
+fn encode_to_svg() {
+    utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("1")
+        .arg("svg")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&level1_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert!(level1_output.stdout.len() <= level0_output.stdout.len());
-    assert!(level1_output.status.success());
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
+}
 
-    let level2_output = utils::command::command()
+// The edges of modules cannot be checked because the AVIF image cannot be decoded
+// without the native AV1 decoder, so only the file type is checked.
+#[cfg(feature = "output-as-avif")]
+#[test]
+fn encode_to_avif() {
+    let output = utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("2")
+        .arg("avif")
         .arg("QR code")
         .output()
         .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&level2_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert!(level2_output.stdout.len() <= level1_output.stdout.len());
-    assert!(level2_output.status.success());
+    assert!(output.status.success());
+    assert_eq!(&output.stdout[4..12], b"ftypavif");
+}
 
-    let level3_output = utils::command::command()
+#[cfg(feature = "output-as-avif")]
+#[test]
+fn encode_to_avif_from_extension() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/output.avif");
+    utils::command::command()
         .arg("encode")
-        .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("3")
+        .arg("-o")
+        .arg(path)
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&level3_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert!(level3_output.stdout.len() <= level2_output.stdout.len());
-    assert!(level3_output.status.success());
+        .assert()
+        .success();
+    assert_eq!(&std::fs::read(path).unwrap()[4..12], b"ftypavif");
+}
 
-    let level4_output = utils::command::command()
+#[test]
+fn encode_to_svg_with_viewbox() {
+    utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("4")
+        .arg("svg")
+        .arg("--svg-viewbox")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&level4_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert!(level4_output.stdout.len() <= level3_output.stdout.len());
-    assert!(level4_output.status.success());
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/viewbox.svg")));
+}
 
-    let level5_output = utils::command::command()
+#[test]
+fn encode_to_svg_with_optimize() {
+    utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("5")
+        .arg("svg")
+        .arg("--svg-optimize")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&level5_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert!(level5_output.stdout.len() <= level4_output.stdout.len());
-    assert!(level5_output.status.success());
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/optimize.svg")));
+}
 
-    let level6_output = utils::command::command()
+#[test]
+fn encode_to_svg_with_optimize_auto() {
+    utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("6")
+        .arg("svg")
+        .arg("-v")
+        .arg("10")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&level6_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert!(level6_output.stdout.len() <= level5_output.stdout.len());
-    assert!(level6_output.status.success());
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"d="M32 32h56v8h-56zM"#));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("-v")
+        .arg("9")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"d="M32 32h8v8H32V32M"#));
 }
 
-#[cfg(feature = "optimize-output-png")]
 #[test]
-fn encode_to_maximum_optimized_png() {
-    let level6_output = utils::command::command()
+fn encode_to_svg_with_optimize_never() {
+    utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("6")
+        .arg("svg")
+        .arg("--svg-optimize=never")
+        .arg("-v")
+        .arg("10")
         .arg("QR code")
-        .output()
-        .unwrap();
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#"d="M32 32h8v8H32V32M"#));
+}
 
-    let max_output = utils::command::command()
+#[test]
+fn encode_to_svg_with_crisp_edges() {
+    utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("max")
+        .arg("svg")
+        .arg("--crisp-edges")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&max_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert_eq!(max_output.stdout.len(), level6_output.stdout.len());
-    assert!(max_output.status.success());
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/crisp_edges.svg")));
 }
 
-#[cfg(feature = "optimize-output-png")]
+#[cfg(feature = "decode-from-svg")]
 #[test]
-fn encode_to_optimized_png_without_value() {
-    let level2_output = utils::command::command()
+fn encode_to_svg_with_crisp_edges_and_decode() {
+    for args in [&[][..], &["--repeat", "2x1"], &["--svg-viewbox"]] {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("-t")
+            .arg("svg")
+            .arg("--crisp-edges")
+            .args(args)
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        utils::command::command()
+            .arg("decode")
+            .arg("-t")
+            .arg("svg")
+            .write_stdin(output.stdout)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("QR code"));
+    }
+}
+
+#[test]
+fn encode_to_svg_without_background() {
+    let output = utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("2")
+        .arg("svg")
+        .arg("--svg-background")
+        .arg("none")
         .arg("QR code")
         .output()
         .unwrap();
-
-    let without_value_output = utils::command::command()
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(!svg.contains("<rect"));
+    assert!(svg.contains(r##"<path fill="#000000""##));
+    assert!(output.status.success());
+    utils::command::command()
         .arg("encode")
-        .arg("--optimize-png")
         .arg("-t")
-        .arg("png")
+        .arg("svg")
+        .arg("--svg-background")
+        .arg("color")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&without_value_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert_eq!(
-        without_value_output.stdout.len(),
-        level2_output.stdout.len()
-    );
-    assert!(without_value_output.status.success());
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
 }
 
-#[cfg(feature = "optimize-output-png")]
 #[test]
-fn encode_to_optimized_png_with_invalid_level() {
+fn encode_to_svg_symbol() {
     utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("7")
+        .arg("svg")
+        .arg("--svg-symbol")
+        .arg("qr-code")
         .arg("QR code")
         .assert()
-        .failure()
+        .success()
+        .stdout(predicate::str::starts_with(
+            r#"<?xml version="1.0" standalone="yes"?><svg xmlns="http://www.w3.org/2000/svg" version="1.1" style="display: none"><symbol id="qr-code" width="232" height="232" viewBox="0 0 232 232""#,
+        ))
+        .stdout(predicate::str::ends_with("</symbol></svg>\n"));
+}
+
+#[test]
+fn encode_with_invalid_svg_symbol() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--svg-symbol")
+        .arg("qr code")
+        .arg("QR code")
+        .assert()
+        .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value '7' for '--optimize-png [<LEVEL>]'",
+            "invalid value 'qr code' for '--svg-symbol <ID>'",
         ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--svg-symbol")
+        .arg("qr-code")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not SVG"));
 }
 
-#[cfg(feature = "optimize-output-png")]
 #[test]
-fn encode_to_optimized_png_with_invalid_output_format() {
-    {
+fn encode_to_svg_with_percentage_module_size() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("-s")
+        .arg("5%")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#"version="1.1" width="145%" viewBox="0 0 232 232""#,
+        ));
+}
+
+#[test]
+fn encode_with_percentage_module_size_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-s")
+        .arg("5%")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "percentage module size can only be used with SVG",
+        ));
+}
+
+#[test]
+fn encode_with_optimize_never_to_png() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--svg-optimize=never")
+        .arg("QR code")
+        .assert()
+        .success();
+}
+
+#[test]
+fn encode_with_optimize_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--svg-optimize")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not SVG"));
+}
+
+#[test]
+fn encode_with_crisp_edges_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--crisp-edges")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not SVG"));
+}
+
+#[test]
+fn encode_without_svg_background_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--svg-background")
+        .arg("none")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not SVG"));
+}
+
+#[test]
+fn encode_with_viewbox_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--svg-viewbox")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not SVG"));
+}
+
+#[cfg(feature = "clipboard")]
+#[test]
+fn encode_with_clipboard_and_output() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--clipboard")
+        .arg("-o")
+        .arg("output.png")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--clipboard' cannot be used with '--output <FILE>'",
+        ));
+}
+
+#[test]
+fn encode_with_output_dir() {
+    let dir = concat!(env!("CARGO_TARGET_TMPDIR"), "/output_dir");
+    for _ in 0..2 {
         utils::command::command()
             .arg("encode")
+            .arg("--output-dir")
+            .arg(dir)
             .arg("-t")
             .arg("svg")
-            .arg("--optimize-png")
-            .arg("0")
-            .arg("QR code")
-            .assert()
-            .failure()
-            .code(1)
-            .stderr(predicate::str::contains("output format is not PNG"));
-    }
-    {
-        utils::command::command()
-            .arg("encode")
-            .arg("-t")
-            .arg("unicode")
-            .arg("--optimize-png")
-            .arg("0")
             .arg("QR code")
             .assert()
-            .failure()
-            .code(1)
-            .stderr(predicate::str::contains("output format is not PNG"));
+            .success();
     }
+    assert_eq!(
+        std::fs::read_to_string(format!("{dir}/2c24d35a117c162d.svg")).unwrap(),
+        include_str!("data/encode/encode.svg")
+    );
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-dir")
+        .arg(dir)
+        .arg("-t")
+        .arg("pic")
+        .arg("QR code")
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(format!("{dir}/2c24d35a117c162d.pic")).unwrap(),
+        include_str!("data/encode/encode.pic")
+    );
 }
 
-#[cfg(feature = "optimize-output-png")]
 #[test]
-fn encode_to_optimized_png_using_zopfli() {
-    let without_value_output = utils::command::command()
+fn encode_with_opacity() {
+    utils::command::command()
         .arg("encode")
-        .arg("--optimize-png")
         .arg("-t")
-        .arg("png")
+        .arg("svg")
+        .arg("--foreground")
+        .arg("red")
+        .arg("--foreground-opacity")
+        .arg("0.5")
+        .arg("--background-opacity")
+        .arg("0.5")
         .arg("QR code")
-        .output()
-        .unwrap();
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r##"fill="#ff000080""##))
+        .stdout(predicate::str::contains(r##"fill="#ffffff80""##));
+}
 
-    let zopfli_5_iterations_output = utils::command::command()
+#[test]
+fn encode_with_invalid_opacity() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground-opacity")
+        .arg("1.5")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "opacity must be a number between 0.0 and 1.0",
+        ));
+}
+
+#[test]
+fn encode_with_verify() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--verify")
+        .arg("QR code")
+        .assert()
+        .success();
+    #[cfg(feature = "decode-from-svg")]
+    utils::command::command()
         .arg("encode")
         .arg("-t")
-        .arg("png")
-        .arg("--optimize-png")
-        .arg("--zopfli")
-        .arg("5")
+        .arg("svg")
+        .arg("--verify")
         .arg("QR code")
-        .output()
-        .unwrap();
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
+}
+
+#[test]
+fn encode_with_verify_with_low_contrast() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("#777777")
+        .arg("--background")
+        .arg("#888888")
+        .arg("--verify")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "no QR code was detected in the rendered image",
+        ));
+}
+
+#[test]
+fn encode_with_verify_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ascii")
+        .arg("--verify")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG"));
+}
+
+#[test]
+fn encode_with_dump_all_masks() {
+    let file = concat!(env!("CARGO_TARGET_TMPDIR"), "/dump_all_masks.svg");
+    utils::command::command()
+        .arg("encode")
+        .arg("-o")
+        .arg(file)
+        .arg("-t")
+        .arg("svg")
+        .arg("--dump-all-masks")
+        .arg("--verbose")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::is_match("(?m)^Mask 0: penalty [0-9]+$").unwrap())
+        .stderr(predicate::str::is_match("(?m)^Mask 7: penalty [0-9]+$").unwrap());
+    for mask in 0..8 {
+        let file = concat!(env!("CARGO_TARGET_TMPDIR"), "/dump_all_masks-mask");
+        assert!(std::fs::metadata(format!("{file}{mask}.svg")).is_ok());
+    }
     assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&zopfli_5_iterations_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
+        std::fs::read_to_string(concat!(
+            env!("CARGO_TARGET_TMPDIR"),
+            "/dump_all_masks-mask3.svg"
+        ))
+        .unwrap(),
+        include_str!("data/encode/encode.svg")
     );
-    assert!(zopfli_5_iterations_output.stdout.len() < without_value_output.stdout.len());
-    assert!(zopfli_5_iterations_output.status.success());
+}
 
-    let zopfli_default_iterations_output = utils::command::command()
+#[test]
+fn encode_with_dump_all_masks_without_output() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--dump-all-masks")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "all mask patterns can only be output to files",
+        ));
+}
+
+#[test]
+fn encode_to_micro_qr_code_with_dump_all_masks() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-o")
+        .arg("output.png")
+        .arg("--variant")
+        .arg("micro")
+        .arg("-v")
+        .arg("3")
+        .arg("--dump-all-masks")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "all mask patterns can only be output for a normal QR code",
+        ));
+}
+
+#[test]
+fn encode_with_output_dir_conflicts_with_output() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-dir")
+        .arg("data/dummy")
+        .arg("-o")
+        .arg("output.png")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--output-dir <DIRECTORY>' cannot be used with '--output <FILE>'",
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn encode_with_output_fd() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-fd")
+        .arg("1")
+        .arg("-t")
+        .arg("svg")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
+}
+
+#[cfg(unix)]
+#[test]
+fn encode_with_closed_output_fd() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-fd")
+        .arg("1023")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(66)
+        .stderr(predicate::str::contains(
+            "could not write the image to the file descriptor 1023",
+        ));
+}
+
+#[test]
+fn encode_with_output_on_stderr() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-on-stderr")
+        .arg("-t")
+        .arg("svg")
+        .arg("--verbose")
+        .arg("-m")
+        .arg("2")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Version: 1\nLevel: M\n"))
+        .stdout(predicate::str::contains(
+            "Warning: the margin is smaller than the recommended quiet zone",
+        ))
+        .stderr(predicate::str::starts_with("<?xml"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-on-stderr")
+        .arg("-t")
+        .arg("svg")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::eq(include_str!("data/encode/encode.svg")));
+}
+
+#[test]
+fn encode_with_output_on_stderr_conflicts_with_output() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-on-stderr")
+        .arg("-o")
+        .arg("output.png")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--output-on-stderr' cannot be used with '--output <FILE>'",
+        ));
+}
+
+#[test]
+fn encode_with_output_fd_conflicts_with_output() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--output-fd")
+        .arg("3")
+        .arg("-o")
+        .arg("output.png")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--output-fd <FD>' cannot be used with '--output <FILE>'",
+        ));
+}
+
+#[test]
+fn encode_with_output_format_inferred_from_extension() {
+    {
+        let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/inferred.svg");
+        utils::command::command()
+            .arg("encode")
+            .arg("-o")
+            .arg(path)
+            .arg("QR code")
+            .assert()
+            .success()
+            .stderr(predicate::str::is_empty());
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            include_str!("data/encode/encode.svg")
+        );
+    }
+    {
+        let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/inferred.PIC");
+        utils::command::command()
+            .arg("encode")
+            .arg("-o")
+            .arg(path)
+            .arg("QR code")
+            .assert()
+            .success()
+            .stderr(predicate::str::is_empty());
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            include_str!("data/encode/encode.pic")
+        );
+    }
+}
+
+#[test]
+fn encode_with_output_format_inferred_from_unknown_extension() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/inferred.txt");
+    utils::command::command()
+        .arg("encode")
+        .arg("-o")
+        .arg(path)
+        .arg("QR code")
+        .assert()
+        .success();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&std::fs::read(path).unwrap())
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/basic/basic.png").unwrap()
+    );
+}
+
+#[test]
+fn encode_with_output_format_not_matching_extension() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/mismatched.svg");
+    utils::command::command()
+        .arg("encode")
+        .arg("-o")
+        .arg(path)
+        .arg("-t")
+        .arg("png")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: the output format does not match the extension of",
+        ));
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory_with_format(
+                &std::fs::read(path).unwrap(),
+                image::ImageFormat::Png
+            )
+            .unwrap()
+            .to_luma8()
+        ),
+        image::open("tests/data/basic/basic.png").unwrap()
+    );
+}
+
+#[test]
+fn encode_to_pic() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("pic")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.pic")));
+}
+
+#[cfg(feature = "output-as-ansi")]
+#[test]
+fn encode_to_ansi() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ansi")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/ansi.txt")));
+}
+
+#[cfg(feature = "output-as-ansi")]
+#[test]
+fn encode_to_ansi_256() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ansi256")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/ansi_256.txt")));
+}
+
+#[cfg(feature = "output-as-ansi")]
+#[test]
+fn encode_to_ansi_true_color() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ansi-true-color")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!(
+            "data/encode/ansi_true_color.txt"
+        )));
+}
+
+#[test]
+fn encode_to_ascii() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ascii")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/ascii.txt")));
+}
+
+#[test]
+fn encode_to_ascii_invert() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ascii-invert")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/ascii_invert.txt")));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ASCIIi")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/ascii_invert.txt")));
+}
+
+#[test]
+fn encode_to_unicode() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("unicode")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/unicode.txt")));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("terminal")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/unicode.txt")));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("UTF8")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/unicode.txt")));
+}
+
+#[test]
+fn encode_to_unicode_invert() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("unicode-invert")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!(
+            "data/encode/unicode_invert.txt"
+        )));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("UTF8i")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!(
+            "data/encode/unicode_invert.txt"
+        )));
+}
+
+#[test]
+fn encode_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("a")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'a' for '--type <FORMAT>'",
+        ));
+}
+
+#[test]
+fn encode_is_reproducible() {
+    for format in ["png", "svg"] {
+        let outputs = [(); 2].map(|()| {
+            utils::command::command()
+                .arg("encode")
+                .arg("-t")
+                .arg(format)
+                .arg("QR code")
+                .output()
+                .unwrap()
+        });
+        assert!(outputs[0].status.success());
+        assert_eq!(outputs[0].stdout, outputs[1].stdout);
+    }
+}
+
+#[test]
+fn encode_to_png_without_ancillary_chunks() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let mut chunks = Vec::new();
+    let mut data = &output.stdout[8..];
+    while let [a, b, c, d, e, f, g, h, ..] = *data {
+        let length = usize::try_from(u32::from_be_bytes([a, b, c, d])).unwrap();
+        chunks.push([e, f, g, h]);
+        data = &data[12 + length..];
+    }
+    assert_eq!(chunks, [*b"IHDR", *b"IDAT", *b"IEND"]);
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_optimized_png_is_reproducible() {
+    let outputs = [(); 2].map(|()| {
+        utils::command::command()
+            .arg("encode")
+            .arg("--optimize-png")
+            .arg("4")
+            .arg("QR code")
+            .output()
+            .unwrap()
+    });
+    assert!(outputs[0].status.success());
+    assert_eq!(outputs[0].stdout, outputs[1].stdout);
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+#[allow(clippy::too_many_lines)]
+fn encode_to_optimized_png() {
+    let default_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("QR code")
+        .output()
+        .unwrap();
+
+    let level0_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("0")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&level0_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(level0_output.stdout.len() <= default_output.stdout.len());
+    assert!(level0_output.status.success());
+
+    let level1_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("1")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&level1_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(level1_output.stdout.len() <= level0_output.stdout.len());
+    assert!(level1_output.status.success());
+
+    let level2_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("2")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&level2_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(level2_output.stdout.len() <= level1_output.stdout.len());
+    assert!(level2_output.status.success());
+
+    let level3_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("3")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&level3_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(level3_output.stdout.len() <= level2_output.stdout.len());
+    assert!(level3_output.status.success());
+
+    let level4_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("4")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&level4_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(level4_output.stdout.len() <= level3_output.stdout.len());
+    assert!(level4_output.status.success());
+
+    let level5_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("5")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&level5_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(level5_output.stdout.len() <= level4_output.stdout.len());
+    assert!(level5_output.status.success());
+
+    let level6_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("6")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&level6_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(level6_output.stdout.len() <= level5_output.stdout.len());
+    assert!(level6_output.status.success());
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_maximum_optimized_png() {
+    let level6_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("6")
+        .arg("QR code")
+        .output()
+        .unwrap();
+
+    let max_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("max")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&max_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert_eq!(max_output.stdout.len(), level6_output.stdout.len());
+    assert!(max_output.status.success());
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_optimized_png_without_value() {
+    let level2_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("2")
+        .arg("QR code")
+        .output()
+        .unwrap();
+
+    let without_value_output = utils::command::command()
+        .arg("encode")
+        .arg("--optimize-png")
+        .arg("-t")
+        .arg("png")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&without_value_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert_eq!(
+        without_value_output.stdout.len(),
+        level2_output.stdout.len()
+    );
+    assert!(without_value_output.status.success());
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_optimized_png_with_invalid_level() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("7")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '7' for '--optimize-png [<LEVEL>]'",
+        ));
+}
+
+#[test]
+fn encode_to_png_with_compression() {
+    let compress = |level| {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--png-compression")
+            .arg(level)
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+            image::open("tests/data/encode/encode.png").unwrap()
+        );
+        output.stdout
+    };
+    let default = compress("default");
+    let fast = compress("fast");
+    let best = compress("best");
+    assert!(best.len() <= fast.len());
+    assert_eq!(
+        default,
+        utils::command::command()
+            .arg("encode")
+            .arg("QR code")
+            .output()
+            .unwrap()
+            .stdout
+    );
+}
+
+#[test]
+fn encode_to_png_with_bit_depth() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--bit-depth")
+        .arg("16")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let image = image::load_from_memory(&output.stdout).unwrap();
+    assert_eq!(image.color(), image::ColorType::L16);
+    assert_eq!(
+        DynamicImage::ImageLuma8(image.to_luma8()),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--bit-depth")
+        .arg("8")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        image::load_from_memory(&output.stdout).unwrap().color(),
+        image::ColorType::Rgba8
+    );
+}
+
+#[test]
+fn encode_to_bilevel_png() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--bilevel")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    // The bit depth and the color type in the IHDR chunk.
+    assert_eq!(output.stdout[24..26], [1, 0]);
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--bilevel")
+        .arg("--foreground")
+        .arg("white")
+        .arg("--background")
+        .arg("black")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout[24..26], [1, 0]);
+    let mut image = image::load_from_memory(&output.stdout).unwrap().to_luma8();
+    image::imageops::invert(&mut image);
+    assert_eq!(
+        DynamicImage::ImageLuma8(image),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+}
+
+#[test]
+fn encode_to_bilevel_png_with_invalid_options() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--bilevel")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG"));
+    for args in [
+        ["--foreground", "brown"],
+        ["--background", "#ffffff80"],
+        ["--finder-color", "red"],
+    ] {
+        utils::command::command()
+            .arg("encode")
+            .arg("--bilevel")
+            .args(args)
+            .arg("QR code")
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "only opaque black and white can be used with a 1-bit image",
+            ));
+    }
+    utils::command::command()
+        .arg("encode")
+        .arg("--bilevel")
+        .arg("--transparent")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "only opaque black and white can be used with a 1-bit image",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--bilevel")
+        .arg("--bit-depth")
+        .arg("16")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--bilevel' cannot be used with '--bit-depth <DEPTH>'",
+        ));
+}
+
+#[test]
+fn encode_to_png_with_bit_depth_and_invalid_options() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--bit-depth")
+        .arg("16")
+        .arg("--background")
+        .arg("#ffffff80")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "colors with alpha cannot be used with 16-bit grayscale",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--bit-depth")
+        .arg("16")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--bit-depth")
+        .arg("32")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '32' for '--bit-depth <DEPTH>'",
+        ));
+}
+
+#[test]
+fn encode_to_png_with_filter() {
+    let filter = |filter| {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--png-filter")
+            .arg(filter)
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+            image::open("tests/data/encode/encode.png").unwrap()
+        );
+        output.stdout
+    };
+    for value in ["none", "sub", "up", "avg", "paeth"] {
+        filter(value);
+    }
+    assert_ne!(filter("none"), filter("paeth"));
+    assert_eq!(
+        filter("adaptive"),
+        utils::command::command()
+            .arg("encode")
+            .arg("QR code")
+            .output()
+            .unwrap()
+            .stdout
+    );
+}
+
+#[test]
+fn encode_to_png_with_invalid_filter() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--png-filter")
+        .arg("a")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'a' for '--png-filter <FILTER>'",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--png-filter")
+        .arg("up")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG"));
+}
+
+#[test]
+fn encode_to_png_with_invalid_compression() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--png-compression")
+        .arg("a")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'a' for '--png-compression <LEVEL>'",
+        ));
+}
+
+#[test]
+fn encode_to_png_with_compression_and_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--png-compression")
+        .arg("best")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG"));
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_optimized_png_with_invalid_output_format() {
+    {
+        utils::command::command()
+            .arg("encode")
+            .arg("-t")
+            .arg("svg")
+            .arg("--optimize-png")
+            .arg("0")
+            .arg("QR code")
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains("output format is not PNG"));
+    }
+    {
+        utils::command::command()
+            .arg("encode")
+            .arg("-t")
+            .arg("unicode")
+            .arg("--optimize-png")
+            .arg("0")
+            .arg("QR code")
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains("output format is not PNG"));
+    }
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_optimized_png_using_zopfli() {
+    let without_value_output = utils::command::command()
+        .arg("encode")
+        .arg("--optimize-png")
+        .arg("-t")
+        .arg("png")
+        .arg("QR code")
+        .output()
+        .unwrap();
+
+    let zopfli_5_iterations_output = utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("png")
+        .arg("--optimize-png")
+        .arg("--zopfli")
+        .arg("5")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&zopfli_5_iterations_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(zopfli_5_iterations_output.stdout.len() < without_value_output.stdout.len());
+    assert!(zopfli_5_iterations_output.status.success());
+
+    let zopfli_default_iterations_output = utils::command::command()
+        .arg("encode")
+        .arg("--optimize-png")
+        .arg("--zopfli")
+        .arg("-t")
+        .arg("png")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(
+            image::load_from_memory(&zopfli_default_iterations_output.stdout)
+                .unwrap()
+                .to_luma8()
+        ),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(
+        zopfli_default_iterations_output.stdout.len() < zopfli_5_iterations_output.stdout.len()
+    );
+    assert!(zopfli_default_iterations_output.status.success());
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_optimized_png_using_zopfli_without_level() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--zopfli")
+        .arg("-t")
+        .arg("png")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ))
+        .stderr(predicate::str::contains("--optimize-png [<LEVEL>]"));
+}
+
+#[cfg(feature = "optimize-output-png")]
+#[test]
+fn encode_to_optimized_png_using_zopfli_with_invalid_value() {
+    {
+        utils::command::command()
+            .arg("encode")
+            .arg("-t")
+            .arg("png")
+            .arg("--optimize-png")
+            .arg("--zopfli")
+            .arg("0")
+            .arg("QR code")
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicate::str::contains(
+                "invalid value '0' for '--zopfli [<ITERATION>]'",
+            ))
+            .stderr(predicate::str::contains(
+                "number would be zero for non-zero type",
+            ));
+    }
+    {
+        utils::command::command()
+            .arg("encode")
+            .arg("-t")
+            .arg("png")
+            .arg("--optimize-png")
+            .arg("--zopfli")
+            .arg("256")
+            .arg("QR code")
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicate::str::contains(
+                "invalid value '256' for '--zopfli [<ITERATION>]'",
+            ))
+            .stderr(predicate::str::contains(
+                "number too large to fit in target type",
+            ));
+    }
+}
+
+#[test]
+fn encode_in_numeric_mode() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("numeric")
+        .arg("42")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/mode/numeric.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_in_numeric_mode_max() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/numeric_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("numeric")
+        .assert()
+        .success();
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/numeric_over_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("numeric")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_in_alphanumeric_mode() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("alphanumeric")
+        .arg("URL")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/mode/alphanumeric.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_in_alphanumeric_mode_max() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/alphanumeric_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("alphanumeric")
+        .assert()
+        .success();
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/alphanumeric_over_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("alphanumeric")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_in_byte_mode() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/byte.txt")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("byte")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/mode/byte.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_binary_data_in_byte_mode_from_stdin() {
+    // Pseudo-random bytes generated by a linear congruential generator, which
+    // contain NUL bytes and invalid UTF-8 sequences.
+    let mut state = 0x2545_f491_u32;
+    let mut data = (0..256)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            state.to_be_bytes()[0]
+        })
+        .collect::<Vec<_>>();
+    data.extend_from_slice(&[0x00, 0x00, 0xff, 0xfe, 0xc3, 0x28]);
+    assert!(std::str::from_utf8(&data).is_err());
+
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("15")
+        .arg("--mode")
+        .arg("byte")
+        .write_stdin(data.clone())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(output.stdout)
+        .assert()
+        .success()
+        .stdout(predicate::eq(data));
+}
+
+#[test]
+fn encode_with_capacity() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--capacity")
+        .arg("--mode")
+        .arg("byte")
+        .arg("-l")
+        .arg("m")
+        .arg("-v")
+        .arg("10")
+        .assert()
+        .success()
+        .stdout(predicate::eq("213\n"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--capacity")
+        .arg("--mode")
+        .arg("numeric")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::eq(
+            "{\"variant\":\"normal\",\"version\":40,\"mode\":\"numeric\",\"level\":\"L\",\"capacity\":7089}\n",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--capacity")
+        .arg("--variant")
+        .arg("micro")
+        .arg("--mode")
+        .arg("alphanumeric")
+        .arg("-v")
+        .arg("2")
+        .assert()
+        .success()
+        .stdout(predicate::eq("5\n"));
+}
+
+#[test]
+fn encode_with_capacity_with_unsupported_mode() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--capacity")
+        .arg("--variant")
+        .arg("micro")
+        .arg("--mode")
+        .arg("byte")
+        .arg("-v")
+        .arg("1")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not compute the capacity"))
+        .stderr(predicate::str::contains("unsupported character set"));
+}
+
+#[test]
+fn encode_with_capacity_without_mode() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--capacity")
+        .arg("-v")
+        .arg("1")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ))
+        .stderr(predicate::str::contains("--mode <MODE>"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--format")
+        .arg("json")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("--capacity"));
+}
+
+#[test]
+fn encode_in_byte_mode_max() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/byte_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("byte")
+        .assert()
+        .success();
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/byte_over_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("byte")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_in_kanji_mode() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/kanji.txt")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("kanji")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/mode/kanji.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_in_kanji_mode_from_utf8() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/kanji_utf8.txt")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("kanji")
+        .arg("--input-charset")
+        .arg("utf-8")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/mode/kanji.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_in_kanji_mode_from_invalid_utf8() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/kanji.txt")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("kanji")
+        .arg("--input-charset")
+        .arg("utf-8")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "could not convert the data to Shift JIS",
+        ));
+}
+
+#[test]
+fn encode_in_kanji_mode_with_invalid_data() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("kanji")
+        .write_stdin(b"\x93\x5fQR".as_slice())
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "could not construct a QR code\n\nCaused by:\n    0: invalid kanji character at byte offset 2\n    1: invalid character\n",
+        ));
+}
+
+#[test]
+fn encode_with_input_encoding() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--input-encoding")
+        .arg("hex")
+        .arg("5152 20636f6465")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--input-encoding")
+        .arg("base64")
+        .write_stdin("UVIgY29kZQ==\n")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
+}
+
+#[test]
+fn encode_with_invalid_input_encoding_data() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--input-encoding")
+        .arg("hex")
+        .arg("515")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("invalid hexadecimal data"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--input-encoding")
+        .arg("base64")
+        .arg("UVIg*29kZQ==")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("invalid base64 data"));
+}
+
+#[test]
+fn encode_with_prefix_and_suffix() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--prefix")
+        .arg("https://example.com/")
+        .arg("--suffix")
+        .arg("?v=1")
+        .write_stdin("42")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(output.stdout)
+        .assert()
+        .success()
+        .stdout(predicate::eq("https://example.com/42?v=1"));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--prefix")
+        .arg("QR")
+        .arg("--input-encoding")
+        .arg("hex")
+        .arg("20636f6465")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/encode.svg")));
+}
+
+#[test]
+fn encode_with_segments() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--segment")
+        .arg("Hello:ascii")
+        .arg("--segment")
+        .arg("漢字:shift_jis")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(output.stdout)
+        .assert()
+        .success()
+        .stdout(predicate::eq(b"Hello\x8a\xbf\x8e\x9a" as &[u8]));
+}
+
+#[test]
+fn encode_with_segments_without_eci() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--verbose")
+        .arg("--no-eci")
+        .arg("--segment")
+        .arg("Hello:ascii")
+        .arg("--segment")
+        .arg("漢字:shift_jis")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Capacity: 12/16 codewords (75%)"));
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(output.stdout)
+        .assert()
+        .success()
+        .stdout(predicate::eq(b"Hello\x8a\xbf\x8e\x9a" as &[u8]));
+}
+
+#[test]
+fn encode_with_invalid_segment() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--segment")
+        .arg("漢字:ascii")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "\"漢字\" cannot be encoded in ascii",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--segment")
+        .arg("QR code:ebcdic")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("invalid character set \"ebcdic\""));
+    utils::command::command()
+        .arg("encode")
+        .arg("--variant")
+        .arg("auto")
+        .arg("--segment")
+        .arg("QR code:ascii")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "segments can only be encoded in a normal QR code",
+        ));
+}
+
+#[test]
+fn encode_with_input_charset_in_invalid_mode() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("byte")
+        .arg("--input-charset")
+        .arg("utf-8")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "input charset can only be specified in kanji mode",
+        ));
+}
+
+#[test]
+fn encode_in_kanji_mode_max() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/kanji_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("kanji")
+        .assert()
+        .success();
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/kanji_over_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("-v")
+        .arg("40")
+        .arg("--mode")
+        .arg("kanji")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_with_invalid_mode() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("1")
+        .arg("--mode")
+        .arg("a")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'a' for '--mode <MODE>'",
+        ));
+}
+
+#[test]
+fn encode_with_mode_without_symbol_version() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--verbose")
+        .arg("--mode")
+        .arg("byte")
+        .arg("0123456789012345678901234567890123456789")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Version: 3\nLevel: M\nMask: 2\nWidth: 29\nCapacity: 42/44 codewords (95%)\n",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("2")
+        .arg("--mode")
+        .arg("byte")
+        .arg("0123456789012345678901234567890123456789")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_as_normal_qr_code() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("1")
+        .arg("--variant")
+        .arg("normal")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/variant/normal.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_as_micro_qr_code() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("3")
+        .arg("--variant")
+        .arg("micro")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/variant/micro.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_as_micro_qr_code_with_invalid_symbol_version() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("5")
+        .arg("--variant")
+        .arg("micro")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not set the version"))
+        .stderr(predicate::str::contains("invalid version"));
+}
+
+#[test]
+fn encode_with_invalid_variant() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("3")
+        .arg("--variant")
+        .arg("a")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'a' for '--variant <TYPE>'",
+        ));
+}
+
+#[test]
+fn encode_with_optimize_version() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-l")
+        .arg("h")
+        .arg("--optimize-version")
+        .arg("--mode")
+        .arg("byte")
+        .arg("--verbose")
+        .arg("QR code!")
+        .assert()
+        .success()
+        .stderr(predicate::eq(
+            "Version: 2\nLevel: H\nMask: 2\nWidth: 25\nCapacity: 10/16 codewords (62%)\n",
+        ));
+}
+
+#[test]
+fn encode_as_micro_qr_code_with_optimize_version() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--optimize-version")
+        .arg("--variant")
+        .arg("micro")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/variant/micro.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_with_optimize_version_if_data_is_too_long() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-r")
+        .arg("data/mode/byte_over_max.txt")
+        .arg("-l")
+        .arg("l")
+        .arg("--optimize-version")
+        .arg("--mode")
+        .arg("byte")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_with_version_range() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--min-version")
+        .arg("5")
+        .arg("--max-version")
+        .arg("20")
+        .arg("--dry-run")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("Version: 5\n"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--min-version")
+        .arg("2")
+        .arg("-l")
+        .arg("h")
+        .arg("--mode")
+        .arg("byte")
+        .arg("--dry-run")
+        .arg("QR code QR code QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("Version: 3\n"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--max-version")
+        .arg("3")
+        .arg("--variant")
+        .arg("micro")
+        .arg("--mode")
+        .arg("byte")
+        .arg("--dry-run")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("Version: 3\n"));
+}
+
+#[test]
+fn encode_with_version_range_if_data_is_too_long() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--max-version")
+        .arg("1")
+        .arg("QR code QR code QR")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_with_invalid_version_range() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--min-version")
+        .arg("5")
+        .arg("--max-version")
+        .arg("2")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "minimum version is greater than the maximum version",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("3")
+        .arg("--min-version")
+        .arg("2")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--symbol-version <NUMBER>' cannot be used with '--min-version <NUMBER>'",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--max-version")
+        .arg("41")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '41' for '--max-version <NUMBER>'",
+        ));
+}
+
+#[test]
+fn encode_with_optimize_version_and_symbol_version() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("1")
+        .arg("--optimize-version")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--symbol-version <NUMBER>' cannot be used with '--optimize-version'",
+        ));
+}
+
+#[test]
+fn encode_with_auto_variant() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--variant")
+        .arg("auto")
+        .arg("--verbose")
+        .arg("-t")
+        .arg("ascii")
+        .arg("12345")
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with(
+            "Variant: micro\nVersion: 2\nLevel: M\n",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--variant")
+        .arg("auto")
+        .arg("--verbose")
+        .arg("-t")
+        .arg("ascii")
+        .arg("-l")
+        .arg("h")
+        .arg("12345")
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with(
+            "Variant: normal\nVersion: 1\nLevel: H\n",
+        ));
+    utils::command::command()
         .arg("encode")
-        .arg("--optimize-png")
-        .arg("--zopfli")
+        .arg("--variant")
+        .arg("auto")
+        .arg("--min-version")
+        .arg("5")
+        .arg("--verbose")
         .arg("-t")
-        .arg("png")
-        .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(
-            image::load_from_memory(&zopfli_default_iterations_output.stdout)
-                .unwrap()
-                .to_luma8()
-        ),
-        image::open("tests/data/encode/encode.png").unwrap()
-    );
-    assert!(
-        zopfli_default_iterations_output.stdout.len() < zopfli_5_iterations_output.stdout.len()
-    );
-    assert!(zopfli_default_iterations_output.status.success());
+        .arg("ascii")
+        .arg("12345")
+        .assert()
+        .success()
+        .stderr(predicate::str::starts_with("Variant: normal\nVersion: 5\n"));
 }
 
-#[cfg(feature = "optimize-output-png")]
 #[test]
-fn encode_to_optimized_png_using_zopfli_without_level() {
+fn encode_with_auto_variant_and_symbol_version() {
     utils::command::command()
         .arg("encode")
-        .arg("--zopfli")
-        .arg("-t")
-        .arg("png")
+        .arg("--variant")
+        .arg("auto")
+        .arg("-v")
+        .arg("1")
+        .arg("12345")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "automatic type of QR code cannot be used with the symbol version",
+        ));
+}
+
+#[test]
+fn encode_with_variant_without_symbol_version() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--variant")
+        .arg("micro")
         .arg("QR code")
         .assert()
         .failure()
@@ -1158,398 +3790,504 @@ fn encode_to_optimized_png_using_zopfli_without_level() {
         .stderr(predicate::str::contains(
             "the following required arguments were not provided",
         ))
-        .stderr(predicate::str::contains("--optimize-png [<LEVEL>]"));
-}
-
-#[cfg(feature = "optimize-output-png")]
-#[test]
-fn encode_to_optimized_png_using_zopfli_with_invalid_value() {
-    {
-        utils::command::command()
-            .arg("encode")
-            .arg("-t")
-            .arg("png")
-            .arg("--optimize-png")
-            .arg("--zopfli")
-            .arg("0")
-            .arg("QR code")
-            .assert()
-            .failure()
-            .code(2)
-            .stderr(predicate::str::contains(
-                "invalid value '0' for '--zopfli [<ITERATION>]'",
-            ))
-            .stderr(predicate::str::contains(
-                "number would be zero for non-zero type",
-            ));
-    }
-    {
-        utils::command::command()
-            .arg("encode")
-            .arg("-t")
-            .arg("png")
-            .arg("--optimize-png")
-            .arg("--zopfli")
-            .arg("256")
-            .arg("QR code")
-            .assert()
-            .failure()
-            .code(2)
-            .stderr(predicate::str::contains(
-                "invalid value '256' for '--zopfli [<ITERATION>]'",
-            ))
-            .stderr(predicate::str::contains(
-                "number too large to fit in target type",
-            ));
-    }
+        .stderr(predicate::str::contains("--symbol-version <NUMBER>"));
 }
 
 #[test]
-fn encode_in_numeric_mode() {
+fn encode_from_named_fg_color() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("-v")
-        .arg("1")
-        .arg("--mode")
-        .arg("numeric")
-        .arg("42")
+        .arg("--foreground")
+        .arg("brown")
+        .arg("QR code")
         .output()
         .unwrap();
     assert_eq!(
-        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/mode/numeric.png").unwrap()
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/fg.png").unwrap()
     );
     assert!(output.status.success());
 }
 
 #[test]
-fn encode_in_numeric_mode_max() {
-    utils::command::command()
-        .arg("encode")
-        .arg("-r")
-        .arg("data/mode/numeric_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("numeric")
-        .assert()
-        .success();
-    utils::command::command()
-        .arg("encode")
-        .arg("-r")
-        .arg("data/mode/numeric_over_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("numeric")
-        .assert()
-        .failure()
-        .code(65)
-        .stderr(predicate::str::contains("could not construct a QR code"))
-        .stderr(predicate::str::contains("data too long"));
-}
-
-#[test]
-fn encode_in_alphanumeric_mode() {
+fn encode_from_named_bg_color() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("-v")
-        .arg("1")
-        .arg("--mode")
-        .arg("alphanumeric")
-        .arg("URL")
+        .arg("--background")
+        .arg("lightslategray")
+        .arg("QR code")
         .output()
         .unwrap();
     assert_eq!(
-        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/mode/alphanumeric.png").unwrap()
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/bg.png").unwrap()
     );
     assert!(output.status.success());
 }
 
 #[test]
-fn encode_in_alphanumeric_mode_max() {
-    utils::command::command()
-        .arg("encode")
-        .arg("-r")
-        .arg("data/mode/alphanumeric_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("alphanumeric")
-        .assert()
-        .success();
-    utils::command::command()
-        .arg("encode")
-        .arg("-r")
-        .arg("data/mode/alphanumeric_over_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("alphanumeric")
-        .assert()
-        .failure()
-        .code(65)
-        .stderr(predicate::str::contains("could not construct a QR code"))
-        .stderr(predicate::str::contains("data too long"));
+fn encode_with_palette() {
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--palette")
+            .arg("data/palette/palette.toml")
+            .arg("--foreground")
+            .arg("brand-primary")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+            image::open("tests/data/colored/fg.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--palette")
+            .arg("data/palette/palette.toml")
+            .arg("--background")
+            .arg("BRAND-BACKGROUND")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+            image::open("tests/data/colored/bg.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
 }
 
 #[test]
-fn encode_in_byte_mode() {
+fn encode_with_palette_and_built_in_color() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("-r")
-        .arg("data/mode/byte.txt")
-        .arg("-v")
-        .arg("1")
-        .arg("--mode")
-        .arg("byte")
+        .arg("--palette")
+        .arg("data/palette/palette.toml")
+        .arg("--foreground")
+        .arg("brown")
+        .arg("QR code")
         .output()
         .unwrap();
     assert_eq!(
-        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/mode/byte.png").unwrap()
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/fg.png").unwrap()
     );
     assert!(output.status.success());
 }
 
 #[test]
-fn encode_in_byte_mode_max() {
+fn encode_with_unknown_palette_color() {
     utils::command::command()
         .arg("encode")
-        .arg("-r")
-        .arg("data/mode/byte_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("byte")
+        .arg("--palette")
+        .arg("data/palette/palette.toml")
+        .arg("--foreground")
+        .arg("brand-secondary")
+        .arg("QR code")
         .assert()
-        .success();
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'brand-secondary' for '--foreground <COLOR>': invalid unknown format",
+        ));
     utils::command::command()
         .arg("encode")
-        .arg("-r")
-        .arg("data/mode/byte_over_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("byte")
+        .arg("--foreground")
+        .arg("brand-primary")
+        .arg("QR code")
         .assert()
         .failure()
-        .code(65)
-        .stderr(predicate::str::contains("could not construct a QR code"))
-        .stderr(predicate::str::contains("data too long"));
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'brand-primary' for '--foreground <COLOR>': invalid unknown format",
+        ));
+}
+
+#[test]
+fn encode_with_non_existent_palette() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--palette")
+        .arg("non_existent.toml")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(66)
+        .stderr(predicate::str::contains(
+            "could not read the palette from non_existent.toml",
+        ));
 }
 
 #[test]
-fn encode_in_kanji_mode() {
+fn encode_with_finder_color() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("-r")
-        .arg("data/mode/kanji.txt")
-        .arg("-v")
-        .arg("1")
-        .arg("--mode")
-        .arg("kanji")
+        .arg("--finder-color")
+        .arg("red")
+        .arg("QR code")
         .output()
         .unwrap();
     assert_eq!(
-        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/mode/kanji.png").unwrap()
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/finder.png").unwrap()
     );
     assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--finder-color")
+        .arg("red")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/colored/finder.svg")));
 }
 
 #[test]
-fn encode_in_kanji_mode_max() {
+fn encode_with_low_contrast_colors() {
     utils::command::command()
         .arg("encode")
-        .arg("-r")
-        .arg("data/mode/kanji_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("kanji")
+        .arg("--foreground")
+        .arg("#777")
+        .arg("--background")
+        .arg("#888")
+        .arg("QR code")
         .assert()
-        .success();
+        .success()
+        .stderr(predicate::eq(
+            "Warning: the foreground color has low contrast with the background, which may make the QR code hard to scan\n",
+        ));
     utils::command::command()
         .arg("encode")
-        .arg("-r")
-        .arg("data/mode/kanji_over_max.txt")
-        .arg("-l")
-        .arg("l")
-        .arg("-v")
-        .arg("40")
-        .arg("--mode")
-        .arg("kanji")
+        .arg("--foreground")
+        .arg("#777")
+        .arg("--background")
+        .arg("#888")
+        .arg("--transparent")
+        .arg("QR code")
         .assert()
-        .failure()
-        .code(65)
-        .stderr(predicate::str::contains("could not construct a QR code"))
-        .stderr(predicate::str::contains("data too long"));
+        .success()
+        .stderr(predicate::str::is_empty());
 }
 
 #[test]
-fn encode_with_invalid_mode() {
+fn encode_with_strict_contrast() {
     utils::command::command()
         .arg("encode")
-        .arg("-v")
-        .arg("1")
-        .arg("--mode")
-        .arg("a")
+        .arg("--strict-contrast")
+        .arg("--foreground")
+        .arg("#777")
+        .arg("--background")
+        .arg("#888")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'a' for '--mode <MODE>'",
+            "the foreground color has low contrast with the background (1.26:1, less than 3:1)",
         ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--strict-contrast")
+        .arg("--foreground")
+        .arg("brown")
+        .arg("QR code")
+        .assert()
+        .success();
 }
 
 #[test]
-fn encode_with_mode_without_symbol_version() {
+fn encode_with_low_contrast_finder_color() {
     utils::command::command()
         .arg("encode")
-        .arg("--mode")
-        .arg("numeric")
-        .arg("42")
+        .arg("--finder-color")
+        .arg("#eee")
+        .arg("QR code")
         .assert()
-        .failure()
-        .code(2)
+        .success()
         .stderr(predicate::str::contains(
-            "the following required arguments were not provided",
-        ))
-        .stderr(predicate::str::contains("--symbol-version <NUMBER>"));
+            "Warning: the finder color has low contrast with the background",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--finder-color")
+        .arg("#eee")
+        .arg("-q")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
 }
 
 #[test]
-fn encode_as_normal_qr_code() {
-    let output = utils::command::command()
+fn encode_with_finder_color_to_invalid_output_format() {
+    utils::command::command()
         .arg("encode")
-        .arg("-v")
-        .arg("1")
-        .arg("--variant")
-        .arg("normal")
+        .arg("-t")
+        .arg("pic")
+        .arg("--finder-color")
+        .arg("red")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/variant/normal.png").unwrap()
-    );
-    assert!(output.status.success());
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG or SVG"));
 }
 
 #[test]
-fn encode_as_micro_qr_code() {
+fn encode_with_quiet_zone_color() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("-v")
-        .arg("3")
-        .arg("--variant")
-        .arg("micro")
+        .arg("--quiet-zone-color")
+        .arg("yellow")
         .arg("QR code")
         .output()
         .unwrap();
-    assert_eq!(
-        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
-        image::open("tests/data/variant/micro.png").unwrap()
-    );
     assert!(output.status.success());
+    let image = image::load_from_memory(&output.stdout).unwrap().to_rgb8();
+    assert_eq!(image.get_pixel(0, 0).0, [u8::MAX, u8::MAX, u8::MIN]);
+    assert_eq!(image.get_pixel(231, 231).0, [u8::MAX, u8::MAX, u8::MIN]);
+    assert_eq!(image.get_pixel(32, 32).0, [u8::MIN; 3]);
+    assert_eq!(image.get_pixel(88, 32).0, [u8::MAX; 3]);
+    utils::command::command()
+        .arg("decode")
+        .write_stdin(output.stdout)
+        .assert()
+        .success()
+        .stdout(predicate::eq("QR code"));
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--quiet-zone-color")
+        .arg("yellow")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r##"<path fill="#ffff00" d="M0 0h232v232h-232zM32 32v168h168v-168z"/></svg>"##,
+        ));
 }
 
 #[test]
-fn encode_as_micro_qr_code_with_invalid_symbol_version() {
+fn encode_with_quiet_zone_color_to_invalid_output_format() {
     utils::command::command()
         .arg("encode")
-        .arg("-v")
-        .arg("5")
-        .arg("--variant")
-        .arg("micro")
+        .arg("-t")
+        .arg("pic")
+        .arg("--quiet-zone-color")
+        .arg("yellow")
         .arg("QR code")
         .assert()
         .failure()
-        .code(65)
-        .stderr(predicate::str::contains("could not set the version"))
-        .stderr(predicate::str::contains("invalid version"));
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG or SVG"));
 }
 
 #[test]
-fn encode_with_invalid_variant() {
+fn encode_with_circle_modules() {
     utils::command::command()
         .arg("encode")
-        .arg("-v")
-        .arg("3")
-        .arg("--variant")
-        .arg("a")
+        .arg("--module-shape")
+        .arg("circle")
+        .arg("--verify")
+        .arg("QR code")
+        .assert()
+        .success();
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--module-shape")
+        .arg("dot")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r##"<path fill="#000000" d="M32 32h8v8h-8zM"##,
+        ))
+        .stdout(predicate::str::contains(
+            r#"<circle cx="100" cy="36" r="4"/>"#,
+        ));
+}
+
+#[test]
+fn encode_with_circle_finder_patterns() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--finder-shape")
+        .arg("circle")
+        .arg("--finder-color")
+        .arg("red")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r##"<g fill="#ff0000" shape-rendering="geometricPrecision"><circle cx="36" cy="36" r="4"/>"##,
+        ));
+}
+
+#[test]
+fn encode_with_circle_modules_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("pic")
+        .arg("--module-shape")
+        .arg("circle")
         .arg("QR code")
         .assert()
         .failure()
-        .code(2)
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG or SVG"));
+}
+
+#[test]
+fn encode_with_circle_modules_and_nearest_neighbor_only() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--module-shape")
+        .arg("circle")
+        .arg("--nearest-neighbor-only")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
         .stderr(predicate::str::contains(
-            "invalid value 'a' for '--variant <TYPE>'",
+            "circle modules cannot be used with 16-bit grayscale or the nearest neighbor assertion",
         ));
 }
 
 #[test]
-fn encode_with_variant_without_symbol_version() {
+fn encode_with_invalid_module_shape() {
     utils::command::command()
         .arg("encode")
-        .arg("--variant")
-        .arg("micro")
+        .arg("--module-shape")
+        .arg("star")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "the following required arguments were not provided",
-        ))
-        .stderr(predicate::str::contains("--symbol-version <NUMBER>"));
+            "invalid value 'star' for '--module-shape <SHAPE>'",
+        ));
 }
 
 #[test]
-fn encode_from_named_fg_color() {
+fn encode_with_debug_overlay() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("brown")
+        .arg("--debug-overlay")
         .arg("QR code")
         .output()
         .unwrap();
-    assert_eq!(
-        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
-        image::open("tests/data/colored/fg.png").unwrap()
-    );
+    let image = image::load_from_memory(&output.stdout).unwrap().to_rgba8();
+    let plain = image::open("tests/data/basic/basic.png")
+        .unwrap()
+        .to_rgba8();
+    assert_eq!(image.dimensions(), plain.dimensions());
+    assert_ne!(image.get_pixel(32, 32), plain.get_pixel(32, 32));
+    assert_eq!(image.get_pixel(0, 0), plain.get_pixel(0, 0));
     assert!(output.status.success());
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--debug-overlay")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "debug overlay can only be used with an RGBA image",
+        ));
 }
 
 #[test]
-fn encode_from_named_bg_color() {
-    let output = utils::command::command()
+fn encode_with_nearest_neighbor_only() {
+    for size in ["1", "3", "8"] {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("-s")
+            .arg(size)
+            .arg("--nearest-neighbor-only")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let image = image::load_from_memory(&output.stdout).unwrap();
+        assert_eq!(image.width(), 29 * size.parse::<u32>().unwrap());
+    }
+    utils::command::command()
         .arg("encode")
-        .arg("--background")
-        .arg("lightslategray")
+        .arg("--bit-depth")
+        .arg("16")
+        .arg("--nearest-neighbor-only")
         .arg("QR code")
-        .output()
-        .unwrap();
-    assert_eq!(
-        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
-        image::open("tests/data/colored/bg.png").unwrap()
-    );
-    assert!(output.status.success());
+        .assert()
+        .success();
+    utils::command::command()
+        .arg("encode")
+        .arg("-s")
+        .arg("3")
+        .arg("--bilevel")
+        .arg("--nearest-neighbor-only")
+        .arg("QR code")
+        .assert()
+        .success();
+}
+
+#[test]
+fn encode_with_nearest_neighbor_only_to_invalid_output_format() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--nearest-neighbor-only")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "nearest neighbor assertion can only be used with a PNG image",
+        ));
+    #[cfg(feature = "output-as-avif")]
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("avif")
+        .arg("--nearest-neighbor-only")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "nearest neighbor assertion can only be used with a PNG image",
+        ));
+}
+
+#[test]
+fn encode_with_nearest_neighbor_only_and_repeat() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--nearest-neighbor-only")
+        .arg("--repeat")
+        .arg("2x2")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--nearest-neighbor-only' cannot be used with '--repeat <COLUMNSxROWS>'",
+        ));
 }
 
 #[test]
@@ -2119,491 +4857,967 @@ fn encode_from_rgba_color() {
 }
 
 #[test]
-fn encode_from_invalid_rgb_fg_color() {
+fn encode_from_invalid_rgb_fg_color() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("rgb(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'rgb(0)' for '--foreground <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid rgb format"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("rgba(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'rgba(0)' for '--foreground <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid rgb format"));
+}
+
+#[test]
+fn encode_from_invalid_rgb_bg_color() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--background")
+        .arg("rgb(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'rgb(0)' for '--background <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid rgb format"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--background")
+        .arg("rgba(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'rgba(0)' for '--background <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid rgb format"));
+}
+
+#[test]
+fn encode_from_hsl_color() {
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--foreground")
+            .arg("hsl(248 39% 39.2%)")
+            .arg("--background")
+            .arg("hsl(0 0% 66.3%)")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+            image::open("tests/data/colored/hsl.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--foreground")
+            .arg("hsl(248, 39%, 39.2%)")
+            .arg("--background")
+            .arg("hsl(0, 0%, 66.3%)")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+            image::open("tests/data/colored/hsl.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
+}
+
+#[test]
+fn encode_from_hsl_color_with_alpha() {
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--foreground")
+            .arg("hsl(248 39% 39.2% / 49.8%)")
+            .arg("--background")
+            .arg("hsl(0 0% 66.3% / 49.8%)")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            image::load_from_memory(&output.stdout).unwrap(),
+            image::open("tests/data/colored/hsla.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--foreground")
+            .arg("hsl(248, 39%, 39.2%, 49.8%)")
+            .arg("--background")
+            .arg("hsl(0, 0%, 66.3%, 49.8%)")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            image::load_from_memory(&output.stdout).unwrap(),
+            image::open("tests/data/colored/hsla.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
+}
+
+#[test]
+fn encode_from_hsla_color() {
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--foreground")
+            .arg("hsla(248 39% 39.2% / 49.8%)")
+            .arg("--background")
+            .arg("hsla(0 0% 66.3% / 49.8%)")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            image::load_from_memory(&output.stdout).unwrap(),
+            image::open("tests/data/colored/hsla.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
+    {
+        let output = utils::command::command()
+            .arg("encode")
+            .arg("--foreground")
+            .arg("hsla(248, 39%, 39.2%, 49.8%)")
+            .arg("--background")
+            .arg("hsla(0, 0%, 66.3%, 49.8%)")
+            .arg("QR code")
+            .output()
+            .unwrap();
+        assert_eq!(
+            image::load_from_memory(&output.stdout).unwrap(),
+            image::open("tests/data/colored/hsla.png").unwrap()
+        );
+        assert!(output.status.success());
+    }
+}
+
+#[test]
+fn encode_from_invalid_hsl_fg_color() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("hsl(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'hsl(0)' for '--foreground <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid hsl format"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("hsla(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'hsla(0)' for '--foreground <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid hsl format"));
+}
+
+#[test]
+fn encode_from_invalid_hsl_bg_color() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--background")
+        .arg("hsl(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'hsl(0)' for '--background <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid hsl format"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--background")
+        .arg("hsla(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'hsla(0)' for '--background <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid hsl format"));
+}
+
+#[test]
+fn encode_from_hwb_color() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("hwb(50.6 0% 0%)")
+        .arg("--background")
+        .arg("hwb(0 66.3% 33.7%)")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/hwb.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_from_hwb_color_with_alpha() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("hwb(50.6 0% 0% / 49.8%)")
+        .arg("--background")
+        .arg("hwb(0 66.3% 33.7% / 49.8%)")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        image::load_from_memory(&output.stdout).unwrap(),
+        image::open("tests/data/colored/hwb_with_alpha.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_from_invalid_hwb_fg_color() {
     utils::command::command()
         .arg("encode")
         .arg("--foreground")
-        .arg("rgb(0)")
+        .arg("hwb(0)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'rgb(0)' for '--foreground <COLOR>'",
+            "invalid value 'hwb(0)' for '--foreground <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid rgb format"));
+        .stderr(predicate::str::contains("invalid hwb format"));
+}
+
+#[test]
+fn encode_from_invalid_hwb_bg_color() {
     utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("rgba(0)")
+        .arg("--background")
+        .arg("hwb(0)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'rgba(0)' for '--foreground <COLOR>'",
+            "invalid value 'hwb(0)' for '--background <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid rgb format"));
+        .stderr(predicate::str::contains("invalid hwb format"));
 }
 
 #[test]
-fn encode_from_invalid_rgb_bg_color() {
-    utils::command::command()
+fn encode_from_oklab_color() {
+    let output = utils::command::command()
         .arg("encode")
+        .arg("--foreground")
+        .arg("oklab(50.4% -0.0906 0.0069)")
         .arg("--background")
-        .arg("rgb(0)")
+        .arg("oklab(61.9% -0.0120 -0.0302)")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/oklab.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_from_oklab_color_with_alpha() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("oklab(50.4% -0.0906 0.0069 / 0.5)")
+        .arg("--background")
+        .arg("oklab(61.9% -0.0120 -0.0302 / 0.5)")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        image::load_from_memory(&output.stdout).unwrap(),
+        image::open("tests/data/colored/oklab_with_alpha.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_from_invalid_oklab_fg_color() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("oklab(0)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'rgb(0)' for '--background <COLOR>'",
+            "invalid value 'oklab(0)' for '--foreground <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid rgb format"));
+        .stderr(predicate::str::contains("invalid oklab format"));
+}
+
+#[test]
+fn encode_from_invalid_oklab_bg_color() {
     utils::command::command()
         .arg("encode")
         .arg("--background")
-        .arg("rgba(0)")
+        .arg("oklab(0)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'rgba(0)' for '--background <COLOR>'",
+            "invalid value 'oklab(0)' for '--background <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid rgb format"));
+        .stderr(predicate::str::contains("invalid oklab format"));
 }
 
 #[test]
-fn encode_from_hsl_color() {
-    {
-        let output = utils::command::command()
-            .arg("encode")
-            .arg("--foreground")
-            .arg("hsl(248 39% 39.2%)")
-            .arg("--background")
-            .arg("hsl(0 0% 66.3%)")
-            .arg("QR code")
-            .output()
-            .unwrap();
-        assert_eq!(
-            DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
-            image::open("tests/data/colored/hsl.png").unwrap()
-        );
-        assert!(output.status.success());
-    }
-    {
-        let output = utils::command::command()
-            .arg("encode")
-            .arg("--foreground")
-            .arg("hsl(248, 39%, 39.2%)")
-            .arg("--background")
-            .arg("hsl(0, 0%, 66.3%)")
-            .arg("QR code")
-            .output()
-            .unwrap();
-        assert_eq!(
-            DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
-            image::open("tests/data/colored/hsl.png").unwrap()
-        );
-        assert!(output.status.success());
-    }
+fn encode_from_oklch_color() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("oklch(59.41% 0.16 301.29)")
+        .arg("--background")
+        .arg("oklch(61.9% 0.032 248.35)")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/oklch.png").unwrap()
+    );
+    assert!(output.status.success());
 }
 
 #[test]
-fn encode_from_hsl_color_with_alpha() {
-    {
-        let output = utils::command::command()
-            .arg("encode")
-            .arg("--foreground")
-            .arg("hsl(248 39% 39.2% / 49.8%)")
-            .arg("--background")
-            .arg("hsl(0 0% 66.3% / 49.8%)")
-            .arg("QR code")
-            .output()
-            .unwrap();
-        assert_eq!(
-            image::load_from_memory(&output.stdout).unwrap(),
-            image::open("tests/data/colored/hsla.png").unwrap()
-        );
-        assert!(output.status.success());
-    }
-    {
-        let output = utils::command::command()
-            .arg("encode")
-            .arg("--foreground")
-            .arg("hsl(248, 39%, 39.2%, 49.8%)")
-            .arg("--background")
-            .arg("hsl(0, 0%, 66.3%, 49.8%)")
-            .arg("QR code")
-            .output()
-            .unwrap();
-        assert_eq!(
-            image::load_from_memory(&output.stdout).unwrap(),
-            image::open("tests/data/colored/hsla.png").unwrap()
-        );
-        assert!(output.status.success());
-    }
+fn encode_from_oklch_color_with_alpha() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("oklch(59.41% 0.16 301.29 / 49.8%)")
+        .arg("--background")
+        .arg("oklch(61.9% 0.032 248.35 / 49.8%)")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        image::load_from_memory(&output.stdout).unwrap(),
+        image::open("tests/data/colored/oklch_with_alpha.png").unwrap()
+    );
+    assert!(output.status.success());
 }
 
 #[test]
-fn encode_from_hsla_color() {
-    {
-        let output = utils::command::command()
-            .arg("encode")
-            .arg("--foreground")
-            .arg("hsla(248 39% 39.2% / 49.8%)")
-            .arg("--background")
-            .arg("hsla(0 0% 66.3% / 49.8%)")
-            .arg("QR code")
-            .output()
-            .unwrap();
-        assert_eq!(
-            image::load_from_memory(&output.stdout).unwrap(),
-            image::open("tests/data/colored/hsla.png").unwrap()
-        );
-        assert!(output.status.success());
-    }
-    {
-        let output = utils::command::command()
-            .arg("encode")
-            .arg("--foreground")
-            .arg("hsla(248, 39%, 39.2%, 49.8%)")
-            .arg("--background")
-            .arg("hsla(0, 0%, 66.3%, 49.8%)")
-            .arg("QR code")
-            .output()
-            .unwrap();
-        assert_eq!(
-            image::load_from_memory(&output.stdout).unwrap(),
-            image::open("tests/data/colored/hsla.png").unwrap()
-        );
-        assert!(output.status.success());
-    }
+fn encode_from_invalid_oklch_fg_color() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("oklch(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'oklch(0)' for '--foreground <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid oklch format"));
 }
 
 #[test]
-fn encode_from_invalid_hsl_fg_color() {
+fn encode_from_invalid_oklch_bg_color() {
     utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("hsl(0)")
+        .arg("--background")
+        .arg("oklch(0)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'hsl(0)' for '--foreground <COLOR>'",
+            "invalid value 'oklch(0)' for '--background <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid hsl format"));
+        .stderr(predicate::str::contains("invalid oklch format"));
+}
+
+#[test]
+fn encode_from_invalid_fg_color_function() {
     utils::command::command()
         .arg("encode")
         .arg("--foreground")
-        .arg("hsla(0)")
+        .arg("fn(0)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'hsla(0)' for '--foreground <COLOR>'",
+            "invalid value 'fn(0)' for '--foreground <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid hsl format"));
+        .stderr(predicate::str::contains("invalid color function"));
+}
+
+#[test]
+fn encode_from_invalid_bg_color_function() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--background")
+        .arg("fn(0)")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value 'fn(0)' for '--background <COLOR>'",
+        ))
+        .stderr(predicate::str::contains("invalid color function"));
 }
 
 #[test]
-fn encode_from_invalid_hsl_bg_color() {
+fn encode_from_unknown_fg_color() {
     utils::command::command()
         .arg("encode")
-        .arg("--background")
-        .arg("hsl(0)")
+        .arg("--foreground")
+        .arg("a")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'hsl(0)' for '--background <COLOR>'",
+            "invalid value 'a' for '--foreground <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid hsl format"));
+        .stderr(predicate::str::contains("invalid unknown format"));
+}
+
+#[test]
+fn encode_from_unknown_bg_color() {
     utils::command::command()
         .arg("encode")
         .arg("--background")
-        .arg("hsla(0)")
+        .arg("a")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'hsla(0)' for '--background <COLOR>'",
+            "invalid value 'a' for '--background <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid hsl format"));
+        .stderr(predicate::str::contains("invalid unknown format"));
 }
 
+#[cfg(feature = "output-with-title")]
 #[test]
-fn encode_from_hwb_color() {
+fn encode_with_title() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("hwb(50.6 0% 0%)")
-        .arg("--background")
-        .arg("hwb(0 66.3% 33.7%)")
+        .arg("--title")
+        .arg("Scan me")
         .arg("QR code")
         .output()
         .unwrap();
+    let image = image::load_from_memory(&output.stdout).unwrap();
+    assert_eq!((image.width(), image.height()), (232, 268));
     assert_eq!(
-        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
-        image::open("tests/data/colored/hwb.png").unwrap()
+        DynamicImage::ImageLuma8(image.crop_imm(0, 0, 232, 232).to_luma8()),
+        image::open("tests/data/encode/encode.png").unwrap()
     );
+    assert!(image
+        .crop_imm(0, 232, 232, 36)
+        .to_luma8()
+        .pixels()
+        .any(|pixel| pixel.0 == [u8::MIN]));
     assert!(output.status.success());
 }
 
+#[cfg(feature = "output-with-title")]
 #[test]
-fn encode_from_hwb_color_with_alpha() {
+fn encode_with_title_wider_than_image() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("hwb(50.6 0% 0% / 49.8%)")
-        .arg("--background")
-        .arg("hwb(0 66.3% 33.7% / 49.8%)")
+        .arg("--title")
+        .arg("Scan me to open the page")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    let image = image::load_from_memory(&output.stdout).unwrap();
+    assert_eq!((image.width(), image.height()), (588, 268));
+    assert!(output.status.success());
+}
+
+#[cfg(feature = "output-with-title")]
+#[test]
+fn encode_with_empty_title() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--title")
+        .arg("")
         .arg("QR code")
         .output()
         .unwrap();
     assert_eq!(
-        image::load_from_memory(&output.stdout).unwrap(),
-        image::open("tests/data/colored/hwb_with_alpha.png").unwrap()
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/encode/encode.png").unwrap()
     );
     assert!(output.status.success());
 }
 
+#[cfg(feature = "output-with-title")]
 #[test]
-fn encode_from_invalid_hwb_fg_color() {
+fn encode_to_svg_with_title() {
     utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("hwb(0)")
+        .arg("-t")
+        .arg("svg")
+        .arg("--title")
+        .arg("Scan me")
         .arg("QR code")
         .assert()
-        .failure()
-        .code(2)
-        .stderr(predicate::str::contains(
-            "invalid value 'hwb(0)' for '--foreground <COLOR>'",
-        ))
-        .stderr(predicate::str::contains("invalid hwb format"));
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/title.svg")));
 }
 
+#[cfg(feature = "output-with-title")]
 #[test]
-fn encode_from_invalid_hwb_bg_color() {
+fn encode_with_title_to_invalid_output_format() {
     utils::command::command()
         .arg("encode")
-        .arg("--background")
-        .arg("hwb(0)")
+        .arg("-t")
+        .arg("ascii")
+        .arg("--title")
+        .arg("Scan me")
         .arg("QR code")
         .assert()
         .failure()
-        .code(2)
-        .stderr(predicate::str::contains(
-            "invalid value 'hwb(0)' for '--background <COLOR>'",
-        ))
-        .stderr(predicate::str::contains("invalid hwb format"));
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG or SVG"));
 }
 
 #[test]
-fn encode_from_oklab_color() {
+fn encode_with_transparent() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("oklab(50.4% -0.0906 0.0069)")
         .arg("--background")
-        .arg("oklab(61.9% -0.0120 -0.0302)")
+        .arg("lightslategray")
+        .arg("--transparent")
         .arg("QR code")
         .output()
         .unwrap();
-    assert_eq!(
-        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
-        image::open("tests/data/colored/oklab.png").unwrap()
-    );
+    let image = image::load_from_memory(&output.stdout).unwrap().to_rgba8();
+    let expected = image::open("tests/data/encode/encode.png")
+        .unwrap()
+        .to_luma8();
+    assert_eq!(image.dimensions(), expected.dimensions());
+    for (pixel, expected) in image.pixels().zip(expected.pixels()) {
+        if expected.0 == [u8::MAX] {
+            assert_eq!(pixel.0[3], u8::MIN);
+        } else {
+            assert_eq!(pixel.0, [u8::MIN, u8::MIN, u8::MIN, u8::MAX]);
+        }
+    }
     assert!(output.status.success());
 }
 
 #[test]
-fn encode_from_oklab_color_with_alpha() {
+fn encode_to_svg_with_transparent() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--transparent")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/transparent.svg")));
+}
+
+#[cfg(feature = "output-as-ansi")]
+#[test]
+fn encode_to_ansi_with_transparent() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ansi")
+        .arg("--transparent")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/ansi.txt")))
+        .stderr(predicate::eq(
+            "Warning: the output format does not support transparency\n",
+        ));
+}
+
+#[test]
+fn encode_with_transparent_background() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("oklab(50.4% -0.0906 0.0069 / 0.5)")
         .arg("--background")
-        .arg("oklab(61.9% -0.0120 -0.0302 / 0.5)")
+        .arg("transparent")
         .arg("QR code")
         .output()
         .unwrap();
-    assert_eq!(
-        image::load_from_memory(&output.stdout).unwrap(),
-        image::open("tests/data/colored/oklab_with_alpha.png").unwrap()
-    );
+    let image = image::load_from_memory(&output.stdout).unwrap().to_rgba8();
+    let expected = image::open("tests/data/encode/encode.png")
+        .unwrap()
+        .to_luma8();
+    assert_eq!(image.dimensions(), expected.dimensions());
+    for (pixel, expected) in image.pixels().zip(expected.pixels()) {
+        if expected.0 == [u8::MAX] {
+            assert_eq!(pixel.0[3], u8::MIN);
+        } else {
+            assert_eq!(pixel.0, [u8::MIN, u8::MIN, u8::MIN, u8::MAX]);
+        }
+    }
+    assert!(output.stderr.is_empty());
     assert!(output.status.success());
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--background")
+        .arg("transparent")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/transparent.svg")))
+        .stderr(predicate::str::is_empty());
 }
 
+#[cfg(feature = "output-as-ansi")]
 #[test]
-fn encode_from_invalid_oklab_fg_color() {
+fn encode_to_ansi_with_transparent_background() {
     utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("oklab(0)")
+        .arg("-t")
+        .arg("ansi")
+        .arg("--background")
+        .arg("transparent")
         .arg("QR code")
         .assert()
-        .failure()
-        .code(2)
-        .stderr(predicate::str::contains(
-            "invalid value 'oklab(0)' for '--foreground <COLOR>'",
-        ))
-        .stderr(predicate::str::contains("invalid oklab format"));
+        .success()
+        .stderr(predicate::eq(
+            "Warning: the output format does not support transparency\n",
+        ));
 }
 
 #[test]
-fn encode_from_invalid_oklab_bg_color() {
+fn encode_from_matrix() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--from-matrix")
+        .arg("data/matrix/matrix.txt")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/encode/encode.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_from_invalid_matrix() {
     utils::command::command()
         .arg("encode")
-        .arg("--background")
-        .arg("oklab(0)")
+        .arg("--from-matrix")
+        .arg("data/encode/encode.svg")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "could not parse the matrix in data/encode/encode.svg",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--from-matrix")
+        .arg("data/matrix/matrix.txt")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'oklab(0)' for '--background <COLOR>'",
-        ))
-        .stderr(predicate::str::contains("invalid oklab format"));
+            "the argument '--from-matrix <FILE>' cannot be used with '[STRING]'",
+        ));
 }
 
 #[test]
-fn encode_from_oklch_color() {
+fn encode_with_repeat() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("oklch(59.41% 0.16 301.29)")
+        .arg("--repeat")
+        .arg("3x2")
+        .arg("--gap")
+        .arg("10")
         .arg("--background")
-        .arg("oklch(61.9% 0.032 248.35)")
+        .arg("red")
         .arg("QR code")
         .output()
         .unwrap();
+    assert!(output.status.success());
+    let image = image::load_from_memory(&output.stdout).unwrap().to_rgba8();
+    let expected = image::open("tests/data/encode/encode.png")
+        .unwrap()
+        .to_luma8();
+    assert_eq!(image.dimensions(), (3 * 232 + 2 * 10, 2 * 232 + 10));
+    for (x, y) in [(0, 0), (242, 0), (484, 0), (0, 242), (242, 242), (484, 242)] {
+        let tile = image::imageops::crop_imm(&image, x, y, 232, 232).to_image();
+        for (pixel, expected) in tile.pixels().zip(expected.pixels()) {
+            if expected.0 == [u8::MIN] {
+                assert_eq!(pixel.0, [u8::MIN, u8::MIN, u8::MIN, u8::MAX]);
+            } else {
+                assert_eq!(pixel.0, [u8::MAX, u8::MIN, u8::MIN, u8::MAX]);
+            }
+        }
+    }
     assert_eq!(
-        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
-        image::open("tests/data/colored/oklch.png").unwrap()
+        image.get_pixel(236, 100).0,
+        [u8::MAX, u8::MIN, u8::MIN, u8::MAX]
+    );
+    assert_eq!(
+        image.get_pixel(100, 236).0,
+        [u8::MAX, u8::MIN, u8::MIN, u8::MAX]
     );
-    assert!(output.status.success());
 }
 
 #[test]
-fn encode_from_oklch_color_with_alpha() {
+fn encode_with_repeat_and_bit_depth() {
     let output = utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("oklch(59.41% 0.16 301.29 / 49.8%)")
-        .arg("--background")
-        .arg("oklch(61.9% 0.032 248.35 / 49.8%)")
+        .arg("--repeat")
+        .arg("2x1")
+        .arg("--bit-depth")
+        .arg("16")
         .arg("QR code")
         .output()
         .unwrap();
-    assert_eq!(
-        image::load_from_memory(&output.stdout).unwrap(),
-        image::open("tests/data/colored/oklch_with_alpha.png").unwrap()
-    );
     assert!(output.status.success());
+    let image = image::load_from_memory(&output.stdout).unwrap();
+    assert_eq!(image.color(), image::ColorType::L16);
+    assert_eq!((image.width(), image.height()), (2 * 232, 232));
 }
 
 #[test]
-fn encode_from_invalid_oklch_fg_color() {
+fn encode_to_svg_with_repeat() {
     utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("oklch(0)")
+        .arg("-t")
+        .arg("svg")
+        .arg("--repeat")
+        .arg("2x1")
+        .arg("--gap")
+        .arg("8")
         .arg("QR code")
         .assert()
-        .failure()
-        .code(2)
-        .stderr(predicate::str::contains(
-            "invalid value 'oklch(0)' for '--foreground <COLOR>'",
-        ))
-        .stderr(predicate::str::contains("invalid oklch format"));
+        .success()
+        .stdout(predicate::eq(include_str!("data/encode/repeat.svg")));
 }
 
 #[test]
-fn encode_from_invalid_oklch_bg_color() {
+fn encode_with_invalid_repeat() {
     utils::command::command()
         .arg("encode")
-        .arg("--background")
-        .arg("oklch(0)")
+        .arg("--repeat")
+        .arg("0x2")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'oklch(0)' for '--background <COLOR>'",
+            "invalid value '0x2' for '--repeat <COLUMNSxROWS>'",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--repeat")
+        .arg("100000x100000")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "grid must be <COLUMNS>x<ROWS> with integers from 1 to 100",
+        ));
+    for format in ["png", "svg"] {
+        utils::command::command()
+            .arg("encode")
+            .arg("-t")
+            .arg(format)
+            .arg("--repeat")
+            .arg("2x1")
+            .arg("--gap")
+            .arg("4294967295")
+            .arg("QR code")
+            .assert()
+            .failure()
+            .code(1)
+            .stderr(predicate::str::contains("the tiled image is too large"));
+    }
+    utils::command::command()
+        .arg("encode")
+        .arg("-t")
+        .arg("ascii")
+        .arg("--repeat")
+        .arg("2x2")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains("output format is not PNG or SVG"));
+    utils::command::command()
+        .arg("encode")
+        .arg("--gap")
+        .arg("10")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
         ))
-        .stderr(predicate::str::contains("invalid oklch format"));
+        .stderr(predicate::str::contains("--repeat <COLUMNSxROWS>"));
 }
 
 #[test]
-fn encode_from_invalid_fg_color_function() {
+fn encode_with_list_colors() {
     utils::command::command()
         .arg("encode")
-        .arg("--foreground")
-        .arg("fn(0)")
+        .arg("--list-colors")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("aliceblue #f0f8ff\n"))
+        .stdout(predicate::str::contains("\nbrown #a52a2a\n"))
+        .stdout(predicate::str::contains("\nlightslategray #778899\n"))
+        .stdout(predicate::str::ends_with("\nyellowgreen #9acd32\n"));
+}
+
+#[test]
+fn encode_with_list_colors_and_input() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--list-colors")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'fn(0)' for '--foreground <COLOR>'",
-        ))
-        .stderr(predicate::str::contains("invalid color function"));
+            "the argument '--list-colors' cannot be used with",
+        ));
 }
 
 #[test]
-fn encode_from_invalid_bg_color_function() {
+fn encode_with_list_formats() {
     utils::command::command()
         .arg("encode")
-        .arg("--background")
-        .arg("fn(0)")
+        .arg("--list-formats")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "png Portable Network Graphics\nsvg Scalable Vector Graphics\n",
+        ))
+        .stdout(predicate::str::contains("\npic PIC markup language\n"))
+        .stdout(predicate::str::ends_with(
+            "\nunicode-invert To the terminal as UTF-8 string\n",
+        ));
+}
+
+#[test]
+fn encode_with_list_formats_and_input() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--list-formats")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'fn(0)' for '--background <COLOR>'",
-        ))
-        .stderr(predicate::str::contains("invalid color function"));
+            "the argument '--list-formats' cannot be used with",
+        ));
 }
 
 #[test]
-fn encode_from_unknown_fg_color() {
+fn encode_from_cmyk_color() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--foreground")
+        .arg("cmyk(0% 74.545455% 74.545455% 35.294118%)")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageRgb8(image::load_from_memory(&output.stdout).unwrap().to_rgb8()),
+        image::open("tests/data/colored/fg.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_from_invalid_cmyk_fg_color() {
     utils::command::command()
         .arg("encode")
         .arg("--foreground")
-        .arg("a")
+        .arg("cmyk(0% 50% 50%)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'a' for '--foreground <COLOR>'",
+            "invalid value 'cmyk(0% 50% 50%)' for '--foreground <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid unknown format"));
+        .stderr(predicate::str::contains("invalid cmyk format"));
 }
 
 #[test]
-fn encode_from_unknown_bg_color() {
+fn encode_from_invalid_cmyk_bg_color() {
     utils::command::command()
         .arg("encode")
         .arg("--background")
-        .arg("a")
+        .arg("cmyk(0% 50% 50% 0% 0%)")
         .arg("QR code")
         .assert()
         .failure()
         .code(2)
         .stderr(predicate::str::contains(
-            "invalid value 'a' for '--background <COLOR>'",
+            "invalid value 'cmyk(0% 50% 50% 0% 0%)' for '--background <COLOR>'",
         ))
-        .stderr(predicate::str::contains("invalid unknown format"));
+        .stderr(predicate::str::contains("invalid cmyk format"));
 }
 
 #[test]
@@ -2861,6 +6075,173 @@ fn encode_with_colors_to_unicode_invert() {
     }
 }
 
+#[test]
+fn encode_with_config() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--config")
+        .arg("data/config/qrtool/config.toml")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/module_size/3.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_with_config_overridden_by_option() {
+    let output = utils::command::command()
+        .arg("encode")
+        .arg("--config")
+        .arg("data/config/qrtool/config.toml")
+        .arg("-s")
+        .arg("8")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/basic/basic.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn encode_with_default_config() {
+    let output = utils::command::command()
+        .env(
+            "XDG_CONFIG_HOME",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/config"),
+        )
+        .arg("encode")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/module_size/3.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn encode_with_no_config() {
+    let output = utils::command::command()
+        .env(
+            "XDG_CONFIG_HOME",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/config"),
+        )
+        .arg("encode")
+        .arg("--no-config")
+        .arg("QR code")
+        .output()
+        .unwrap();
+    assert_eq!(
+        DynamicImage::ImageLuma8(image::load_from_memory(&output.stdout).unwrap().to_luma8()),
+        image::open("tests/data/basic/basic.png").unwrap()
+    );
+    assert!(output.status.success());
+}
+
+#[test]
+fn encode_with_colors_from_env() {
+    utils::command::command()
+        .env("QRTOOL_FOREGROUND", "red")
+        .env("QRTOOL_BACKGROUND", "yellow")
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r##"fill="#ff0000""##))
+        .stdout(predicate::str::contains(r##"fill="#ffff00""##));
+}
+
+#[test]
+fn encode_with_colors_from_env_overridden_by_option() {
+    utils::command::command()
+        .env("QRTOOL_FOREGROUND", "red")
+        .env("QRTOOL_BACKGROUND", "")
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("--foreground")
+        .arg("blue")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r##"fill="#0000ff""##))
+        .stdout(predicate::str::contains(r##"fill="#ffffff""##));
+}
+
+#[test]
+fn encode_with_invalid_color_from_env() {
+    utils::command::command()
+        .env("QRTOOL_FOREGROUND", "invalid")
+        .arg("encode")
+        .arg("-t")
+        .arg("svg")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "could not parse the color in QRTOOL_FOREGROUND",
+        ));
+}
+
+#[test]
+fn encode_with_config_conflicts_with_no_config() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--config")
+        .arg("data/config/qrtool/config.toml")
+        .arg("--no-config")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--config <FILE>' cannot be used with '--no-config'",
+        ));
+}
+
+#[test]
+fn encode_with_non_existent_config() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--config")
+        .arg("non_existent.toml")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(66)
+        .stderr(predicate::str::contains(
+            "could not read the configuration from non_existent.toml",
+        ));
+}
+
+#[test]
+fn encode_with_invalid_config() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--config")
+        .arg("data/config/invalid.toml")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(78)
+        .stderr(predicate::str::contains(
+            "could not parse the configuration in data/config/invalid.toml",
+        ));
+}
+
 #[test]
 fn encode_with_verbose() {
     utils::command::command()
@@ -2870,7 +6251,156 @@ fn encode_with_verbose() {
         .assert()
         .success()
         .stdout(predicate::ne(&[] as &[u8]))
-        .stderr(predicate::eq("Version: 1\nLevel: M\n"));
+        .stderr(predicate::eq(
+            "Version: 1\nLevel: M\nMask: 3\nWidth: 21\nCapacity: 9/16 codewords (56%)\n",
+        ));
+}
+
+#[test]
+fn encode_as_micro_qr_code_with_verbose() {
+    utils::command::command()
+        .arg("encode")
+        .arg("-v")
+        .arg("3")
+        .arg("--variant")
+        .arg("micro")
+        .arg("--verbose")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::eq(
+            "Version: 3\nLevel: M\nMask: 0\nWidth: 15\nCapacity: 8/9 codewords (88%)\n",
+        ));
+}
+
+#[test]
+fn encode_with_metadata_json() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--metadata-json")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::ne(&[] as &[u8]))
+        .stderr(predicate::eq(
+            "{\"variant\":\"normal\",\"version\":1,\"level\":\"M\",\"mask\":3,\"width\":21,\"codewords\":{\"used\":9,\"total\":16}}\n",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--metadata-json")
+        .arg("--quiet")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("--quiet"));
+}
+
+#[test]
+fn encode_with_dry_run() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--dry-run")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::eq(
+            "Version: 1\nLevel: M\nMask: 3\nWidth: 21\nCapacity: 9/16 codewords (56%)\n",
+        ));
+    utils::command::command()
+        .arg("encode")
+        .arg("--dry-run")
+        .arg("-v")
+        .arg("1")
+        .arg("-l")
+        .arg("h")
+        .arg("QR code QR code QR")
+        .assert()
+        .failure()
+        .code(65)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("could not construct a QR code"))
+        .stderr(predicate::str::contains("data too long"));
+}
+
+#[test]
+fn encode_with_dry_run_and_output() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--dry-run")
+        .arg("-o")
+        .arg("output.png")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the argument '--dry-run' cannot be used with '--output <FILE>'",
+        ));
+}
+
+#[test]
+fn encode_with_benchmark() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--benchmark")
+        .arg("3")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(
+            predicate::str::is_match(
+                "^Construct: min .+, median .+, max .+\nRender: min .+, median .+, max .+\n$",
+            )
+            .unwrap(),
+        );
+}
+
+#[test]
+fn encode_with_benchmark_from_matrix() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--benchmark")
+        .arg("3")
+        .arg("--from-matrix")
+        .arg("data/matrix/matrix.txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::is_match("^Render: min .+, median .+, max .+\n$").unwrap());
+}
+
+#[test]
+fn encode_with_invalid_benchmark() {
+    utils::command::command()
+        .arg("encode")
+        .arg("--benchmark")
+        .arg("0")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "invalid value '0' for '--benchmark <NUMBER>'",
+        ));
+}
+
+#[test]
+fn encode_with_quiet() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/quiet.svg");
+    utils::command::command()
+        .arg("encode")
+        .arg("-o")
+        .arg(path)
+        .arg("-t")
+        .arg("png")
+        .arg("--quiet")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
 }
 
 #[test]
@@ -2915,4 +6445,12 @@ fn validate_the_options_dependencies_for_encode_command() {
         .assert()
         .failure()
         .code(2);
+    utils::command::command()
+        .arg("encode")
+        .arg("--verbose")
+        .arg("--quiet")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(2);
 }
@@ -0,0 +1,159 @@
+// SPDX-FileCopyrightText: 2024 Shun Sakai
+//
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+// Lint levels of rustc.
+#![forbid(unsafe_code)]
+#![deny(missing_debug_implementations)]
+#![warn(rust_2018_idioms)]
+// Lint levels of Clippy.
+#![warn(clippy::cargo, clippy::nursery, clippy::pedantic)]
+#![allow(clippy::multiple_crate_versions)]
+
+mod utils;
+
+use predicates::prelude::predicate;
+
+#[test]
+fn basic_estimate() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq("Version: 1\nType: normal\nCodewords: 9/16\n"));
+}
+
+#[test]
+fn estimate_from_file() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("-r")
+        .arg("data/encode/data.txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Version: 1\n"));
+}
+
+#[test]
+fn estimate_from_stdin() {
+    utils::command::command()
+        .arg("estimate")
+        .write_stdin("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("Version: 1\n"));
+}
+
+#[test]
+fn estimate_with_error_correction_level() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("-l")
+        .arg("h")
+        .arg("QR code!")
+        .assert()
+        .success()
+        .stdout(predicate::eq(
+            "Version: 2\nType: normal\nCodewords: 10/16\n",
+        ));
+}
+
+#[test]
+fn estimate_with_mode() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("--mode")
+        .arg("numeric")
+        .arg("0123456789")
+        .assert()
+        .success()
+        .stdout(predicate::eq("Version: 1\nType: normal\nCodewords: 6/16\n"));
+}
+
+#[test]
+fn estimate_with_variant() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("--variant")
+        .arg("auto")
+        .arg("--mode")
+        .arg("byte")
+        .arg("-l")
+        .arg("l")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq("Version: 3\nType: micro\nCodewords: 8/11\n"));
+}
+
+#[test]
+fn estimate_data_too_long() {
+    utils::command::command()
+        .arg("estimate")
+        .write_stdin([0; 3000])
+        .assert()
+        .success()
+        .stdout(predicate::eq("Version: none\n"));
+    utils::command::command()
+        .arg("estimate")
+        .arg("--variant")
+        .arg("micro")
+        .arg("-l")
+        .arg("h")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq("Version: none\n"));
+}
+
+#[test]
+fn estimate_as_json() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("--format")
+        .arg("json")
+        .arg("QR code")
+        .assert()
+        .success()
+        .stdout(predicate::eq(
+            r#"{"fits":true,"variant":"normal","version":1,"level":"M","codewords":{"used":9,"total":16}}
+"#,
+        ));
+    utils::command::command()
+        .arg("estimate")
+        .arg("--format")
+        .arg("json")
+        .write_stdin([0; 3000])
+        .assert()
+        .success()
+        .stdout(predicate::eq(
+            r#"{"fits":false,"level":"M"}
+"#,
+        ));
+}
+
+#[test]
+fn estimate_with_invalid_kanji() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("--mode")
+        .arg("kanji")
+        .arg("QR code")
+        .assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("could not estimate the version"));
+}
+
+#[test]
+fn after_long_help_for_estimate_command() {
+    utils::command::command()
+        .arg("estimate")
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(include_str!(
+            "assets/estimate-after-long-help.md"
+        )));
+}
@@ -91,6 +91,37 @@ fn generate_completion_with_invalid_shell() {
         ));
 }
 
+#[test]
+fn print_exit_codes() {
+    utils::command::command()
+        .arg("--print-exit-codes")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "0 Successful program execution.\n1 An error occurred.\n",
+        ))
+        .stdout(predicate::str::contains(
+            "65 The input data was incorrect in some way.\n",
+        ))
+        .stdout(predicate::str::contains("69 A service is unavailable.\n"))
+        .stdout(predicate::str::ends_with(
+            "78 Something was found in an unconfigured or misconfigured state.\n",
+        ));
+}
+
+#[test]
+fn print_exit_codes_conflicts_with_subcommands() {
+    utils::command::command()
+        .arg("--print-exit-codes")
+        .arg("encode")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "the subcommand 'encode' cannot be used with '--print-exit-codes'",
+        ));
+}
+
 #[test]
 fn long_version() {
     utils::command::command()